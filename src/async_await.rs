@@ -2,114 +2,1092 @@ pub use ::futures_util::{TryStreamExt, pin_mut};
 
 #[macro_export]
 macro_rules! impl_sql {
-    ( $sql_name:ident = $( { $kind:tt $name:ident ($($variant:tt $param:ident $ptype:tt)*) $doc:literal $s:tt $( $text:tt )+ } ),+ ) => {
+    ( #[options($($option:ident),* $(,)?)] $sql_name:ident = $($body:tt)+ ) => {
+        $crate::check_options!{ $($option)* }
+        $crate::with_option!{ statement_error [$($option)*] {
+            $crate::impl_sql!{ @app_errors ($crate::StatementError) [$($option)*] $sql_name = $($body)+ }
+        } else {
+            $crate::impl_sql!{ @app_errors (::tokio_postgres::Error) [$($option)*] $sql_name = $($body)+ }
+        } }
+    };
+    ( @app_errors ($err:ty) [$($option:ident)*] $sql_name:ident = $($body:tt)+ ) => {
+        $crate::with_option!{ app_errors [$($option)*] {
+            $crate::impl_sql!{ @hooks ($err, yes) [$($option)*] $sql_name = $($body)+ }
+        } else {
+            $crate::impl_sql!{ @hooks ($err, no) [$($option)*] $sql_name = $($body)+ }
+        } }
+    };
+    ( @hooks ($err:ty, $app:ident) [$($option:ident)*] $sql_name:ident = $($body:tt)+ ) => {
+        $crate::with_option!{ interceptor [$($option)*] {
+            $crate::with_option!{ statement_error [$($option)*] {} else {
+                ::std::compile_error!("the `interceptor` option requires the `statement_error` option as calls that the interceptor rejects fail with `CheckFailure::Rejected`");
+            } }
+            $crate::impl_sql!{ @impl ($err, $app, yes) [$($option)*] $sql_name = $($body)+ }
+        } else {
+            $crate::impl_sql!{ @impl ($err, $app, no) [$($option)*] $sql_name = $($body)+ }
+        } }
+    };
+    ( @impl ($err:ty, $app:ident, $hooks:ident) [$($option:ident)*] $sql_name:ident = $( { $kind:tt $name:ident ($($variant:tt $param:ident $ptype:tt)*) $doc:literal $s:tt $( $text:tt )+ } ),+ ) => {
         trait $sql_name {
-            $( $crate::decl_method!{ $kind $name $doc () () () $($param $variant $ptype)* } )+
+            $( $crate::decl_method!{ ($err, $app) $kind $name $doc (Self: $crate::util::Within<{ $crate::within_transaction!(async $kind $doc) }>) () () () $($param $variant $ptype)* } )+
+            $crate::with_option!{ fixtures [$($option)*] {
+                $crate::fixtures_method!{ ($err) decl $sql_name $( $kind $name $doc ($($variant $param)*) )+ }
+            } }
+        }
+        const _: () = {
+            // Implementations of the statements, which, unlike the methods of the generated trait, can be called
+            // on any client. Wrappers delegate to them.
+            trait Statements {
+                $( $crate::decl_method!{ ($err, yes) $kind $name $doc () () () () $($param $variant $ptype)* } )+
+            }
+            impl Statements for ::tokio_postgres::Client {
+                $( $crate::impl_method!{ ($err, $hooks) $sql_name $kind $name $doc () () () ($($param $variant $ptype)*) => ($($variant $param)*) $($text)+ } )+
+            }
+            impl Statements for ::tokio_postgres::Transaction<'_> {
+                $( $crate::impl_method!{ ($err, $hooks) $sql_name $kind $name $doc () () () ($($param $variant $ptype)*) => ($($variant $param)*) $($text)+ } )+
+            }
+            impl $sql_name for ::tokio_postgres::Client {
+                $( $crate::forward_method!{ ($err, $app) this $sql_name $kind $name $doc ($($param)*) () () () $($param $variant $ptype)* } )+
+                $crate::with_option!{ fixtures [$($option)*] {
+                    $crate::fixtures_method!{ ($err) this $sql_name $( $kind $name $doc ($($variant $param)*) )+ }
+                } }
+            }
+            impl $sql_name for ::tokio_postgres::Transaction<'_> {
+                $( $crate::forward_method!{ ($err, $app) this $sql_name $kind $name $doc ($($param)*) () () () $($param $variant $ptype)* } )+
+                $crate::with_option!{ fixtures [$($option)*] {
+                    $crate::fixtures_method!{ ($err) this $sql_name $( $kind $name $doc ($($variant $param)*) )+ }
+                } }
+            }
+            $crate::with_option!{ router [$($option)*] {
+                impl $sql_name for $crate::Router<::tokio_postgres::Client> {
+                    $( $crate::forward_method!{ ($err, $app) route $sql_name $kind $name $doc ($($param)*) () () () $($param $variant $ptype)* } )+
+                    $crate::with_option!{ fixtures [$($option)*] {
+                        $crate::fixtures_method!{ ($err) route $sql_name $( $kind $name $doc ($($variant $param)*) )+ }
+                    } }
+                }
+            } }
+            $crate::with_option!{ reconnect [$($option)*] {
+                impl $sql_name for $crate::async_await::ReconnectingClient {
+                    $( $crate::reconnect_method!{ ($err, $app) $sql_name $kind $name $doc () () () () $($param $variant $ptype)* } )+
+                    $crate::with_option!{ fixtures [$($option)*] {
+                        $crate::fixtures_method!{ ($err) reconnect $sql_name $( $kind $name $doc ($($variant $param)*) )+ }
+                    } }
+                }
+            } }
+            $crate::replay_sql!{ impl ($err, $app) [$($option)*] $sql_name = $( { $kind $name ($($variant $param $ptype)*) $doc } ),+ }
+        };
+        $crate::mock_sql!{ ($err, $app) [$($option)*] $sql_name = $( { $kind $name ($($variant $param $ptype)*) $doc } ),+ }
+        $crate::replay_sql!{ struct $sql_name }
+        $crate::with_option!{ statement_error [$($option)*] {
+            $( $crate::check_statement!{ (yes) $kind $name $doc } )+
+        } else {
+            $( $crate::check_statement!{ (no) $kind $name $doc } )+
+        } }
+        $( $crate::check_statement!{ @fixture $kind $name ($($param)*) $doc } )+
+        $( $crate::check_statement!{ @defaults $name $doc ($($variant $param $ptype)*) } )+
+        $crate::with_option!{ fixtures [$($option)*] {} else {
+            $( $crate::check_statement!{ @no_fixtures $name $doc } )+
+        } }
+    };
+    ( $sql_name:ident = $($body:tt)+ ) => {
+        $crate::impl_sql!{ #[options()] $sql_name = $($body)+ }
+    };
+}
+
+/// Generates `Mock<SqlTraitName>` that implements the generated trait without a database.
+#[cfg(feature = "mock")]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! mock_sql {
+    ( ($err:ty, $app:ident) [$($option:ident)*] $sql_name:ident = $( { $kind:tt $name:ident ($($variant:tt $param:ident $ptype:tt)*) $doc:literal } ),+ ) => {
+        $crate::util::paste::paste! {
+            #[allow(dead_code)]
+            struct [<Mock $sql_name>] {
+                mock: $crate::mock::Mock,
+            }
+            #[allow(dead_code)]
+            impl [<Mock $sql_name>] {
+                fn new() -> Self {
+                    Self { mock: $crate::mock::Mock::new(stringify!($sql_name)) }
+                }
+                $(
+                    fn [<expect_ $name>](&mut self) -> &mut $crate::Expectation {
+                        self.mock.expect(stringify!($name))
+                    }
+                )+
+                fn calls(&self) -> ::std::vec::Vec<$crate::MockCall> {
+                    self.mock.calls()
+                }
+                fn checkpoint(&mut self) {
+                    self.mock.checkpoint()
+                }
+            }
+            impl $sql_name for [<Mock $sql_name>] {
+                $( $crate::mock_method!{ ($err, $app) $sql_name $kind $name $doc ($($param)*) () () () $($param $variant $ptype)* } )+
+                $crate::with_option!{ fixtures [$($option)*] {
+                    $crate::fixtures_method!{ ($err) mock $sql_name $( $kind $name $doc ($($variant $param)*) )+ }
+                } }
+            }
+            // The mock stands in for a transaction as well
+            impl $crate::util::Within<true> for [<Mock $sql_name>] {}
+        }
+    };
+}
+
+#[cfg(not(feature = "mock"))]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! mock_sql {
+    ( $($tt:tt)* ) => {};
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! fixtures_method {
+    ( ($err:ty) decl $sql_name:ident $( $kind:tt $name:ident $doc:literal ($($variant:tt $param:ident)*) )+ ) => {
+        /// Applies the named fixtures, i.e. statements annotated with `fixture:`, and the fixtures they depend on.
+        #[allow(dead_code)]
+        fn apply_fixtures<'tr, 'st>(&'st self, fixtures: &[&str])
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<(),$err>> + Send + 'tr>>
+        where Self: Sync + 'tr, 'st: 'tr, Self: $crate::util::Within<{ $crate::util::fixtures_require_transaction(&[ $($doc),+ ], true) }>;
+    };
+    ( ($err:ty) $via:ident $sql_name:ident $( $kind:tt $name:ident $doc:literal ($($variant:tt $param:ident)*) )+ ) => {
+        fn apply_fixtures<'tr, 'st>(&'st self, fixtures: &[&str])
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<(),$err>> + Send + 'tr>>
+        where Self: Sync + 'tr, 'st: 'tr
+        {
+            let statements = [ $( (::std::stringify!($name), $doc) ),+ ];
+            let order = $crate::util::fixture_order(::std::stringify!($sql_name), &statements, fixtures);
+            ::std::boxed::Box::pin(async move {
+                for stmt in order {
+                    $( if stmt == ::std::stringify!($name) { $crate::apply_fixture!{ ($err) $via self $sql_name $kind $name $doc ($($variant $param)*) } } )+
+                }
+                Ok(())
+            })
+        }
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! apply_fixture {
+    ( ($err:ty) $via:ident $self:ident $sql_name:ident ! $name:ident $doc:literal () ) => {
+        $crate::forward_call!(($err) $via $self $sql_name false $doc $name ()).await?;
+    };
+    ( ($err:ty) $via:ident $self:ident $sql_name:ident $kind:tt $name:ident $doc:literal ($($params:tt)*) ) => {
+        ::std::unreachable!("only `!` statements without parameters can be parts of fixtures")
+    };
+}
+
+/// Calls the implementation of the statement for the client that executes it.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! forward_call {
+    ( ($err:ty) this $self:ident $sql_name:ident $reads:tt $doc:literal $name:ident ($($arg:ident)*) ) => {
+        <Self as Statements>::$name($self $(, $arg)*)
+    };
+    ( ($err:ty) route $self:ident $sql_name:ident $reads:tt $doc:literal $name:ident ($($arg:ident)*) ) => {
+        <::tokio_postgres::Client as Statements>::$name($self.route($crate::annotations!($name $doc).use_replica($reads)) $(, $arg)*)
+    };
+    ( ($err:ty) reconnect $self:ident $sql_name:ident $reads:tt $doc:literal $name:ident ($($arg:ident)*) ) => {
+        <::tokio_postgres::Client as Statements>::$name(
+            &*$self.connected().await.map_err($crate::util::connect_error::<$err>(::std::stringify!($sql_name), ::std::stringify!($name)))?
+            $(, $arg)*
+        )
+    };
+    ( ($err:ty) mock $self:ident $sql_name:ident $reads:tt $doc:literal $name:ident ($($arg:ident)*) ) => {
+        <Self as $sql_name>::$name($self $(, $arg)*)
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! decl_method {
+    ( ($err:ty, no) ? $name:ident $doc:literal ($($bound:tt)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        #[doc=$doc]
+        fn $name<'tr, 'st $(, $lt)* $($gen_type)*, F>(&'st self $($fn_params)* , row_cb: F)
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<(),$err>> + Send + 'tr>>
+        where
+            F: FnMut(::tokio_postgres::Row) -> ::std::result::Result<(),$err>,
+            F: Send, F: 'tr, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*, $($bound)*;
+    };
+    ( ($err:ty, yes) ? $name:ident $doc:literal ($($bound:tt)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        #[doc=$doc]
+        fn $name<'tr, 'st $(, $lt)* $($gen_type)*, F, E>(&'st self $($fn_params)* , row_cb: F)
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<(),E>> + Send + 'tr>>
+        where
+            F: FnMut(::tokio_postgres::Row) -> ::std::result::Result<(),E>, E: ::std::convert::From<$err>, E: Send,
+            F: Send, F: 'tr, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*, $($bound)*;
+    };
+    ( ($err:ty, $app:ident) ^ $name:ident $doc:literal ($($bound:tt)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        #[doc=$doc]
+        fn $name<'tr, 'st $(, $lt)* $($gen_type)*>(&'st self $($fn_params)*)
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<$crate::RowStream,$err>> + Send + 'tr>>
+        where Self: 'tr, 'st: 'tr $(, $lt : 'tr)*, $($bound)*;
+    };
+    ( ($err:ty, no) % $name:ident $doc:literal ($($bound:tt)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        #[doc=$doc]
+        fn $name<'tr, 'st $(, $lt)* $($gen_type : ::tokio_postgres::types::ToSql)*, R>(&'st self $($fn_params)*) 
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::std::vec::Vec<R>,$err>> + Send + 'tr>>
+        where R: Send, R: ::std::convert::TryFrom<::tokio_postgres::Row>, $err: ::std::convert::From<R::Error>, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*, $($bound)*;
+    };
+    ( ($err:ty, yes) % $name:ident $doc:literal ($($bound:tt)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        #[doc=$doc]
+        fn $name<'tr, 'st $(, $lt)* $($gen_type : ::tokio_postgres::types::ToSql)*, R, E>(&'st self $($fn_params)*) 
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::std::vec::Vec<R>,E>> + Send + 'tr>>
+        where R: Send, R: ::std::convert::TryFrom<::tokio_postgres::Row>, E: ::std::convert::From<R::Error> + ::std::convert::From<$err>, E: Send, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*, $($bound)*;
+    };
+    ( ($err:ty, $app:ident) ! $name:ident $doc:literal ($($bound:tt)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        #[doc=$doc]
+        fn $name<'tr, 'st $(, $lt)* $($gen_type)*>(&'st self $($fn_params)*)
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<u64,$err>> + Send + 'tr>>
+        where Self: 'tr, 'st: 'tr $(, $lt : 'tr)*, $($bound)*;
+    };
+    ( ($err:ty, $app:ident) -> $name:ident $doc:literal ($($bound:tt)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        #[doc=$doc]
+        fn $name<'tr, 'st $(, $lt)* $($gen_type)*>(&'st self $($fn_params)*)
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::tokio_postgres::Row,$err>> + Send + 'tr>>
+        where Self: 'tr, 'st: 'tr $(, $lt : 'tr)*, $($bound)*;
+    };
+    ( ($err:ty, $app:ident) $kind:tt $name:ident $doc:literal ($($bound:tt)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident : _ $($tail:tt)* ) => {
+        $crate::decl_method!{
+            ($err, $app)
+            $kind
+            $name
+            $doc
+            ($($bound)*)
+            ($($lt)*)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : impl ::tokio_postgres::types::ToSql + Sync + Send + 'tr)
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $kind:tt $name:ident $doc:literal ($($bound:tt)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident : ($plt:lifetime & $ptype:ty) $($tail:tt)* ) => {
+        $crate::decl_method!{
+            ($err, $app)
+            $kind
+            $name
+            $doc
+            ($($bound)*)
+            ($($lt)* $plt)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : & $plt $ptype)
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $kind:tt $name:ident $doc:literal ($($bound:tt)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident : ($ptype:ty) $($tail:tt)* ) => {
+        $crate::decl_method!{
+            ($err, $app)
+            $kind
+            $name
+            $doc
+            ($($bound)*)
+            ($($lt)*)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : $crate::arg_type!($doc $param $ptype))
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $kind:tt $name:ident $doc:literal ($($bound:tt)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident # [$alt:lifetime $gtype:ident] $($tail:tt)* ) => {
+        $crate::decl_method!{
+            ($err, $app)
+            $kind
+            $name
+            $doc
+            ($($bound)*)
+            ($($lt)* $alt)
+            ($($gen_type)* , $gtype : ::tokio_postgres::types::ToSql + Sync + Send + 'tr)
+            ($($fn_params)* , $param : & $alt [ $gtype ] )
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $kind:tt $name:ident $doc:literal ($($bound:tt)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident # ($alt:lifetime $plt:lifetime & $ptype:ty) $($tail:tt)* ) => {
+        $crate::decl_method!{
+            ($err, $app)
+            $kind
+            $name
+            $doc
+            ($($bound)*)
+            ($($lt)* $alt $plt)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : & $alt [ & $plt $ptype ] )
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $kind:tt $name:ident $doc:literal ($($bound:tt)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident # ($alt:lifetime $ptype:ty) $($tail:tt)* ) => {
+        $crate::decl_method!{
+            ($err, $app)
+            $kind
+            $name
+            $doc
+            ($($bound)*)
+            ($($lt)* $alt)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : & $alt [ $ptype ] )
+            $($tail)*
+        }
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! forward_method {
+    ( ($err:ty, no) $via:ident $sql_name:ident ? $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        fn $name<'tr, 'st $(, $lt)* $($gen_type)*, F>(&'st self $($fn_params)* , row_cb: F)
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<(),$err>> + Send + 'tr>>
+        where
+            F: FnMut(::tokio_postgres::Row) -> ::std::result::Result<(),$err>,
+            F: Send, F: 'tr, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+        {
+            $crate::forward_call!(($err) $via self $sql_name true $doc $name ($($arg)* row_cb))
+        }
+    };
+    ( ($err:ty, yes) $via:ident $sql_name:ident ? $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        fn $name<'tr, 'st $(, $lt)* $($gen_type)*, F, E>(&'st self $($fn_params)* , row_cb: F)
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<(),E>> + Send + 'tr>>
+        where
+            F: FnMut(::tokio_postgres::Row) -> ::std::result::Result<(),E>, E: ::std::convert::From<$err>, E: Send,
+            F: Send, F: 'tr, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+        {
+            $crate::forward_call!(($err) $via self $sql_name true $doc $name ($($arg)* row_cb))
+        }
+    };
+    ( ($err:ty, $app:ident) $via:ident $sql_name:ident ^ $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        fn $name<'tr, 'st $(, $lt)* $($gen_type)*>(&'st self $($fn_params)*)
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<$crate::RowStream,$err>> + Send + 'tr>>
+        where Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+        {
+            $crate::forward_call!(($err) $via self $sql_name true $doc $name ($($arg)*))
+        }
+    };
+    ( ($err:ty, no) $via:ident $sql_name:ident % $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        fn $name<'tr, 'st $(, $lt)* $($gen_type : ::tokio_postgres::types::ToSql)*, R>(&'st self $($fn_params)*) 
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::std::vec::Vec<R>,$err>> + Send + 'tr>>
+        where R: Send, R: ::std::convert::TryFrom<::tokio_postgres::Row>, $err: ::std::convert::From<R::Error>, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+        {
+            $crate::forward_call!(($err) $via self $sql_name true $doc $name ($($arg)*))
+        }
+    };
+    ( ($err:ty, yes) $via:ident $sql_name:ident % $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        fn $name<'tr, 'st $(, $lt)* $($gen_type : ::tokio_postgres::types::ToSql)*, R, E>(&'st self $($fn_params)*) 
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::std::vec::Vec<R>,E>> + Send + 'tr>>
+        where R: Send, R: ::std::convert::TryFrom<::tokio_postgres::Row>, E: ::std::convert::From<R::Error> + ::std::convert::From<$err>, E: Send, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+        {
+            $crate::forward_call!(($err) $via self $sql_name true $doc $name ($($arg)*))
+        }
+    };
+    ( ($err:ty, $app:ident) $via:ident $sql_name:ident ! $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        fn $name<'tr, 'st $(, $lt)* $($gen_type)*>(&'st self $($fn_params)*)
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<u64,$err>> + Send + 'tr>>
+        where Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+        {
+            $crate::forward_call!(($err) $via self $sql_name false $doc $name ($($arg)*))
+        }
+    };
+    ( ($err:ty, $app:ident) $via:ident $sql_name:ident -> $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        fn $name<'tr, 'st $(, $lt)* $($gen_type)*>(&'st self $($fn_params)*)
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::tokio_postgres::Row,$err>> + Send + 'tr>>
+        where Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+        {
+            $crate::forward_call!(($err) $via self $sql_name false $doc $name ($($arg)*))
+        }
+    };
+    ( ($err:ty, $app:ident) $via:ident $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident : _ $($tail:tt)* ) => {
+        $crate::forward_method!{
+            ($err, $app)
+            $via
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)*)
+            ($($lt)*)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : impl ::tokio_postgres::types::ToSql + Sync + Send + 'tr)
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $via:ident $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident : ($plt:lifetime & $ptype:ty) $($tail:tt)* ) => {
+        $crate::forward_method!{
+            ($err, $app)
+            $via
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)*)
+            ($($lt)* $plt)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : & $plt $ptype)
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $via:ident $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident : ($ptype:ty) $($tail:tt)* ) => {
+        $crate::forward_method!{
+            ($err, $app)
+            $via
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)*)
+            ($($lt)*)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : $crate::arg_type!($doc $param $ptype))
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $via:ident $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident # [$alt:lifetime $gtype:ident] $($tail:tt)* ) => {
+        $crate::forward_method!{
+            ($err, $app)
+            $via
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)*)
+            ($($lt)* $alt)
+            ($($gen_type)* , $gtype : ::tokio_postgres::types::ToSql + Sync + Send + 'tr)
+            ($($fn_params)* , $param : & $alt [ $gtype ] )
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $via:ident $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident # ($alt:lifetime $plt:lifetime & $ptype:ty) $($tail:tt)* ) => {
+        $crate::forward_method!{
+            ($err, $app)
+            $via
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)*)
+            ($($lt)* $alt $plt)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : & $alt [ & $plt $ptype ] )
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $via:ident $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident # ($alt:lifetime $ptype:ty) $($tail:tt)* ) => {
+        $crate::forward_method!{
+            ($err, $app)
+            $via
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)*)
+            ($($lt)* $alt)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : & $alt [ $ptype ] )
+            $($tail)*
+        }
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! mock_method {
+    ( ($err:ty, no) $sql_name:ident ? $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        fn $name<'tr, 'st $(, $lt)* $($gen_type)*, F>(&'st self $($fn_params)* , row_cb: F)
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<(),$err>> + Send + 'tr>>
+        where
+            F: FnMut(::tokio_postgres::Row) -> ::std::result::Result<(),$err>,
+            F: Send, F: 'tr, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+        {
+            let rows = self.mock.call(stringify!($name), $crate::annotations!($name $doc), &[$((stringify!($arg), &$arg as $crate::Param)),*]).fetch::<$err>();
+            ::std::boxed::Box::pin(async move { $crate::canned::process(rows, row_cb) })
+        }
+    };
+    ( ($err:ty, yes) $sql_name:ident ? $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        fn $name<'tr, 'st $(, $lt)* $($gen_type)*, F, E>(&'st self $($fn_params)* , row_cb: F)
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<(),E>> + Send + 'tr>>
+        where
+            F: FnMut(::tokio_postgres::Row) -> ::std::result::Result<(),E>, E: ::std::convert::From<$err>, E: Send,
+            F: Send, F: 'tr, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+        {
+            let rows = self.mock.call(stringify!($name), $crate::annotations!($name $doc), &[$((stringify!($arg), &$arg as $crate::Param)),*]).fetch::<$err>();
+            ::std::boxed::Box::pin(async move { $crate::canned::process(rows, row_cb) })
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident ^ $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        fn $name<'tr, 'st $(, $lt)* $($gen_type)*>(&'st self $($fn_params)*)
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<$crate::RowStream,$err>> + Send + 'tr>>
+        where Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+        {
+            let res = self.mock.call(stringify!($name), $crate::annotations!($name $doc), &[$((stringify!($arg), &$arg as $crate::Param)),*]).rows();
+            ::std::boxed::Box::pin(async move { res })
+        }
+    };
+    ( ($err:ty, no) $sql_name:ident % $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        fn $name<'tr, 'st $(, $lt)* $($gen_type : ::tokio_postgres::types::ToSql)*, R>(&'st self $($fn_params)*) 
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::std::vec::Vec<R>,$err>> + Send + 'tr>>
+        where R: Send, R: ::std::convert::TryFrom<::tokio_postgres::Row>, $err: ::std::convert::From<R::Error>, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+        {
+            let rows = self.mock.call(stringify!($name), $crate::annotations!($name $doc), &[$((stringify!($arg), &$arg as $crate::Param)),*]).fetch::<$err>();
+            ::std::boxed::Box::pin(async move { $crate::canned::collect(rows) })
+        }
+    };
+    ( ($err:ty, yes) $sql_name:ident % $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        fn $name<'tr, 'st $(, $lt)* $($gen_type : ::tokio_postgres::types::ToSql)*, R, E>(&'st self $($fn_params)*) 
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::std::vec::Vec<R>,E>> + Send + 'tr>>
+        where R: Send, R: ::std::convert::TryFrom<::tokio_postgres::Row>, E: ::std::convert::From<R::Error> + ::std::convert::From<$err>, E: Send, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+        {
+            let rows = self.mock.call(stringify!($name), $crate::annotations!($name $doc), &[$((stringify!($arg), &$arg as $crate::Param)),*]).fetch::<$err>();
+            ::std::boxed::Box::pin(async move { $crate::canned::collect(rows) })
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident ! $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        fn $name<'tr, 'st $(, $lt)* $($gen_type)*>(&'st self $($fn_params)*)
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<u64,$err>> + Send + 'tr>>
+        where Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+        {
+            let res = self.mock.call(stringify!($name), $crate::annotations!($name $doc), &[$((stringify!($arg), &$arg as $crate::Param)),*]).rows_affected();
+            ::std::boxed::Box::pin(async move { res })
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident -> $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        fn $name<'tr, 'st $(, $lt)* $($gen_type)*>(&'st self $($fn_params)*)
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::tokio_postgres::Row,$err>> + Send + 'tr>>
+        where Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+        {
+            let res = self.mock.call(stringify!($name), $crate::annotations!($name $doc), &[$((stringify!($arg), &$arg as $crate::Param)),*]).row();
+            ::std::boxed::Box::pin(async move { res })
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident : _ $($tail:tt)* ) => {
+        $crate::mock_method!{
+            ($err, $app)
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)*)
+            ($($lt)*)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : impl ::tokio_postgres::types::ToSql + Sync + Send + 'tr)
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident : ($plt:lifetime & $ptype:ty) $($tail:tt)* ) => {
+        $crate::mock_method!{
+            ($err, $app)
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)*)
+            ($($lt)* $plt)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : & $plt $ptype)
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident : ($ptype:ty) $($tail:tt)* ) => {
+        $crate::mock_method!{
+            ($err, $app)
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)*)
+            ($($lt)*)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : $crate::arg_type!($doc $param $ptype))
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident # [$alt:lifetime $gtype:ident] $($tail:tt)* ) => {
+        $crate::mock_method!{
+            ($err, $app)
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)*)
+            ($($lt)* $alt)
+            ($($gen_type)* , $gtype : ::tokio_postgres::types::ToSql + Sync + Send + 'tr)
+            ($($fn_params)* , $param : & $alt [ $gtype ] )
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident # ($alt:lifetime $plt:lifetime & $ptype:ty) $($tail:tt)* ) => {
+        $crate::mock_method!{
+            ($err, $app)
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)*)
+            ($($lt)* $alt $plt)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : & $alt [ & $plt $ptype ] )
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident # ($alt:lifetime $ptype:ty) $($tail:tt)* ) => {
+        $crate::mock_method!{
+            ($err, $app)
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)*)
+            ($($lt)* $alt)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : & $alt [ $ptype ] )
+            $($tail)*
+        }
+    };
+}
+
+/// Generates `Replay<SqlTraitName>` that implements the generated trait by recording results of the calls
+/// that the database executes, or by answering the calls with the recorded results.
+#[cfg(feature = "replay")]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! replay_sql {
+    ( struct $sql_name:ident ) => {
+        $crate::util::paste::paste! {
+            #[allow(dead_code)]
+            struct [<Replay $sql_name>] {
+                replay: $crate::replay::Replay<::tokio_postgres::Client>,
+            }
+            #[allow(dead_code)]
+            impl [<Replay $sql_name>] {
+                async fn start<F, Fut, E>(path: impl ::std::convert::AsRef<::std::path::Path>, connect: F) -> ::std::result::Result<Self, ::std::boxed::Box<dyn ::std::error::Error>>
+                where
+                    F: FnOnce() -> Fut,
+                    Fut: ::std::future::Future<Output = ::std::result::Result<::tokio_postgres::Client, E>>,
+                    E: ::std::convert::Into<::std::boxed::Box<dyn ::std::error::Error>>
+                {
+                    let path = path.as_ref();
+                    if $crate::replay::is_recorded(path) {
+                        Self::play(path).map_err(::std::convert::Into::into)
+                    } else {
+                        Ok(Self::record(path, connect().await.map_err(::std::convert::Into::into)?))
+                    }
+                }
+                fn record(path: impl ::std::convert::AsRef<::std::path::Path>, client: ::tokio_postgres::Client) -> Self {
+                    Self { replay: $crate::replay::Replay::record(stringify!($sql_name), path, client) }
+                }
+                fn play(path: impl ::std::convert::AsRef<::std::path::Path>) -> ::std::io::Result<Self> {
+                    $crate::replay::Replay::play(stringify!($sql_name), path).map(|replay| Self { replay })
+                }
+                fn is_recording(&self) -> bool {
+                    self.replay.is_recording()
+                }
+            }
+            // The recorded calls might have been executed in a transaction
+            impl $crate::util::Within<true> for [<Replay $sql_name>] {}
+        }
+    };
+    ( impl ($err:ty, $app:ident) [$($option:ident)*] $sql_name:ident = $( { $kind:tt $name:ident ($($variant:tt $param:ident $ptype:tt)*) $doc:literal } ),+ ) => {
+        $crate::util::paste::paste! {
+            impl $sql_name for [<Replay $sql_name>] {
+                $( $crate::replay_method!{ ($err, $app) $sql_name $kind $name $doc ($($param)*) () () () $($param $variant $ptype)* } )+
+                $crate::with_option!{ fixtures [$($option)*] {
+                    $crate::fixtures_method!{ ($err) mock $sql_name $( $kind $name $doc ($($variant $param)*) )+ }
+                } }
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "replay"))]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! replay_sql {
+    ( $($tt:tt)* ) => {};
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! replay_method {
+    ( ($err:ty, no) $sql_name:ident ? $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        fn $name<'tr, 'st $(, $lt)* $($gen_type)*, F>(&'st self $($fn_params)* , row_cb: F)
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<(),$err>> + Send + 'tr>>
+        where
+            F: FnMut(::tokio_postgres::Row) -> ::std::result::Result<(),$err>,
+            F: Send, F: 'tr, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+        {
+            if let Some((client, recorder)) = self.replay.recorder() {
+                let mut recording = $crate::replay::Recording::new(stringify!($name), &[$((stringify!($arg), &$arg as $crate::Param)),*]);
+                return ::std::boxed::Box::pin(async move {
+                    let mut row_cb = row_cb;
+                    let res = <::tokio_postgres::Client as Statements>::$name(client $(, $arg)*, |row| {
+                        recording.row(&row);
+                        row_cb(row).map_err($crate::replay::CallbackError::Callback)
+                    }).await;
+                    recorder.record_processed(recording, res)
+                });
+            }
+            let rows = self.replay.answer(stringify!($name), $crate::annotations!($name $doc), &[$((stringify!($arg), &$arg as $crate::Param)),*]).fetch::<$err>();
+            ::std::boxed::Box::pin(async move { $crate::canned::process(rows, row_cb) })
+        }
+    };
+    ( ($err:ty, yes) $sql_name:ident ? $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        fn $name<'tr, 'st $(, $lt)* $($gen_type)*, F, E>(&'st self $($fn_params)* , row_cb: F)
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<(),E>> + Send + 'tr>>
+        where
+            F: FnMut(::tokio_postgres::Row) -> ::std::result::Result<(),E>, E: ::std::convert::From<$err>, E: Send,
+            F: Send, F: 'tr, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+        {
+            if let Some((client, recorder)) = self.replay.recorder() {
+                let mut recording = $crate::replay::Recording::new(stringify!($name), &[$((stringify!($arg), &$arg as $crate::Param)),*]);
+                return ::std::boxed::Box::pin(async move {
+                    let mut row_cb = row_cb;
+                    let res = <::tokio_postgres::Client as Statements>::$name(client $(, $arg)*, |row| {
+                        recording.row(&row);
+                        row_cb(row).map_err($crate::replay::CallbackError::Callback)
+                    }).await;
+                    recorder.record_processed::<_, $err>(recording, res)
+                });
+            }
+            let rows = self.replay.answer(stringify!($name), $crate::annotations!($name $doc), &[$((stringify!($arg), &$arg as $crate::Param)),*]).fetch::<$err>();
+            ::std::boxed::Box::pin(async move { $crate::canned::process(rows, row_cb) })
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident ^ $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        fn $name<'tr, 'st $(, $lt)* $($gen_type)*>(&'st self $($fn_params)*)
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<$crate::RowStream,$err>> + Send + 'tr>>
+        where Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+        {
+            if let Some((client, recorder)) = self.replay.recorder() {
+                let recording = $crate::replay::Recording::new(stringify!($name), &[$((stringify!($arg), &$arg as $crate::Param)),*]);
+                return ::std::boxed::Box::pin(async move {
+                    let res = <::tokio_postgres::Client as Statements>::$name(client $(, $arg)*).await;
+                    recorder.record_rows(recording, $crate::annotations!($name $doc), res).await
+                });
+            }
+            let res = self.replay.answer(stringify!($name), $crate::annotations!($name $doc), &[$((stringify!($arg), &$arg as $crate::Param)),*]).rows();
+            ::std::boxed::Box::pin(async move { res })
+        }
+    };
+    ( ($err:ty, no) $sql_name:ident % $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        fn $name<'tr, 'st $(, $lt)* $($gen_type : ::tokio_postgres::types::ToSql)*, R>(&'st self $($fn_params)*) 
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::std::vec::Vec<R>,$err>> + Send + 'tr>>
+        where R: Send, R: ::std::convert::TryFrom<::tokio_postgres::Row>, $err: ::std::convert::From<R::Error>, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+        {
+            if let Some((client, recorder)) = self.replay.recorder() {
+                let recording = $crate::replay::Recording::new(stringify!($name), &[$((stringify!($arg), &$arg as $crate::Param)),*]);
+                return ::std::boxed::Box::pin(async move {
+                    let res : ::std::result::Result<::std::vec::Vec<$crate::replay::RawRow>, $err> = <::tokio_postgres::Client as Statements>::$name(client $(, $arg)*).await;
+                    $crate::canned::collect(recorder.record_collected(recording, res))
+                });
+            }
+            let rows = self.replay.answer(stringify!($name), $crate::annotations!($name $doc), &[$((stringify!($arg), &$arg as $crate::Param)),*]).fetch::<$err>();
+            ::std::boxed::Box::pin(async move { $crate::canned::collect(rows) })
+        }
+    };
+    ( ($err:ty, yes) $sql_name:ident % $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        fn $name<'tr, 'st $(, $lt)* $($gen_type : ::tokio_postgres::types::ToSql)*, R, E>(&'st self $($fn_params)*) 
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::std::vec::Vec<R>,E>> + Send + 'tr>>
+        where R: Send, R: ::std::convert::TryFrom<::tokio_postgres::Row>, E: ::std::convert::From<R::Error> + ::std::convert::From<$err>, E: Send, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+        {
+            if let Some((client, recorder)) = self.replay.recorder() {
+                let recording = $crate::replay::Recording::new(stringify!($name), &[$((stringify!($arg), &$arg as $crate::Param)),*]);
+                return ::std::boxed::Box::pin(async move {
+                    let res : ::std::result::Result<::std::vec::Vec<$crate::replay::RawRow>, $err> = <::tokio_postgres::Client as Statements>::$name(client $(, $arg)*).await;
+                    $crate::canned::collect(recorder.record_collected(recording, res))
+                });
+            }
+            let rows = self.replay.answer(stringify!($name), $crate::annotations!($name $doc), &[$((stringify!($arg), &$arg as $crate::Param)),*]).fetch::<$err>();
+            ::std::boxed::Box::pin(async move { $crate::canned::collect(rows) })
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident ! $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        fn $name<'tr, 'st $(, $lt)* $($gen_type)*>(&'st self $($fn_params)*)
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<u64,$err>> + Send + 'tr>>
+        where Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+        {
+            if let Some((client, recorder)) = self.replay.recorder() {
+                let recording = $crate::replay::Recording::new(stringify!($name), &[$((stringify!($arg), &$arg as $crate::Param)),*]);
+                return ::std::boxed::Box::pin(async move {
+                    let res = <::tokio_postgres::Client as Statements>::$name(client $(, $arg)*).await;
+                    recorder.record_affected(recording, res)
+                });
+            }
+            let res = self.replay.answer(stringify!($name), $crate::annotations!($name $doc), &[$((stringify!($arg), &$arg as $crate::Param)),*]).rows_affected();
+            ::std::boxed::Box::pin(async move { res })
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident -> $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        fn $name<'tr, 'st $(, $lt)* $($gen_type)*>(&'st self $($fn_params)*)
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::tokio_postgres::Row,$err>> + Send + 'tr>>
+        where Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+        {
+            if let Some((client, recorder)) = self.replay.recorder() {
+                let recording = $crate::replay::Recording::new(stringify!($name), &[$((stringify!($arg), &$arg as $crate::Param)),*]);
+                return ::std::boxed::Box::pin(async move {
+                    let res = <::tokio_postgres::Client as Statements>::$name(client $(, $arg)*).await;
+                    recorder.record_row(recording, res)
+                });
+            }
+            let res = self.replay.answer(stringify!($name), $crate::annotations!($name $doc), &[$((stringify!($arg), &$arg as $crate::Param)),*]).row();
+            ::std::boxed::Box::pin(async move { res })
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident : _ $($tail:tt)* ) => {
+        $crate::replay_method!{
+            ($err, $app)
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)*)
+            ($($lt)*)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : impl ::tokio_postgres::types::ToSql + Sync + Send + 'tr)
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident : ($plt:lifetime & $ptype:ty) $($tail:tt)* ) => {
+        $crate::replay_method!{
+            ($err, $app)
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)*)
+            ($($lt)* $plt)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : & $plt $ptype)
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident : ($ptype:ty) $($tail:tt)* ) => {
+        $crate::replay_method!{
+            ($err, $app)
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)*)
+            ($($lt)*)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : $crate::arg_type!($doc $param $ptype))
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident # [$alt:lifetime $gtype:ident] $($tail:tt)* ) => {
+        $crate::replay_method!{
+            ($err, $app)
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)*)
+            ($($lt)* $alt)
+            ($($gen_type)* , $gtype : ::tokio_postgres::types::ToSql + Sync + Send + 'tr)
+            ($($fn_params)* , $param : & $alt [ $gtype ] )
+            $($tail)*
         }
-        impl $sql_name for ::tokio_postgres::Client {
-            $( $crate::impl_method!{ $kind $name () () () ($($param $variant $ptype)*) => ($($variant $param)*) $($text)+ } )+
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident # ($alt:lifetime $plt:lifetime & $ptype:ty) $($tail:tt)* ) => {
+        $crate::replay_method!{
+            ($err, $app)
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)*)
+            ($($lt)* $alt $plt)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : & $alt [ & $plt $ptype ] )
+            $($tail)*
         }
-        impl $sql_name for ::tokio_postgres::Transaction<'_> {
-            $( $crate::impl_method!{ $kind $name () () () ($($param $variant $ptype)*) => ($($variant $param)*) $($text)+ } )+
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident # ($alt:lifetime $ptype:ty) $($tail:tt)* ) => {
+        $crate::replay_method!{
+            ($err, $app)
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)*)
+            ($($lt)* $alt)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : & $alt [ $ptype ] )
+            $($tail)*
         }
     };
 }
 
+
 #[macro_export]
 #[doc(hidden)]
-macro_rules! decl_method {
-    ( ? $name:ident $doc:literal ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
-        #[doc=$doc]
-        fn $name<'tr, 'st $(, $lt)* $($gen_type)*, F>(&'st self $($fn_params)* , row_cb: F)
-        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<(),::tokio_postgres::Error>> + Send + 'tr>>
+macro_rules! reconnect_method {
+    ( ($err:ty, no) $sql_name:ident ? $name:ident $doc:literal ($($arg:tt)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        fn $name<'tr, 'st $(, $lt)* $($gen_type)*, F>(&'st self $($fn_params)* , mut row_cb: F)
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<(),$err>> + Send + 'tr>>
         where
-            F: FnMut(::tokio_postgres::Row) -> ::std::result::Result<(),::tokio_postgres::Error>,
-            F: Send, F: 'tr, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*;
+            F: FnMut(::tokio_postgres::Row) -> ::std::result::Result<(),$err>,
+            F: Send, F: 'tr, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+        {
+            ::std::boxed::Box::pin(async move {
+                let delivered = ::std::sync::atomic::AtomicBool::new(false);
+                let mut row_cb = |row| { delivered.store(true, ::std::sync::atomic::Ordering::Relaxed); row_cb(row) };
+                $crate::reconnect_call!(($err) self client $sql_name $name (!delivered.load(::std::sync::atomic::Ordering::Relaxed) && $crate::annotations!($name $doc).idempotent(true)) =>
+                    <::tokio_postgres::Client as Statements>::$name(&*client $($arg)* , &mut row_cb).await
+                )
+            })
+        }
     };
-    ( ^ $name:ident $doc:literal ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
-        #[doc=$doc]
+    ( ($err:ty, yes) $sql_name:ident ? $name:ident $doc:literal ($($arg:tt)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        fn $name<'tr, 'st $(, $lt)* $($gen_type)*, F, E>(&'st self $($fn_params)* , mut row_cb: F)
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<(),E>> + Send + 'tr>>
+        where
+            F: FnMut(::tokio_postgres::Row) -> ::std::result::Result<(),E>, E: ::std::convert::From<$err>, E: Send,
+            F: Send, F: 'tr, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+        {
+            ::std::boxed::Box::pin(async move {
+                let delivered = ::std::sync::atomic::AtomicBool::new(false);
+                let mut row_cb = |row| { delivered.store(true, ::std::sync::atomic::Ordering::Relaxed); row_cb(row) };
+                // The call returns the error type chosen by the caller, thus a lost session is detected only by the closed connection.
+                $crate::reconnect_call!(($err) self client $sql_name $name (!delivered.load(::std::sync::atomic::Ordering::Relaxed) && $crate::annotations!($name $doc).idempotent(true), |_: &E| false) =>
+                    <::tokio_postgres::Client as Statements>::$name(&*client $($arg)* , &mut row_cb).await
+                )
+            })
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident ^ $name:ident $doc:literal ($($arg:tt)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
         fn $name<'tr, 'st $(, $lt)* $($gen_type)*>(&'st self $($fn_params)*)
-        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::tokio_postgres::RowStream,::tokio_postgres::Error>> + Send + 'tr>>
-        where Self: 'tr, 'st: 'tr $(, $lt : 'tr)*;
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<$crate::RowStream,$err>> + Send + 'tr>>
+        where Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+        {
+            ::std::boxed::Box::pin(async move {
+                $crate::reconnect_call!(($err) self client $sql_name $name ($crate::annotations!($name $doc).idempotent(true)) =>
+                    <::tokio_postgres::Client as Statements>::$name(&*client $($arg)*).await
+                )
+            })
+        }
     };
-    ( % $name:ident $doc:literal ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
-        #[doc=$doc]
+    ( ($err:ty, no) $sql_name:ident % $name:ident $doc:literal ($($arg:tt)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
         fn $name<'tr, 'st $(, $lt)* $($gen_type : ::tokio_postgres::types::ToSql)*, R>(&'st self $($fn_params)*) 
-        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::std::vec::Vec<R>,::tokio_postgres::Error>> + Send + 'tr>>
-        where R: Send, R: ::std::convert::TryFrom<::tokio_postgres::Row>, ::tokio_postgres::Error: ::std::convert::From<R::Error>, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*;
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::std::vec::Vec<R>,$err>> + Send + 'tr>>
+        where R: Send, R: ::std::convert::TryFrom<::tokio_postgres::Row>, $err: ::std::convert::From<R::Error>, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+        {
+            ::std::boxed::Box::pin(async move {
+                $crate::reconnect_call!(($err) self client $sql_name $name ($crate::annotations!($name $doc).idempotent(true)) =>
+                    <::tokio_postgres::Client as Statements>::$name(&*client $($arg)*).await
+                )
+            })
+        }
     };
-    ( ! $name:ident $doc:literal ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
-        #[doc=$doc]
+    ( ($err:ty, yes) $sql_name:ident % $name:ident $doc:literal ($($arg:tt)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
+        fn $name<'tr, 'st $(, $lt)* $($gen_type : ::tokio_postgres::types::ToSql)*, R, E>(&'st self $($fn_params)*) 
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::std::vec::Vec<R>,E>> + Send + 'tr>>
+        where R: Send, R: ::std::convert::TryFrom<::tokio_postgres::Row>, E: ::std::convert::From<R::Error> + ::std::convert::From<$err>, E: Send, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+        {
+            ::std::boxed::Box::pin(async move {
+                $crate::reconnect_call!(($err) self client $sql_name $name ($crate::annotations!($name $doc).idempotent(true), |_: &E| false) =>
+                    <::tokio_postgres::Client as Statements>::$name(&*client $($arg)*).await
+                )
+            })
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident ! $name:ident $doc:literal ($($arg:tt)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
         fn $name<'tr, 'st $(, $lt)* $($gen_type)*>(&'st self $($fn_params)*)
-        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<u64,::tokio_postgres::Error>> + Send + 'tr>>
-        where Self: 'tr, 'st: 'tr $(, $lt : 'tr)*;
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<u64,$err>> + Send + 'tr>>
+        where Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+        {
+            ::std::boxed::Box::pin(async move {
+                $crate::reconnect_call!(($err) self client $sql_name $name ($crate::annotations!($name $doc).idempotent(false)) =>
+                    <::tokio_postgres::Client as Statements>::$name(&*client $($arg)*).await
+                )
+            })
+        }
     };
-    ( -> $name:ident $doc:literal ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
-        #[doc=$doc]
+    ( ($err:ty, $app:ident) $sql_name:ident -> $name:ident $doc:literal ($($arg:tt)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ) => {
         fn $name<'tr, 'st $(, $lt)* $($gen_type)*>(&'st self $($fn_params)*)
-        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::tokio_postgres::Row,::tokio_postgres::Error>> + Send + 'tr>>
-        where Self: 'tr, 'st: 'tr $(, $lt : 'tr)*;
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::tokio_postgres::Row,$err>> + Send + 'tr>>
+        where Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+        {
+            ::std::boxed::Box::pin(async move {
+                $crate::reconnect_call!(($err) self client $sql_name $name ($crate::annotations!($name $doc).idempotent(false)) =>
+                    <::tokio_postgres::Client as Statements>::$name(&*client $($arg)*).await
+                )
+            })
+        }
     };
-    ( $kind:tt $name:ident $doc:literal ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident : _ $($tail:tt)* ) => {
-        $crate::decl_method!{
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:tt)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident : _ $($tail:tt)* ) => {
+        $crate::reconnect_method!{
+            ($err, $app)
+            $sql_name
             $kind
             $name
             $doc
+            ($($arg)* , & $param)
             ($($lt)*)
             ($($gen_type)*)
             ($($fn_params)* , $param : impl ::tokio_postgres::types::ToSql + Sync + Send + 'tr)
             $($tail)*
         }
     };
-    ( $kind:tt $name:ident $doc:literal ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident : ($plt:lifetime & $ptype:ty) $($tail:tt)* ) => {
-        $crate::decl_method!{
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:tt)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident : ($plt:lifetime & $ptype:ty) $($tail:tt)* ) => {
+        $crate::reconnect_method!{
+            ($err, $app)
+            $sql_name
             $kind
             $name
             $doc
+            ($($arg)* , $param)
             ($($lt)* $plt)
             ($($gen_type)*)
             ($($fn_params)* , $param : & $plt $ptype)
             $($tail)*
         }
     };
-    ( $kind:tt $name:ident $doc:literal ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident : ($ptype:ty) $($tail:tt)* ) => {
-        $crate::decl_method!{
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:tt)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident : ($ptype:ty) $($tail:tt)* ) => {
+        $crate::reconnect_method!{
+            ($err, $app)
+            $sql_name
             $kind
             $name
             $doc
+            ($($arg)* , ::std::clone::Clone::clone(& $param))
             ($($lt)*)
             ($($gen_type)*)
-            ($($fn_params)* , $param : $ptype)
+            ($($fn_params)* , $param : $crate::arg_type!($doc $param $ptype))
             $($tail)*
         }
     };
-    ( $kind:tt $name:ident $doc:literal ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident # [$alt:lifetime $gtype:ident] $($tail:tt)* ) => {
-        $crate::decl_method!{
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:tt)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident # [$alt:lifetime $gtype:ident] $($tail:tt)* ) => {
+        $crate::reconnect_method!{
+            ($err, $app)
+            $sql_name
             $kind
             $name
             $doc
+            ($($arg)* , $param)
             ($($lt)* $alt)
             ($($gen_type)* , $gtype : ::tokio_postgres::types::ToSql + Sync + Send + 'tr)
             ($($fn_params)* , $param : & $alt [ $gtype ] )
             $($tail)*
         }
     };
-    ( $kind:tt $name:ident $doc:literal ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident # ($alt:lifetime $plt:lifetime & $ptype:ty) $($tail:tt)* ) => {
-        $crate::decl_method!{
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:tt)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident # ($alt:lifetime $plt:lifetime & $ptype:ty) $($tail:tt)* ) => {
+        $crate::reconnect_method!{
+            ($err, $app)
+            $sql_name
             $kind
             $name
             $doc
+            ($($arg)* , $param)
             ($($lt)* $alt $plt)
             ($($gen_type)*)
             ($($fn_params)* , $param : & $alt [ & $plt $ptype ] )
             $($tail)*
         }
     };
-    ( $kind:tt $name:ident $doc:literal ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident # ($alt:lifetime $ptype:ty) $($tail:tt)* ) => {
-        $crate::decl_method!{
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:tt)*) ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) $param:ident # ($alt:lifetime $ptype:ty) $($tail:tt)* ) => {
+        $crate::reconnect_method!{
+            ($err, $app)
+            $sql_name
             $kind
             $name
             $doc
+            ($($arg)* , $param)
             ($($lt)* $alt)
             ($($gen_type)*)
             ($($fn_params)* , $param : & $alt [ $ptype ] )
@@ -118,51 +1096,88 @@ macro_rules! decl_method {
     };
 }
 
+#[macro_export]
+#[doc(hidden)]
+macro_rules! reconnect_call {
+    ( ($err:ty) $this:ident $client:ident $sql_name:ident $name:ident ($retry:expr) => $call:expr) => {
+        $crate::reconnect_call!(($err) $this $client $sql_name $name ($retry, |err: &$err| $crate::util::MethodError::driver_error(err).is_some_and($crate::util::session_terminated)) => $call)
+    };
+    ( ($err:ty) $this:ident $client:ident $sql_name:ident $name:ident ($retry:expr, $terminated:expr) => $call:expr) => {{
+        let $client = $this.connected().await.map_err($crate::util::connect_error::<$err>(::std::stringify!($sql_name), ::std::stringify!($name)))?;
+        match $call {
+            Err(err) if $client.is_closed() || $terminated(&err) => {
+                if $retry {
+                    let $client = $this.reconnect(&$client).await.map_err($crate::util::connect_error::<$err>(::std::stringify!($sql_name), ::std::stringify!($name)))?;
+                    $call
+                } else {
+                    $this.session_terminated(&$client).await;
+                    Err(err)
+                }
+            }
+            res => res,
+        }
+    }};
+}
+
 #[macro_export]
 #[doc(hidden)]
 macro_rules! impl_method {
-    ( ? $name:ident () () () () => () $text:literal ) => {
-        fn $name<'tr, 'st, F>(&'st self, mut row_cb: F)
-        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<(),::tokio_postgres::Error>> + Send + 'tr>>
-        where F: FnMut(::tokio_postgres::Row) -> ::std::result::Result<(),::tokio_postgres::Error>, F: Send, F: 'tr, Self: 'tr, 'st: 'tr
+    ( ($err:ty, $hooks:ident) $sql_name:ident ? $name:ident $doc:literal () () () () => () $text:literal ) => {
+        fn $name<'tr, 'st, F, E>(&'st self, mut row_cb: F)
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<(),E>> + Send + 'tr>>
+        where F: FnMut(::tokio_postgres::Row) -> ::std::result::Result<(),E>, E: ::std::convert::From<$err>, E: Send, F: Send, F: 'tr, Self: 'tr, 'st: 'tr
         {
             use $crate::async_await::TryStreamExt;
 
+            let sql = $crate::util::with_trace_context($crate::tagged_sql!($sql_name $name $text));
             ::std::boxed::Box::pin(async move {
-                let rows = self.query_raw( $text, [] as [&(dyn ::tokio_postgres::types::ToSql + Sync); 0] ).await?;
-                $crate::async_await::pin_mut!(rows);
-                while let Some(row) = rows.try_next().await? {
-                    row_cb(row)?;
-                }
-                Ok::<(),::tokio_postgres::Error>(())
+                let annotations = $crate::annotations!($name $doc);
+                $crate::call_stmt!(($err, $hooks) self $sql_name $name annotations (&*sql, None, $crate::call_params!(), ::std::vec::Vec::new()) -> $crate::util::CallFailure<E> {
+                    let mut num_rows = 0u64;
+                    let rows = self.query_raw( &*sql, [] as [&(dyn ::tokio_postgres::types::ToSql + Sync); 0] ).await?;
+                    $crate::async_await::pin_mut!(rows);
+                    while let Some(row) = rows.try_next().await? {
+                        row_cb(row).map_err($crate::util::CallFailure::Aborted)?;
+                        num_rows += 1;
+                    }
+                    Ok(num_rows)
+                }).map(|_| ())
             })
         }
     };
-    ( ? $name:ident ($($lt:lifetime)*) () ($($fn_params:tt)+) () => (: $head:ident $(: $tail:ident)*) $($text:tt)+) => {
-        fn $name<'tr, 'st $(, $lt)*, F>(&'st self $($fn_params)+ , mut row_cb: F)
-        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<(),::tokio_postgres::Error>> + Send + 'tr>>
-        where F: FnMut(::tokio_postgres::Row) -> ::std::result::Result<(),::tokio_postgres::Error>, F: Send, F: 'tr, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+    ( ($err:ty, $hooks:ident) $sql_name:ident ? $name:ident $doc:literal ($($lt:lifetime)*) () ($($fn_params:tt)+) () => (: $head:ident $(: $tail:ident)*) $($text:tt)+) => {
+        fn $name<'tr, 'st $(, $lt)*, F, E>(&'st self $($fn_params)+ , mut row_cb: F)
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<(),E>> + Send + 'tr>>
+        where F: FnMut(::tokio_postgres::Row) -> ::std::result::Result<(),E>, E: ::std::convert::From<$err>, E: Send, F: Send, F: 'tr, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
         {
+            $crate::bind_defaults!($doc : $head $(: $tail)*);
             use $crate::async_await::TryStreamExt;
 
+            let sql = $crate::util::with_trace_context($crate::tagged_sql!($sql_name $name $crate::sql_literal!( $head $($tail)* => $($text)+ )));
             ::std::boxed::Box::pin(async move {
-                let rows = self.query_raw(
-                    $crate::sql_literal!( $head $($tail)* => $($text)+ ) ,
-                    [& $head as &(dyn ::tokio_postgres::types::ToSql + Sync) $(, & $tail)* ]
-                ).await?;
-                $crate::async_await::pin_mut!(rows);
-                while let Some(row) = rows.try_next().await? {
-                    row_cb(row)?;
-                }
-                Ok::<(),::tokio_postgres::Error>(())
+                let annotations = $crate::annotations!($name $doc);
+                $crate::call_stmt!(($err, $hooks) self $sql_name $name annotations (&*sql, None, $crate::call_params!(: $head $(: $tail)*), ::std::vec![& $head as &(dyn ::tokio_postgres::types::ToSql + Sync) $(, & $tail as &(dyn ::tokio_postgres::types::ToSql + Sync))*]) -> $crate::util::CallFailure<E> {
+                    let mut num_rows = 0u64;
+                    let rows = self.query_raw(
+                        &*sql ,
+                        [& $head as &(dyn ::tokio_postgres::types::ToSql + Sync) $(, & $tail)* ]
+                    ).await?;
+                    $crate::async_await::pin_mut!(rows);
+                    while let Some(row) = rows.try_next().await? {
+                        row_cb(row).map_err($crate::util::CallFailure::Aborted)?;
+                        num_rows += 1;
+                    }
+                    Ok(num_rows)
+                }).map(|_| ())
             })
         }
     };
-    ( ? $name:ident ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)+) () => ($($pv:tt $param:ident)+) $($text:tt)+) => {
-        fn $name<'tr, 'st $(, $lt)* $($gen_type)*, F>(&'st self $($fn_params)+, mut row_cb: F)
-        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<(),::tokio_postgres::Error>> + Send + 'tr>>
-        where F: FnMut(::tokio_postgres::Row) -> ::std::result::Result<(),::tokio_postgres::Error>, F: Send, F: 'tr, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+    ( ($err:ty, $hooks:ident) $sql_name:ident ? $name:ident $doc:literal ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)+) () => ($($pv:tt $param:ident)+) $($text:tt)+) => {
+        fn $name<'tr, 'st $(, $lt)* $($gen_type)*, F, E>(&'st self $($fn_params)+, mut row_cb: F)
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<(),E>> + Send + 'tr>>
+        where F: FnMut(::tokio_postgres::Row) -> ::std::result::Result<(),E>, E: ::std::convert::From<$err>, E: Send, F: Send, F: 'tr, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
         {
+            $crate::bind_defaults!($doc $($pv $param)+);
             use $crate::async_await::TryStreamExt;
 
             ::std::boxed::Box::pin(async move {
@@ -170,104 +1185,167 @@ macro_rules! impl_method {
                 let mut args = ::std::vec::Vec::<&(dyn ::tokio_postgres::types::ToSql + Sync)>::with_capacity($crate::num_args!($($pv $param)+));
                 let mut i = 0;
                 $crate::dynamic_sql!(stmt args i $($text)+);
-                let rows = self.query_raw(&stmt, args).await?;
-                $crate::async_await::pin_mut!(rows);
-                while let Some(row) = rows.try_next().await? {
-                    row_cb(row)?;
-                }
-                Ok::<(),::tokio_postgres::Error>(())
+                $crate::util::tag_sql(&mut stmt, $crate::sql_comment!($sql_name $name));
+                let annotations = $crate::annotations!($name $doc);
+                $crate::call_stmt!(($err, $hooks) self $sql_name $name annotations (&stmt, Some($crate::in_list_len!($($pv $param)+)), $crate::call_params!($($pv $param)+), args.clone()) -> $crate::util::CallFailure<E> {
+                    let mut num_rows = 0u64;
+                    let rows = self.query_raw(&stmt, args.iter().copied()).await?;
+                    $crate::async_await::pin_mut!(rows);
+                    while let Some(row) = rows.try_next().await? {
+                        row_cb(row).map_err($crate::util::CallFailure::Aborted)?;
+                        num_rows += 1;
+                    }
+                    Ok(num_rows)
+                }).map(|_| ())
             })
         }
     };
-    ( ^ $name:ident () () () () => () $text:literal ) => {
+    ( ($err:ty, $hooks:ident) $sql_name:ident ^ $name:ident $doc:literal () () () () => () $text:literal ) => {
         fn $name<'tr, 'st>(&'st self)
-        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::tokio_postgres::RowStream,::tokio_postgres::Error>> + Send + 'tr>>
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<$crate::RowStream,$err>> + Send + 'tr>>
         where Self: 'tr, 'st: 'tr
         {
-            use $crate::async_await::TryStreamExt;
-
-            ::std::boxed::Box::pin(
-                self.query_raw( $text, [] as [&(dyn ::tokio_postgres::types::ToSql + Sync); 0] )
-            )
+            let sql = $crate::util::with_trace_context($crate::tagged_sql!($sql_name $name $text));
+            ::std::boxed::Box::pin(async move {
+                let annotations = $crate::annotations!($name $doc);
+                let started = ::std::time::Instant::now();
+                let metrics = $crate::util::CallMetrics::start(::std::stringify!($sql_name), ::std::stringify!($name));
+                let span = $crate::stmt_span!($name);
+                $crate::util::trace_statement(&span, &*sql, None);
+                let params = $crate::call_params!();
+                let call = $crate::Call { sql_trait: ::std::stringify!($sql_name), name: ::std::stringify!($name), statement: &*sql, parameters: &params };
+                let interceptor = $crate::hooks!($hooks);
+                let res = $crate::util::Instrument::instrument(async {
+                    $crate::async_await::intercept::<$crate::util::CallFailure<$err>>(interceptor.as_deref(), &call).await?;
+                    $crate::async_await::before_call::<_, $crate::util::CallFailure<$err>>(self, annotations).await?;
+                    self.query_raw( &*sql, [] as [&(dyn ::tokio_postgres::types::ToSql + Sync); 0] ).await.map_err($crate::util::CallFailure::from)
+                }, span.clone()).await;
+                if let Some(log) = $crate::SlowQueryLog::exceeded(started) {
+                    log.report(&log.slow_query(::std::stringify!($sql_name), ::std::stringify!($name), &*sql, started, &params));
+                }
+                $crate::async_await::rows::<$err>(res, &call, annotations, interceptor, span, metrics, started).await
+            })
         }
     };
-    ( ^ $name:ident ($($lt:lifetime)*) () ($($fn_params:tt)+) () => (: $head:ident $(: $tail:ident)*) $($text:tt)+) => {
+    ( ($err:ty, $hooks:ident) $sql_name:ident ^ $name:ident $doc:literal ($($lt:lifetime)*) () ($($fn_params:tt)+) () => (: $head:ident $(: $tail:ident)*) $($text:tt)+) => {
         fn $name<'tr, 'st $(, $lt)*>(&'st self $($fn_params)+)
-        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::tokio_postgres::RowStream,::tokio_postgres::Error>> + Send + 'tr>>
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<$crate::RowStream,$err>> + Send + 'tr>>
         where Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
         {
-            use $crate::async_await::TryStreamExt;
-
+            $crate::bind_defaults!($doc : $head $(: $tail)*);
+            let sql = $crate::util::with_trace_context($crate::tagged_sql!($sql_name $name $crate::sql_literal!( $head $($tail)* => $($text)+ )));
             ::std::boxed::Box::pin(async move {
-                self.query_raw(
-                    $crate::sql_literal!( $head $($tail)* => $($text)+ ) ,
-                    [& $head as &(dyn ::tokio_postgres::types::ToSql + Sync) $(, & $tail)* ]
-                ).await
+                let annotations = $crate::annotations!($name $doc);
+                let started = ::std::time::Instant::now();
+                let metrics = $crate::util::CallMetrics::start(::std::stringify!($sql_name), ::std::stringify!($name));
+                let span = $crate::stmt_span!($name);
+                $crate::util::trace_statement(&span, &*sql, None);
+                let params = $crate::call_params!(: $head $(: $tail)*);
+                let call = $crate::Call { sql_trait: ::std::stringify!($sql_name), name: ::std::stringify!($name), statement: &*sql, parameters: &params };
+                let interceptor = $crate::hooks!($hooks);
+                let res = $crate::util::Instrument::instrument(async {
+                    $crate::async_await::intercept::<$crate::util::CallFailure<$err>>(interceptor.as_deref(), &call).await?;
+                    $crate::async_await::before_call::<_, $crate::util::CallFailure<$err>>(self, annotations).await?;
+                    self.query_raw(
+                        &*sql ,
+                        [& $head as &(dyn ::tokio_postgres::types::ToSql + Sync) $(, & $tail)* ]
+                    ).await.map_err($crate::util::CallFailure::from)
+                }, span.clone()).await;
+                if let Some(log) = $crate::SlowQueryLog::exceeded(started) {
+                    log.report(&log.slow_query(::std::stringify!($sql_name), ::std::stringify!($name), &*sql, started, &params));
+                }
+                $crate::async_await::rows::<$err>(res, &call, annotations, interceptor, span, metrics, started).await
             })
         }
     };
-    ( ^ $name:ident ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)+) () => ($($pv:tt $param:ident)+) $($text:tt)+) => {
+    ( ($err:ty, $hooks:ident) $sql_name:ident ^ $name:ident $doc:literal ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)+) () => ($($pv:tt $param:ident)+) $($text:tt)+) => {
         fn $name<'tr, 'st $(, $lt)* $($gen_type)*>(&'st self $($fn_params)+)
-        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::tokio_postgres::RowStream,::tokio_postgres::Error>> + Send + 'tr>>
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<$crate::RowStream,$err>> + Send + 'tr>>
         where Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
         {
-            use $crate::async_await::TryStreamExt;
-
+            $crate::bind_defaults!($doc $($pv $param)+);
             ::std::boxed::Box::pin(async move {
                 let mut stmt = ::std::string::String::with_capacity($crate::sql_len!($($text)+));
                 let mut args = ::std::vec::Vec::<&(dyn ::tokio_postgres::types::ToSql + Sync)>::with_capacity($crate::num_args!($($pv $param)+));
                 let mut i = 0;
                 $crate::dynamic_sql!(stmt args i $($text)+);
-                self.query_raw(&stmt, args).await
+                $crate::util::tag_sql(&mut stmt, $crate::sql_comment!($sql_name $name));
+                let annotations = $crate::annotations!($name $doc);
+                let started = ::std::time::Instant::now();
+                let metrics = $crate::util::CallMetrics::start(::std::stringify!($sql_name), ::std::stringify!($name));
+                let span = $crate::stmt_span!($name);
+                $crate::util::trace_statement(&span, &stmt, Some($crate::in_list_len!($($pv $param)+)));
+                let params = $crate::call_params!($($pv $param)+);
+                let call = $crate::Call { sql_trait: ::std::stringify!($sql_name), name: ::std::stringify!($name), statement: &stmt, parameters: &params };
+                let interceptor = $crate::hooks!($hooks);
+                let res = $crate::util::Instrument::instrument(async {
+                    $crate::async_await::intercept::<$crate::util::CallFailure<$err>>(interceptor.as_deref(), &call).await?;
+                    $crate::async_await::before_call::<_, $crate::util::CallFailure<$err>>(self, annotations).await?;
+                    self.query_raw(&stmt, args).await.map_err($crate::util::CallFailure::from)
+                }, span.clone()).await;
+                if let Some(log) = $crate::SlowQueryLog::exceeded(started) {
+                    log.report(&log.slow_query(::std::stringify!($sql_name), ::std::stringify!($name), &stmt, started, &params));
+                }
+                $crate::async_await::rows::<$err>(res, &call, annotations, interceptor, span, metrics, started).await
             })
         }
     };
-    ( % $name:ident () () () () => () $text:literal ) => {
-        fn $name<'tr, 'st, R>(&'st self)
-        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::std::vec::Vec<R>,::tokio_postgres::Error>> + Send + 'tr>>
-        where R: Send, R: ::std::convert::TryFrom<::tokio_postgres::Row>, ::tokio_postgres::Error: ::std::convert::From<R::Error>, Self: 'tr, 'st: 'tr
+    ( ($err:ty, $hooks:ident) $sql_name:ident % $name:ident $doc:literal () () () () => () $text:literal ) => {
+        fn $name<'tr, 'st, R, E>(&'st self)
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::std::vec::Vec<R>,E>> + Send + 'tr>>
+        where R: Send, R: ::std::convert::TryFrom<::tokio_postgres::Row>, E: ::std::convert::From<R::Error> + ::std::convert::From<$err>, E: Send, Self: 'tr, 'st: 'tr
         {
             use $crate::async_await::TryStreamExt;
 
+            let sql = $crate::util::with_trace_context($crate::tagged_sql!($sql_name $name $text));
             ::std::boxed::Box::pin(async move {
-                let rows = self.query_raw( $text, [] as [&(dyn ::tokio_postgres::types::ToSql + Sync); 0] ).await?;
-                $crate::async_await::pin_mut!(rows);
-                let mut data = ::std::vec::Vec::new();
-                while let Some(row) = rows.try_next().await? {
-                    let item = R::try_from(row)?;
-                    data.push(item);
-                }
-                Ok(data)
+                let annotations = $crate::annotations!($name $doc);
+                $crate::call_stmt!(($err, $hooks) self $sql_name $name annotations (&*sql, None, $crate::call_params!(), ::std::vec::Vec::new()) -> $crate::util::CallFailure<E> {
+                    let rows = self.query_raw( &*sql, [] as [&(dyn ::tokio_postgres::types::ToSql + Sync); 0] ).await?;
+                    $crate::async_await::pin_mut!(rows);
+                    let mut data = ::std::vec::Vec::new();
+                    while let Some(row) = rows.try_next().await? {
+                        let item = R::try_from(row).map_err(|err| $crate::util::CallFailure::Aborted(E::from(err)))?;
+                        data.push(item);
+                    }
+                    Ok(data)
+                })
             })
         }
     };
-    ( % $name:ident ($($lt:lifetime)*) () ($($fn_params:tt)+) () => (: $head:ident $(: $tail:ident)*) $($text:tt)+) => {
-        fn $name<'tr, 'st $(, $lt)*, R>(&'st self $($fn_params)+)
-        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::std::vec::Vec<R>,::tokio_postgres::Error>> + Send + 'tr>>
-        where R: Send, R: ::std::convert::TryFrom<::tokio_postgres::Row>, ::tokio_postgres::Error: ::std::convert::From<R::Error>, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+    ( ($err:ty, $hooks:ident) $sql_name:ident % $name:ident $doc:literal ($($lt:lifetime)*) () ($($fn_params:tt)+) () => (: $head:ident $(: $tail:ident)*) $($text:tt)+) => {
+        fn $name<'tr, 'st $(, $lt)*, R, E>(&'st self $($fn_params)+)
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::std::vec::Vec<R>,E>> + Send + 'tr>>
+        where R: Send, R: ::std::convert::TryFrom<::tokio_postgres::Row>, E: ::std::convert::From<R::Error> + ::std::convert::From<$err>, E: Send, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
         {
+            $crate::bind_defaults!($doc : $head $(: $tail)*);
             use $crate::async_await::TryStreamExt;
 
+            let sql = $crate::util::with_trace_context($crate::tagged_sql!($sql_name $name $crate::sql_literal!( $head $($tail)* => $($text)+ )));
             ::std::boxed::Box::pin(async move {
-                let rows = self.query_raw(
-                    $crate::sql_literal!( $head $($tail)* => $($text)+ ) ,
-                    [& $head as &(dyn ::tokio_postgres::types::ToSql + Sync) $(, & $tail)* ]
-                ).await?;
-                $crate::async_await::pin_mut!(rows);
-                let mut data = ::std::vec::Vec::new();
-                while let Some(row) = rows.try_next().await? {
-                    let item = R::try_from(row)?;
-                    data.push(item);
-                }
-                Ok(data)
+                let annotations = $crate::annotations!($name $doc);
+                $crate::call_stmt!(($err, $hooks) self $sql_name $name annotations (&*sql, None, $crate::call_params!(: $head $(: $tail)*), ::std::vec![& $head as &(dyn ::tokio_postgres::types::ToSql + Sync) $(, & $tail as &(dyn ::tokio_postgres::types::ToSql + Sync))*]) -> $crate::util::CallFailure<E> {
+                    let rows = self.query_raw(
+                        &*sql ,
+                        [& $head as &(dyn ::tokio_postgres::types::ToSql + Sync) $(, & $tail)* ]
+                    ).await?;
+                    $crate::async_await::pin_mut!(rows);
+                    let mut data = ::std::vec::Vec::new();
+                    while let Some(row) = rows.try_next().await? {
+                        let item = R::try_from(row).map_err(|err| $crate::util::CallFailure::Aborted(E::from(err)))?;
+                        data.push(item);
+                    }
+                    Ok(data)
+                })
             })
         }
     };
-    ( % $name:ident ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)+) () => ($($pv:tt $param:ident)+) $($text:tt)+) => {
-        fn $name<'tr, 'st $(, $lt)* $($gen_type)*, R>(&'st self $($fn_params)+)
-        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::std::vec::Vec<R>,::tokio_postgres::Error>> + Send + 'tr>>
-        where R: Send, R: ::std::convert::TryFrom<::tokio_postgres::Row>, ::tokio_postgres::Error: ::std::convert::From<R::Error>, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
+    ( ($err:ty, $hooks:ident) $sql_name:ident % $name:ident $doc:literal ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)+) () => ($($pv:tt $param:ident)+) $($text:tt)+) => {
+        fn $name<'tr, 'st $(, $lt)* $($gen_type)*, R, E>(&'st self $($fn_params)+)
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::std::vec::Vec<R>,E>> + Send + 'tr>>
+        where R: Send, R: ::std::convert::TryFrom<::tokio_postgres::Row>, E: ::std::convert::From<R::Error> + ::std::convert::From<$err>, E: Send, Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
         {
+            $crate::bind_defaults!($doc $($pv $param)+);
             use $crate::async_await::TryStreamExt;
 
             ::std::boxed::Box::pin(async move {
@@ -275,95 +1353,133 @@ macro_rules! impl_method {
                 let mut args = ::std::vec::Vec::<&(dyn ::tokio_postgres::types::ToSql + Sync)>::with_capacity($crate::num_args!($($pv $param)+));
                 let mut i = 0;
                 $crate::dynamic_sql!(stmt args i $($text)+);
-                let rows = self.query_raw(&stmt, args).await?;
-                $crate::async_await::pin_mut!(rows);
-                let mut data = ::std::vec::Vec::new();
-                while let Some(row) = rows.try_next().await? {
-                    let item = R::try_from(row)?;
-                    data.push(item);
-                }
-                Ok(data)
+                $crate::util::tag_sql(&mut stmt, $crate::sql_comment!($sql_name $name));
+                let annotations = $crate::annotations!($name $doc);
+                $crate::call_stmt!(($err, $hooks) self $sql_name $name annotations (&stmt, Some($crate::in_list_len!($($pv $param)+)), $crate::call_params!($($pv $param)+), args.clone()) -> $crate::util::CallFailure<E> {
+                    let rows = self.query_raw(&stmt, args.iter().copied()).await?;
+                    $crate::async_await::pin_mut!(rows);
+                    let mut data = ::std::vec::Vec::new();
+                    while let Some(row) = rows.try_next().await? {
+                        let item = R::try_from(row).map_err(|err| $crate::util::CallFailure::Aborted(E::from(err)))?;
+                        data.push(item);
+                    }
+                    Ok(data)
+                })
             })
         }
     };
-    ( ! $name:ident () () () () => () $text:literal ) => {
+    ( ($err:ty, $hooks:ident) $sql_name:ident ! $name:ident $doc:literal () () () () => () $text:literal ) => {
         fn $name<'tr, 'st>(&'st self)
-        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<u64,::tokio_postgres::Error>> + Send + 'tr>>
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<u64,$err>> + Send + 'tr>>
         where Self: 'tr, 'st: 'tr
         {
+            let sql = $crate::util::with_trace_context($crate::tagged_sql!($sql_name $name $text));
             ::std::boxed::Box::pin(async move {
-                self.execute( $text, &[] ).await
+                let annotations = $crate::annotations!($name $doc);
+                $crate::call_stmt!(($err, $hooks) self $sql_name $name annotations (&*sql, None, $crate::call_params!(), ::std::vec::Vec::new()) -> $crate::util::CallFailure<$err> {
+                    let num_rows = self.execute( &*sql, &[] ).await?;
+                    Ok(annotations.expect_rows(num_rows)?)
+                })
             })
         }
     };
-    ( ! $name:ident ($($lt:lifetime)*) () ($($fn_params:tt)+) () => (: $head:ident $(: $tail:ident)*) $($text:tt)+) => {
+    ( ($err:ty, $hooks:ident) $sql_name:ident ! $name:ident $doc:literal ($($lt:lifetime)*) () ($($fn_params:tt)+) () => (: $head:ident $(: $tail:ident)*) $($text:tt)+) => {
         fn $name<'tr, 'st $(, $lt)*>(&'st self $($fn_params)+ )
-        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<u64,::tokio_postgres::Error>> + Send + 'tr>>
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<u64,$err>> + Send + 'tr>>
         where Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
         {
+            $crate::bind_defaults!($doc : $head $(: $tail)*);
+            let sql = $crate::util::with_trace_context($crate::tagged_sql!($sql_name $name $crate::sql_literal!( $head $($tail)* => $($text)+ )));
             ::std::boxed::Box::pin(async move {
-                self.execute(
-                    $crate::sql_literal!( $head $($tail)* => $($text)+ ) ,
-                    &[& $head as &(dyn ::tokio_postgres::types::ToSql + Sync) $(, & $tail)* ]
-                ).await
+                let annotations = $crate::annotations!($name $doc);
+                $crate::call_stmt!(($err, $hooks) self $sql_name $name annotations (&*sql, None, $crate::call_params!(: $head $(: $tail)*), ::std::vec![& $head as &(dyn ::tokio_postgres::types::ToSql + Sync) $(, & $tail as &(dyn ::tokio_postgres::types::ToSql + Sync))*]) -> $crate::util::CallFailure<$err> {
+                    let num_rows = self.execute(
+                        &*sql ,
+                        &[& $head as &(dyn ::tokio_postgres::types::ToSql + Sync) $(, & $tail)* ]
+                    ).await?;
+                    Ok(annotations.expect_rows(num_rows)?)
+                })
             })
         }
     };
-    ( ! $name:ident ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)+) () => ($($pv:tt $param:ident)+) $($text:tt)+) => {
+    ( ($err:ty, $hooks:ident) $sql_name:ident ! $name:ident $doc:literal ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)+) () => ($($pv:tt $param:ident)+) $($text:tt)+) => {
         fn $name<'tr, 'st $(, $lt)* $($gen_type)*>(&'st self $($fn_params)+ )
-        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<u64,::tokio_postgres::Error>> + Send + 'tr>>
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<u64,$err>> + Send + 'tr>>
         where Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
         {
+            $crate::bind_defaults!($doc $($pv $param)+);
             ::std::boxed::Box::pin(async move {
                 let mut stmt = ::std::string::String::with_capacity($crate::sql_len!($($text)+));
                 let mut args = ::std::vec::Vec::<&(dyn ::tokio_postgres::types::ToSql + Sync)>::with_capacity($crate::num_args!($($pv $param)+));
                 let mut i = 0;
                 $crate::dynamic_sql!(stmt args i $($text)+);
-                self.execute_raw(&stmt, args).await
+                $crate::util::tag_sql(&mut stmt, $crate::sql_comment!($sql_name $name));
+                let annotations = $crate::annotations!($name $doc);
+                $crate::call_stmt!(($err, $hooks) self $sql_name $name annotations (&stmt, Some($crate::in_list_len!($($pv $param)+)), $crate::call_params!($($pv $param)+), args.clone()) -> $crate::util::CallFailure<$err> {
+                    let num_rows = self.execute_raw(&stmt, args.iter().copied()).await?;
+                    Ok(annotations.expect_rows(num_rows)?)
+                })
             })
         }
     };
-    ( -> $name:ident () () () () => () $text:literal ) => {
+    ( ($err:ty, $hooks:ident) $sql_name:ident -> $name:ident $doc:literal () () () () => () $text:literal ) => {
         fn $name<'tr, 'st>(&'st self)
-        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::tokio_postgres::Row,::tokio_postgres::Error>> + Send + 'tr>>
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::tokio_postgres::Row,$err>> + Send + 'tr>>
         where Self: 'tr, 'st: 'tr
         {
+            let sql = $crate::util::with_trace_context($crate::tagged_sql!($sql_name $name $text));
             ::std::boxed::Box::pin(async move {
-                self.query_one( $text, &[] ).await
+                let annotations = $crate::annotations!($name $doc);
+                $crate::call_stmt!(($err, $hooks) self $sql_name $name annotations (&*sql, None, $crate::call_params!(), ::std::vec::Vec::new()) {
+                    self.query_one( &*sql, &[] ).await
+                })
             })
         }
     };
-    ( -> $name:ident ($($lt:lifetime)*) () ($($fn_params:tt)+) () => (: $head:ident $(: $tail:ident)*) $($text:tt)+) => {
+    ( ($err:ty, $hooks:ident) $sql_name:ident -> $name:ident $doc:literal ($($lt:lifetime)*) () ($($fn_params:tt)+) () => (: $head:ident $(: $tail:ident)*) $($text:tt)+) => {
         fn $name<'tr, 'st $(, $lt)*>(&'st self $($fn_params)+ )
-        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::tokio_postgres::Row,::tokio_postgres::Error>> + Send + 'tr>>
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::tokio_postgres::Row,$err>> + Send + 'tr>>
         where Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
         {
+            $crate::bind_defaults!($doc : $head $(: $tail)*);
+            let sql = $crate::util::with_trace_context($crate::tagged_sql!($sql_name $name $crate::sql_literal!( $head $($tail)* => $($text)+ )));
             ::std::boxed::Box::pin(async move {
-                self.query_one(
-                    $crate::sql_literal!( $head $($tail)* => $($text)+ ) ,
-                    &[& $head as &(dyn ::tokio_postgres::types::ToSql + Sync) $(, & $tail)* ]
-                ).await
+                let annotations = $crate::annotations!($name $doc);
+                $crate::call_stmt!(($err, $hooks) self $sql_name $name annotations (&*sql, None, $crate::call_params!(: $head $(: $tail)*), ::std::vec![& $head as &(dyn ::tokio_postgres::types::ToSql + Sync) $(, & $tail as &(dyn ::tokio_postgres::types::ToSql + Sync))*]) {
+                    self.query_one(
+                        &*sql ,
+                        &[& $head as &(dyn ::tokio_postgres::types::ToSql + Sync) $(, & $tail)* ]
+                    ).await
+                })
             })
         }
     };
-    ( -> $name:ident ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)+) () => ($($pv:tt $param:ident)+) $($text:tt)+) => {
+    ( ($err:ty, $hooks:ident) $sql_name:ident -> $name:ident $doc:literal ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)+) () => ($($pv:tt $param:ident)+) $($text:tt)+) => {
         fn $name<'tr, 'st $(, $lt)* $($gen_type)*>(&'st self $($fn_params)+ )
-        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::tokio_postgres::Row,::tokio_postgres::Error>> + Send + 'tr>>
+        -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::std::result::Result<::tokio_postgres::Row,$err>> + Send + 'tr>>
         where Self: 'tr, 'st: 'tr $(, $lt : 'tr)*
         {
+            $crate::bind_defaults!($doc $($pv $param)+);
             ::std::boxed::Box::pin(async move {
                 let mut stmt = ::std::string::String::with_capacity($crate::sql_len!($($text)+));
                 let mut args = ::std::vec::Vec::<&(dyn ::tokio_postgres::types::ToSql + Sync)>::with_capacity($crate::num_args!($($pv $param)+));
                 let mut i = 0;
                 $crate::dynamic_sql!(stmt args i $($text)+);
-                self.query_one(&stmt, &args).await
+                $crate::util::tag_sql(&mut stmt, $crate::sql_comment!($sql_name $name));
+                let annotations = $crate::annotations!($name $doc);
+                $crate::call_stmt!(($err, $hooks) self $sql_name $name annotations (&stmt, Some($crate::in_list_len!($($pv $param)+)), $crate::call_params!($($pv $param)+), args.clone()) {
+                    self.query_one(&stmt, &args).await
+                })
             })
         }
     };
-    ( $kind:tt $name:ident ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ($param:ident : _ $($tail:tt)*) => ($($pv:tt $param_name:ident)+) $($text:tt)+)  => {
+    ( ($err:ty, $hooks:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ($param:ident : _ $($tail:tt)*) => ($($pv:tt $param_name:ident)+) $($text:tt)+)  => {
         $crate::impl_method!{
+            ($err, $hooks)
+            $sql_name
             $kind
             $name
+            $doc
             ($($lt)*)
             ($($gen_type)*)
             ($($fn_params)* , $param : impl ::tokio_postgres::types::ToSql + Sync + Send + 'tr)
@@ -373,10 +1489,13 @@ macro_rules! impl_method {
             $($text)+
         }
     };
-    ( $kind:tt $name:ident ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ($param:ident : ($plt:lifetime & $ptype:ty) $($tail:tt)*) => ($($pv:tt $param_name:ident)+) $($text:tt)+)  => {
+    ( ($err:ty, $hooks:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ($param:ident : ($plt:lifetime & $ptype:ty) $($tail:tt)*) => ($($pv:tt $param_name:ident)+) $($text:tt)+)  => {
         $crate::impl_method!{
+            ($err, $hooks)
+            $sql_name
             $kind
             $name
+            $doc
             ($($lt)* $plt)
             ($($gen_type)*)
             ($($fn_params)* , $param : & $plt $ptype)
@@ -386,23 +1505,29 @@ macro_rules! impl_method {
             $($text)+
         }
     };
-    ( $kind:tt $name:ident ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ($param:ident : ($ptype:ty) $($tail:tt)*) => ($($pv:tt $param_name:ident)+) $($text:tt)+)  => {
+    ( ($err:ty, $hooks:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ($param:ident : ($ptype:ty) $($tail:tt)*) => ($($pv:tt $param_name:ident)+) $($text:tt)+)  => {
         $crate::impl_method!{
+            ($err, $hooks)
+            $sql_name
             $kind
             $name
+            $doc
             ($($lt)*)
             ($($gen_type)*)
-            ($($fn_params)* , $param : $ptype)
+            ($($fn_params)* , $param : $crate::arg_type!($doc $param $ptype))
             ($($tail)*)
             =>
             ($($pv $param_name)+)
             $($text)+
         }
     };
-    ( $kind:tt $name:ident ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ($param:ident # [$alt:lifetime $gtype:ident] $($tail:tt)*) => ($($pv:tt $param_name:ident)+) $($text:tt)+)  => {
+    ( ($err:ty, $hooks:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ($param:ident # [$alt:lifetime $gtype:ident] $($tail:tt)*) => ($($pv:tt $param_name:ident)+) $($text:tt)+)  => {
         $crate::impl_method!{
+            ($err, $hooks)
+            $sql_name
             $kind
             $name
+            $doc
             ($($lt)* $alt)
             ($($gen_type)*  , $gtype : ::tokio_postgres::types::ToSql + Sync + Send + 'tr)
             ($($fn_params)* , $param : & $alt [ $gtype ])
@@ -412,10 +1537,13 @@ macro_rules! impl_method {
             $($text)+
         }
     };
-    ( $kind:tt $name:ident ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ($param:ident # ($alt:lifetime $plt:lifetime & $ptype:ty) $($tail:tt)*) => ($($pv:tt $param_name:ident)+) $($text:tt)+)  => {
+    ( ($err:ty, $hooks:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ($param:ident # ($alt:lifetime $plt:lifetime & $ptype:ty) $($tail:tt)*) => ($($pv:tt $param_name:ident)+) $($text:tt)+)  => {
         $crate::impl_method!{
+            ($err, $hooks)
+            $sql_name
             $kind
             $name
+            $doc
             ($($lt)* $alt $plt)
             ($($gen_type)*)
             ($($fn_params)* , $param : & $alt [ & $plt $ptype ])
@@ -425,10 +1553,13 @@ macro_rules! impl_method {
             $($text)+
         }
     };
-    ( $kind:tt $name:ident ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ($param:ident # ($alt:lifetime $ptype:ty) $($tail:tt)*) => ($($pv:tt $param_name:ident)+) $($text:tt)+)  => {
+    ( ($err:ty, $hooks:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($lt:lifetime)*) ($($gen_type:tt)*) ($($fn_params:tt)*) ($param:ident # ($alt:lifetime $ptype:ty) $($tail:tt)*) => ($($pv:tt $param_name:ident)+) $($text:tt)+)  => {
         $crate::impl_method!{
+            ($err, $hooks)
+            $sql_name
             $kind
             $name
+            $doc
             ($($lt)* $alt)
             ($($gen_type)*)
             ($($fn_params)* , $param : & $alt [ $ptype ])
@@ -439,3 +1570,759 @@ macro_rules! impl_method {
         }
     };
 }
+
+/// Tells generated methods whether they are executed within a transaction.
+pub trait Scope {
+    const IN_TRANSACTION: bool;
+}
+
+impl Scope for ::tokio_postgres::Client {
+    const IN_TRANSACTION: bool = false;
+}
+
+impl Scope for ::tokio_postgres::Transaction<'_> {
+    const IN_TRANSACTION: bool = true;
+}
+
+impl crate::util::Within<true> for ::tokio_postgres::Transaction<'_> {}
+
+/**
+Prepares the session for the execution of the named statement.
+
+See `util::Annotations::parse` for what is done. Returns what has to be done after the call.
+*/
+pub async fn before_call<C, X>(client: &C, annotations: &crate::util::Annotations) -> Result<crate::util::AfterCall, X>
+where C: ::tokio_postgres::GenericClient + Scope, X: crate::util::Failure
+{
+    let (cmds, after) = annotations.before_call(C::IN_TRANSACTION);
+    let res = prepare_session(client, annotations, cmds).await;
+    if res.is_err() && after == crate::util::AfterCall::EndTransaction {
+        let _ = client.batch_execute("ROLLBACK").await;
+    }
+    res.map(|_| after)
+}
+
+/// Executes the commands that prepare the session and tries to acquire the `try_advisory_lock:` locks.
+async fn prepare_session<C, X>(client: &C, annotations: &crate::util::Annotations, cmds: &str) -> Result<(), X>
+where C: ::tokio_postgres::GenericClient, X: crate::util::Failure
+{
+    if !cmds.is_empty() {
+        client.batch_execute(cmds).await?;
+    }
+    if let Some(query) = annotations.try_lock_query() {
+        let row = client.query_one(query, &[]).await?;
+        if let Some(failure) = annotations.busy_lock(&row)? {
+            return Err(X::from(failure));
+        }
+    }
+    Ok(())
+}
+
+/**
+Rolls back the transaction that was started by `before_call` when the future of the generated method
+is dropped before the call completes.
+
+Like `tokio_postgres::Transaction` the guard polls the cleanup request once when it is dropped,
+which sends it to the server without waiting for the response.
+*/
+pub struct AfterCallGuard<'a, C: ::tokio_postgres::GenericClient> {
+    client: &'a C,
+    annotations: &'a crate::util::Annotations,
+    after: Option<crate::util::AfterCall>,
+}
+
+impl<'a, C: ::tokio_postgres::GenericClient> AfterCallGuard<'a, C> {
+    pub fn new(client: &'a C, annotations: &'a crate::util::Annotations, after: crate::util::AfterCall) -> Self {
+        Self { client, annotations, after: Some(after) }
+    }
+
+    /// Ends the transaction that was started by `before_call`.
+    pub async fn after_call<T, X: crate::util::Failure>(mut self, res: Result<T, X>) -> Result<T, X> {
+        let after = self.after.take().unwrap_or(crate::util::AfterCall::Nothing);
+        match self.annotations.after_call(after, res.is_ok()) {
+            Some(cmds) => {
+                let after_res = self.client.batch_execute(cmds).await;
+                res.and_then(|data| after_res.map(|_| data).map_err(X::from))
+            }
+            None => res,
+        }
+    }
+}
+
+impl<C: ::tokio_postgres::GenericClient> Drop for AfterCallGuard<'_, C> {
+    fn drop(&mut self) {
+        if let Some(cmds) = self.after.take().and_then(|after| self.annotations.after_call(after, false)) {
+            let mut cx = ::std::task::Context::from_waker(::futures_util::task::noop_waker_ref());
+            let _ = self.client.batch_execute(cmds).as_mut().poll(&mut cx);
+        }
+    }
+}
+
+/// Calls the `before` hook of the interceptor and fails the call when the interceptor rejects it.
+pub async fn intercept<X: crate::util::Failure>(interceptor: Option<&dyn crate::Interceptor>, call: &crate::Call<'_>) -> Result<(), X> {
+    if let Some(interceptor) = interceptor {
+        if let Err(rejection) = interceptor.before(call).await {
+            return Err(X::from(crate::CheckFailure::Rejected(rejection)));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the execution plan of the slow statement or `None` if it cannot be obtained.
+pub async fn explain<C>(client: &C, stmt: &str, args: Vec<&(dyn ::tokio_postgres::types::ToSql + Sync)>) -> Option<String>
+where C: ::tokio_postgres::GenericClient + Scope
+{
+    let explain = crate::slow_query::explain_command(stmt)?;
+    if C::IN_TRANSACTION {
+        client.batch_execute(crate::slow_query::EXPLAIN_SAVEPOINT).await.ok()?;
+    }
+    let plan = client.query_opt(explain.as_str(), &args).await;
+    if C::IN_TRANSACTION {
+        client.batch_execute(crate::slow_query::EXPLAIN_ROLLBACK).await.ok()?;
+    }
+    plan.ok()??.try_get::<_, crate::slow_query::Plan>(0).ok().map(|plan| plan.0)
+}
+
+/**
+Rows returned by a `^` method.
+
+The method call is completed - its tracing span is closed, its metrics are recorded, and the `after` hook of
+the interceptor is called - when the stream ends, fails to fetch the next row, or is dropped. The stream ends
+after the `after` hook resolves. When the stream is dropped early the `after` hook is polled only once.
+
+```rust , ignore
+use futures_util::TryStreamExt;
+
+let mut rows = db.get_loaned_books("mjones").await?;
+while let Some(row) = rows.try_next().await? {
+    let book_title : &str = row.try_get("book_title")?;
+    println!("{}", book_title);
+}
+```
+*/
+pub struct RowStream {
+    rows: Rows,
+    call: Option<crate::util::RowsCall>,
+    finishing: Option<crate::BoxFuture<'static, Option<::tokio_postgres::Error>>>,
+}
+
+enum Rows {
+    Driver(::std::pin::Pin<Box<::tokio_postgres::RowStream>>),
+    #[cfg(any(feature = "mock", feature = "replay"))]
+    Canned(::std::vec::IntoIter<::tokio_postgres::Row>, u64),
+}
+
+impl RowStream {
+    /// Returns the number of rows affected by the statement. Available only after the stream is exhausted.
+    pub fn rows_affected(&self) -> Option<u64> {
+        match &self.rows {
+            Rows::Driver(rows) => rows.rows_affected(),
+            #[cfg(any(feature = "mock", feature = "replay"))]
+            Rows::Canned(rows, num_rows) => if rows.as_slice().is_empty() { Some(*num_rows) } else { None },
+        }
+    }
+
+    /// Streams canned rows.
+    #[cfg(any(feature = "mock", feature = "replay"))]
+    pub(crate) fn canned(rows: Vec<::tokio_postgres::Row>) -> Self {
+        let num_rows = rows.len() as u64;
+        Self { rows: Rows::Canned(rows.into_iter(), num_rows), call: None, finishing: None }
+    }
+}
+
+impl ::futures_util::Stream for RowStream {
+    type Item = Result<::tokio_postgres::Row, ::tokio_postgres::Error>;
+
+    fn poll_next(mut self: ::std::pin::Pin<&mut Self>, cx: &mut ::std::task::Context<'_>) -> ::std::task::Poll<Option<Self::Item>> {
+        use ::std::task::Poll;
+
+        let this = &mut *self;
+        if this.finishing.is_none() {
+            let next = match &mut this.rows {
+                Rows::Driver(rows) => rows.as_mut().poll_next(cx),
+                #[cfg(any(feature = "mock", feature = "replay"))]
+                Rows::Canned(rows, _) => Poll::Ready(rows.next().map(Ok)),
+            };
+            match next {
+                Poll::Ready(Some(Ok(row))) => {
+                    if let Some(call) = &mut this.call {
+                        call.row();
+                    }
+                    return Poll::Ready(Some(Ok(row)));
+                }
+                Poll::Ready(res) => match this.call.take() {
+                    Some(call) => this.finishing = Some(call.finish(res.and_then(Result::err))),
+                    None => return Poll::Ready(res),
+                },
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        match this.finishing.as_mut().map(|finishing| finishing.as_mut().poll(cx)) {
+            Some(Poll::Ready(err)) => {
+                this.finishing = None;
+                Poll::Ready(err.map(Err))
+            }
+            _ => Poll::Pending,
+        }
+    }
+}
+
+impl Drop for RowStream {
+    fn drop(&mut self) {
+        if let Some(call) = self.call.take() {
+            let mut cx = ::std::task::Context::from_waker(::futures_util::task::noop_waker_ref());
+            let _ = call.finish(None).as_mut().poll(&mut cx);
+        }
+    }
+}
+
+/// Returns the rows that the `^` method call retrieved or completes the call that failed.
+pub async fn rows<M>(
+    res: Result<::tokio_postgres::RowStream, crate::util::CallFailure<M>>, call: &crate::Call<'_>, annotations: &crate::util::Annotations,
+    interceptor: Option<::std::sync::Arc<dyn crate::Interceptor>>, span: crate::util::Span, metrics: crate::util::CallMetrics, started: ::std::time::Instant,
+) -> Result<RowStream, M>
+where M: crate::util::MethodError
+{
+    let res = match res {
+        Ok(rows) => {
+            let call = crate::util::RowsCall::new(call, interceptor, span, metrics, started);
+            return Ok(RowStream { rows: Rows::Driver(Box::pin(rows)), call: Some(call), finishing: None });
+        }
+        Err(err) => Err::<RowStream, _>(err),
+    };
+    if let Some(interceptor) = &interceptor {
+        interceptor.after(call, crate::Outcome::of(&res), started.elapsed()).await;
+    }
+    crate::util::trace_result(&span, &res);
+    metrics.record(&res);
+    crate::util::call_error::<M, _, _>(res, call.sql_trait, call.name, annotations, call.statement, call.parameters)
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! call_stmt {
+    ( ($err:ty, $hooks:ident) $client:ident $sql_name:ident $name:ident $annotations:ident ($stmt:expr, $in_list_len:expr, $params:expr, $args:expr) $body:block) => {
+        $crate::call_stmt!(($err, $hooks) $client $sql_name $name $annotations ($stmt, $in_list_len, $params, $args) -> $crate::util::CallFailure<$err> {
+            let res : ::std::result::Result<_,::tokio_postgres::Error> = $body;
+            res.map_err($crate::util::CallFailure::from)
+        })
+    };
+    ( ($err:ty, $hooks:ident) $client:ident $sql_name:ident $name:ident $annotations:ident ($stmt:expr, $in_list_len:expr, $params:expr, $args:expr) -> $failure:ty $body:block) => {{
+        let started = ::std::time::Instant::now();
+        let metrics = $crate::util::CallMetrics::start(::std::stringify!($sql_name), ::std::stringify!($name));
+        let span = $crate::stmt_span!($name);
+        $crate::util::trace_statement(&span, $stmt, $in_list_len);
+        let params = $params;
+        let call = $crate::Call { sql_trait: ::std::stringify!($sql_name), name: ::std::stringify!($name), statement: $stmt, parameters: &params };
+        let interceptor = $crate::hooks!($hooks);
+        let res = $crate::util::Instrument::instrument(async {
+            $crate::async_await::intercept::<$failure>(interceptor.as_deref(), &call).await?;
+            let after = $crate::async_await::before_call::<_, $failure>($client, $annotations).await?;
+            let guard = $crate::async_await::AfterCallGuard::new($client, $annotations, after);
+            let res : ::std::result::Result<_,$failure> = async { $body }.await;
+            guard.after_call(res).await
+        }, span.clone()).await;
+        if let Some(interceptor) = &interceptor {
+            interceptor.after(&call, $crate::Outcome::of(&res), started.elapsed()).await;
+        }
+        $crate::util::trace_result(&span, &res);
+        metrics.record(&res);
+        if let Some(log) = $crate::SlowQueryLog::exceeded(started) {
+            let mut query = log.slow_query(::std::stringify!($sql_name), ::std::stringify!($name), $stmt, started, &params);
+            if log.explains() && res.is_ok() {
+                query.plan = $crate::async_await::explain($client, $stmt, $args).await;
+            }
+            log.report(&query);
+        }
+        $crate::util::call_error::<$err, _, _>(res, ::std::stringify!($sql_name), ::std::stringify!($name), $annotations, $stmt, &params)
+    }};
+}
+
+impl crate::Router<::tokio_postgres::Client> {
+    /// Starts a transaction on the primary client.
+    pub async fn transaction(&mut self) -> Result<::tokio_postgres::Transaction<'_>, ::tokio_postgres::Error> {
+        self.primary_mut().transaction().await
+    }
+}
+
+type Connect = dyn Fn() -> ::std::pin::Pin<Box<dyn ::std::future::Future<Output = Result<::tokio_postgres::Client, ::tokio_postgres::Error>> + Send>> + Send + Sync;
+
+/**
+A client that re-establishes the connection when it is lost.
+
+`impl_sql` implements the generated trait for `ReconnectingClient` when it is given the `reconnect` option.
+When the connection is found closed before a call, the client reconnects using the stored configuration
+before executing the statement.
+When the connection is lost during a call the statement is executed again on a new connection only if
+it is safe to do so, i.e. if the statement only reads data - `?`, `^`, and `%` statements and statements
+annotated with `read only` - or if it is annotated with `idempotent`. `?` statements are not retried if some of the rows were
+already passed to the callback.
+
+The connection of the client is driven by a task spawned on the current tokio runtime.
+
+```rust , ignore
+let db = ReconnectingClient::connect(config, NoTls).await?;
+let rows = db.get_top_sales("London", 2).await?;
+```
+*/
+pub struct ReconnectingClient {
+    connect: Box<Connect>,
+    client: ::tokio::sync::Mutex<Connection>,
+}
+
+struct Connection {
+    client: ::std::sync::Arc<::tokio_postgres::Client>,
+    terminated: bool,
+}
+
+impl ReconnectingClient {
+    /// Connects to the database and returns the client that will reconnect using the same configuration.
+    pub async fn connect<T>(config: ::tokio_postgres::Config, tls: T) -> Result<Self, ::tokio_postgres::Error>
+    where
+        T: ::tokio_postgres::tls::MakeTlsConnect<::tokio_postgres::Socket> + Clone + Send + Sync + 'static,
+        T::Stream: Send + 'static,
+        T::TlsConnect: Send,
+        <T::TlsConnect as ::tokio_postgres::tls::TlsConnect<::tokio_postgres::Socket>>::Future: Send,
+    {
+        let connect = move || -> ::std::pin::Pin<Box<dyn ::std::future::Future<Output = _> + Send>> {
+            let config = config.clone();
+            let tls = tls.clone();
+            Box::pin(async move {
+                let (client, conn) = config.connect(tls).await?;
+                ::tokio::spawn(conn);
+                Ok(client)
+            })
+        };
+        let client = ::std::sync::Arc::new(connect().await?);
+        Ok(Self { connect: Box::new(connect), client: ::tokio::sync::Mutex::new(Connection { client, terminated: false }) })
+    }
+
+    /// Returns the connected client, reconnecting if the connection was closed.
+    pub async fn connected(&self) -> Result<::std::sync::Arc<::tokio_postgres::Client>, ::tokio_postgres::Error> {
+        let mut conn = self.client.lock().await;
+        if conn.terminated || conn.client.is_closed() {
+            conn.client = ::std::sync::Arc::new((self.connect)().await?);
+            conn.terminated = false;
+        }
+        Ok(conn.client.clone())
+    }
+
+    /// Replaces the failed client with a newly connected one unless it has been replaced already.
+    pub async fn reconnect(&self, failed: &::std::sync::Arc<::tokio_postgres::Client>) -> Result<::std::sync::Arc<::tokio_postgres::Client>, ::tokio_postgres::Error> {
+        let mut conn = self.client.lock().await;
+        if ::std::sync::Arc::ptr_eq(&conn.client, failed) {
+            conn.client = ::std::sync::Arc::new((self.connect)().await?);
+            conn.terminated = false;
+        }
+        Ok(conn.client.clone())
+    }
+
+    /// Records that the server terminated the session of the client, which the client might not have noticed yet.
+    #[doc(hidden)]
+    pub async fn session_terminated(&self, client: &::std::sync::Arc<::tokio_postgres::Client>) {
+        let mut conn = self.client.lock().await;
+        if ::std::sync::Arc::ptr_eq(&conn.client, client) {
+            conn.terminated = true;
+        }
+    }
+
+    /// Starts a transaction, reconnecting first if the connection was closed.
+    ///
+    /// Statements executed within the transaction are not retried.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a client returned by `connected` is still held.
+    pub async fn transaction(&mut self) -> Result<::tokio_postgres::Transaction<'_>, ::tokio_postgres::Error> {
+        let conn = self.client.get_mut();
+        if conn.terminated || conn.client.is_closed() {
+            conn.client = ::std::sync::Arc::new((self.connect)().await?);
+            conn.terminated = false;
+        }
+        ::std::sync::Arc::get_mut(&mut conn.client).expect("no calls are in progress").transaction().await
+    }
+}
+
+/**
+A session-level advisory lock that is released when the guard is dropped.
+
+The guard dereferences to the client that holds the lock, thus generated methods can be called
+through it while the lock is held. Like `tokio_postgres::Transaction` the guard sends the unlock
+request without waiting for the response when it is dropped. Use `unlock` to release the lock and
+wait for the result.
+
+```rust , ignore
+let lock = AdvisoryLock::lock(&db, JOB_SCHEDULER_LOCK).await?;
+let jobs = lock.get_due_jobs().await?;
+lock.unlock().await?;
+```
+*/
+pub struct AdvisoryLock<'a> {
+    client: &'a ::tokio_postgres::Client,
+    key: i64,
+    locked: bool,
+}
+
+impl<'a> AdvisoryLock<'a> {
+    /// Acquires the session-level advisory lock, waiting if necessary.
+    pub async fn lock(client: &'a ::tokio_postgres::Client, key: i64) -> Result<AdvisoryLock<'a>, ::tokio_postgres::Error> {
+        client.execute("SELECT pg_advisory_lock($1)", &[&key]).await?;
+        Ok(Self { client, key, locked: true })
+    }
+
+    /// Acquires the session-level advisory lock if it is available. Returns `None` if the lock is held by another session.
+    pub async fn try_lock(client: &'a ::tokio_postgres::Client, key: i64) -> Result<Option<AdvisoryLock<'a>>, ::tokio_postgres::Error> {
+        let locked : bool = client.query_one("SELECT pg_try_advisory_lock($1)", &[&key]).await?.try_get(0)?;
+        Ok(if locked { Some(Self { client, key, locked }) } else { None })
+    }
+
+    /// Returns the lock key.
+    pub fn key(&self) -> i64 {
+        self.key
+    }
+
+    /// Releases the lock.
+    pub async fn unlock(mut self) -> Result<(), ::tokio_postgres::Error> {
+        self.locked = false;
+        self.client.execute("SELECT pg_advisory_unlock($1)", &[&self.key]).await.map(|_| ())
+    }
+}
+
+impl ::std::ops::Deref for AdvisoryLock<'_> {
+    type Target = ::tokio_postgres::Client;
+
+    fn deref(&self) -> &Self::Target {
+        self.client
+    }
+}
+
+impl Drop for AdvisoryLock<'_> {
+    fn drop(&mut self) {
+        if self.locked {
+            // simple query protocol sends the request on the first poll, extended protocol would only send `Parse`
+            let unlock = format!("SELECT pg_advisory_unlock({})", self.key);
+            let mut cx = ::std::task::Context::from_waker(::futures_util::task::noop_waker_ref());
+            let _ = ::std::future::Future::poll(Box::pin(self.client.batch_execute(&unlock)).as_mut(), &mut cx);
+        }
+    }
+}
+
+/// A client or a transaction which queries can be canceled by [`cancel_on_drop`] and [`timeout`].
+pub trait Cancelable {
+    /// Returns the token that cancels the query that the session is executing.
+    fn cancel_token(&self) -> ::tokio_postgres::CancelToken;
+}
+
+impl Cancelable for ::tokio_postgres::Client {
+    fn cancel_token(&self) -> ::tokio_postgres::CancelToken {
+        ::tokio_postgres::Client::cancel_token(self)
+    }
+}
+
+impl Cancelable for ::tokio_postgres::Transaction<'_> {
+    fn cancel_token(&self) -> ::tokio_postgres::CancelToken {
+        ::tokio_postgres::Transaction::cancel_token(self)
+    }
+}
+
+/**
+Calls a generated method and wraps the returned future to cancel the query that it executes if the future
+is dropped before it completes.
+
+Postgres cancels whatever query the session is executing when the cancellation request arrives. Therefore
+the future blocks the thread that drops it until the request is sent - via a separate connection from a
+short-lived thread - and the client, or the transaction, stays borrowed until then.
+
+Note that the server processes the request asynchronously. If the query completes before that, a query that
+is sent via the same session right after the future is dropped might still be the one that gets canceled and
+fail with `SqlState::QUERY_CANCELED`.
+
+```rust , ignore
+let report = include_postgres_sql::cancel_on_drop(&mut db, NoTls, |db| db.get_sales_report(2024));
+let sales : Vec<Sale> = tokio::select! {
+    sales = report => sales?,
+    _ = shutdown.recv() => return Ok(()),
+};
+```
+*/
+pub fn cancel_on_drop<'c, S, C, F, T>(session: &'c mut S, tls: T, call: C) -> CancelOnDrop<F, T>
+where
+    S: Cancelable,
+    C: FnOnce(&'c S) -> F,
+    F: ::std::future::Future + Unpin,
+    T: ::tokio_postgres::tls::MakeTlsConnect<::tokio_postgres::Socket> + Send + 'static,
+    T::Stream: Send,
+    T::TlsConnect: Send,
+    <T::TlsConnect as ::tokio_postgres::tls::TlsConnect<::tokio_postgres::Socket>>::Future: Send,
+{
+    let token = session.cancel_token();
+    let future = call(session);
+    CancelOnDrop { future, cancel: Some((token, tls)), started: false }
+}
+
+/// Future returned by [`cancel_on_drop`].
+pub struct CancelOnDrop<F, T>
+where
+    T: ::tokio_postgres::tls::MakeTlsConnect<::tokio_postgres::Socket> + Send + 'static,
+    T::Stream: Send,
+    T::TlsConnect: Send,
+    <T::TlsConnect as ::tokio_postgres::tls::TlsConnect<::tokio_postgres::Socket>>::Future: Send,
+{
+    future: F,
+    cancel: Option<(::tokio_postgres::CancelToken, T)>,
+    started: bool,
+}
+
+// Neither the token nor the TLS connector are ever pinned
+impl<F, T> Unpin for CancelOnDrop<F, T>
+where
+    F: Unpin,
+    T: ::tokio_postgres::tls::MakeTlsConnect<::tokio_postgres::Socket> + Send + 'static,
+    T::Stream: Send,
+    T::TlsConnect: Send,
+    <T::TlsConnect as ::tokio_postgres::tls::TlsConnect<::tokio_postgres::Socket>>::Future: Send,
+{}
+
+impl<F, T> ::std::future::Future for CancelOnDrop<F, T>
+where
+    F: ::std::future::Future + Unpin,
+    T: ::tokio_postgres::tls::MakeTlsConnect<::tokio_postgres::Socket> + Send + 'static,
+    T::Stream: Send,
+    T::TlsConnect: Send,
+    <T::TlsConnect as ::tokio_postgres::tls::TlsConnect<::tokio_postgres::Socket>>::Future: Send,
+{
+    type Output = F::Output;
+
+    fn poll(self: ::std::pin::Pin<&mut Self>, cx: &mut ::std::task::Context<'_>) -> ::std::task::Poll<Self::Output> {
+        let this = self.get_mut();
+        this.started = true;
+        let poll = ::std::pin::Pin::new(&mut this.future).poll(cx);
+        if poll.is_ready() {
+            this.cancel = None;
+        }
+        poll
+    }
+}
+
+impl<F, T> Drop for CancelOnDrop<F, T>
+where
+    T: ::tokio_postgres::tls::MakeTlsConnect<::tokio_postgres::Socket> + Send + 'static,
+    T::Stream: Send,
+    T::TlsConnect: Send,
+    <T::TlsConnect as ::tokio_postgres::tls::TlsConnect<::tokio_postgres::Socket>>::Future: Send,
+{
+    fn drop(&mut self) {
+        // The query is not sent to the server until the future is polled
+        if !self.started {
+            return;
+        }
+        if let Some((token, tls)) = self.cancel.take() {
+            // The request is sent before the session is released. Sent later it would likely arrive when the
+            // session is already executing the next query and cancel that one instead.
+            let request = ::std::thread::spawn(move || {
+                if let Ok(rt) = ::tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                    let _ = rt.block_on(token.cancel_query(tls));
+                }
+            });
+            let _ = request.join();
+        }
+    }
+}
+
+/**
+Executes a future returned by a generated method with a deadline.
+
+The deadline covers the entire call including, for example, processing of all rows by the `?` method callback.
+When the deadline elapses the future is dropped, the query that it executes is canceled, and the call fails
+with `TimeoutError::Elapsed`.
+
+```rust , ignore
+let sales : Vec<Sale> = include_postgres_sql::timeout(
+    Duration::from_secs(5), &mut db, NoTls, |db| db.get_sales_report(2024)
+).await?;
+```
+
+Like [`cancel_on_drop`] it borrows the client, or the transaction, exclusively for the duration of the call.
+*/
+pub async fn timeout<'c, S, C, F, T, R, E>(duration: ::std::time::Duration, session: &'c mut S, tls: T, call: C) -> Result<R, TimeoutError<E>>
+where
+    S: Cancelable,
+    C: FnOnce(&'c S) -> F,
+    F: ::std::future::Future<Output = Result<R, E>> + Unpin,
+    T: ::tokio_postgres::tls::MakeTlsConnect<::tokio_postgres::Socket> + Send + 'static,
+    T::Stream: Send,
+    T::TlsConnect: Send,
+    <T::TlsConnect as ::tokio_postgres::tls::TlsConnect<::tokio_postgres::Socket>>::Future: Send,
+{
+    match ::tokio::time::timeout(duration, cancel_on_drop(session, tls, call)).await {
+        Ok(res) => res.map_err(TimeoutError::Db),
+        Err(_) => Err(TimeoutError::Elapsed),
+    }
+}
+
+/// Error returned by [`timeout`] - `E` is the error of the generated method, `postgres::Error` or `StatementError`.
+#[derive(Debug)]
+pub enum TimeoutError<E = ::tokio_postgres::Error> {
+    /// The deadline elapsed before the call completed.
+    Elapsed,
+    /// The call failed before the deadline.
+    Db(E),
+}
+
+impl<E: ::std::fmt::Display> ::std::fmt::Display for TimeoutError<E> {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        match self {
+            Self::Elapsed => f.write_str("deadline has elapsed"),
+            Self::Db(err) => err.fmt(f),
+        }
+    }
+}
+
+impl<E: ::std::error::Error + 'static> ::std::error::Error for TimeoutError<E> {
+    fn source(&self) -> Option<&(dyn ::std::error::Error + 'static)> {
+        match self {
+            Self::Elapsed => None,
+            Self::Db(err) => Some(err),
+        }
+    }
+}
+
+impl<E> From<E> for TimeoutError<E> {
+    fn from(err: E) -> Self {
+        Self::Db(err)
+    }
+}
+
+impl From<::tokio_postgres::Error> for TimeoutError<crate::StatementError> {
+    fn from(err: ::tokio_postgres::Error) -> Self {
+        Self::Db(crate::StatementError::from(err))
+    }
+}
+
+/**
+Executes the closure within a transaction and commits the transaction if the future returned by the closure succeeds.
+
+The transaction is started with the isolation level of the `retry` policy. If the closure or the commit
+fail with a serialization failure, a deadlock, or an optimistic lock conflict, the transaction is rolled
+back and the closure is retried as the policy allows.
+
+```rust , ignore
+let retry = Retry::new(IsolationLevel::Serializable);
+let book_id = with_transaction(&mut db, &retry, |tx| Box::pin(async move {
+    let book = tx.add_new_book(isbn, title).await?;
+    tx.loan_books(&[title], user_id).await?;
+    book.try_get("book_id")
+})).await?;
+```
+*/
+pub async fn with_transaction<T, E, F>(client: &mut ::tokio_postgres::Client, retry: &crate::Retry, mut body: F) -> Result<T, E>
+where
+    F: for<'a, 'c> FnMut(&'a ::tokio_postgres::Transaction<'c>) -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = Result<T, E>> + Send + 'a>>,
+    E: From<::tokio_postgres::Error> + 'static,
+{
+    let mut attempt = 1;
+    loop {
+        let tx = client.build_transaction().isolation_level(retry.isolation).start().await?;
+        let res = match body(&tx).await {
+            Ok(data) => tx.commit().await.map(|_| data).map_err(E::from),
+            Err(err) => {
+                let _ = tx.rollback().await;
+                Err(err)
+            }
+        };
+        match res {
+            Err(err) => match retry.next_delay(&err, attempt) {
+                Some(delay) => ::tokio::time::sleep(delay).await,
+                None => return Err(err),
+            },
+            ok => return ok,
+        }
+        attempt += 1;
+    }
+}
+
+/**
+Executes the closure within a transaction of a new session with the test database and rolls the transaction
+back regardless of the outcome of the future returned by the closure.
+
+The session is opened using the specified configuration and TLS connector. Each call connects separately,
+thus tests that use it stay isolated from each other and can run in parallel.
+
+```rust , ignore
+#[tokio::test]
+async fn loan_books() -> Result<(), Box<dyn std::error::Error>> {
+    let config : tokio_postgres::Config = std::env::var("DATABASE_URL")?.parse()?;
+    test_transaction(&config, NoTls, |tx| Box::pin(async move {
+        tx.loan_books(&["War and Peace"], "Sheldon Cooper").await?;
+        let loaned : Vec<LoanedBook> = tx.get_loaned_books("Sheldon Cooper").await?;
+        assert_eq!(loaned.len(), 1);
+        Ok(())
+    })).await
+}
+```
+*/
+pub async fn test_transaction<T, E, F, M>(config: &::tokio_postgres::Config, tls: M, body: F) -> Result<T, E>
+where
+    F: for<'a, 'c> FnOnce(&'a ::tokio_postgres::Transaction<'c>) -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = Result<T, E>> + Send + 'a>>,
+    E: From<::tokio_postgres::Error>,
+    M: ::tokio_postgres::tls::MakeTlsConnect<::tokio_postgres::Socket>,
+    M::Stream: Send + 'static,
+{
+    let (mut client, conn) = config.connect(tls).await?;
+    let conn = ::tokio::spawn(conn);
+    let tx = client.transaction().await?;
+    let res = body(&tx).await;
+    let rolled_back = tx.rollback().await;
+    drop(client);
+    let _ = conn.await;
+    let data = res?;
+    rolled_back?;
+    Ok(data)
+}
+
+/**
+Executes the closure within a transaction and prepares the transaction for two-phase commit under the
+specified global transaction identifier.
+
+The transaction is rolled back if the future returned by the closure fails. Once prepared, the transaction
+is no longer associated with the session and has to be finished by `commit_prepared` or `rollback_prepared`,
+which can be called from any session.
+
+```rust , ignore
+prepare_transaction(&mut orders_db, "order-42", |tx| Box::pin(tx.add_order(42, user_id))).await?;
+prepare_transaction(&mut billing_db, "order-42", |tx| Box::pin(tx.add_invoice(42, amount))).await?;
+commit_prepared(&orders_db, "order-42").await?;
+commit_prepared(&billing_db, "order-42").await?;
+```
+*/
+pub async fn prepare_transaction<T, E, F>(client: &mut ::tokio_postgres::Client, gid: &str, body: F) -> Result<T, E>
+where
+    F: for<'a, 'c> FnOnce(&'a ::tokio_postgres::Transaction<'c>) -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = Result<T, E>> + Send + 'a>>,
+    E: From<::tokio_postgres::Error>,
+{
+    let tx = client.transaction().await?;
+    let data = body(&tx).await?;
+    tx.batch_execute(&crate::two_phase::command("PREPARE TRANSACTION", gid)).await?;
+    // `PREPARE TRANSACTION` has ended the transaction in this session, `COMMIT` only marks `tx` as done
+    tx.commit().await?;
+    Ok(data)
+}
+
+/// Commits the transaction that was prepared for two-phase commit.
+pub async fn commit_prepared(client: &::tokio_postgres::Client, gid: &str) -> Result<(), ::tokio_postgres::Error> {
+    client.batch_execute(&crate::two_phase::command("COMMIT PREPARED", gid)).await
+}
+
+/// Rolls back the transaction that was prepared for two-phase commit.
+pub async fn rollback_prepared(client: &::tokio_postgres::Client, gid: &str) -> Result<(), ::tokio_postgres::Error> {
+    client.batch_execute(&crate::two_phase::command("ROLLBACK PREPARED", gid)).await
+}
+
+/// Returns transactions of the current database that are prepared for two-phase commit, but that are not
+/// committed or rolled back yet. Used to finish transactions that were left in-doubt after a failure.
+pub async fn prepared_transactions(client: &::tokio_postgres::Client) -> Result<Vec<crate::PreparedTransaction>, ::tokio_postgres::Error> {
+    client.query(crate::two_phase::SELECT_PREPARED, &[]).await?
+        .into_iter()
+        .map(<crate::PreparedTransaction as ::std::convert::TryFrom<_>>::try_from)
+        .collect()
+}
@@ -0,0 +1,299 @@
+//! Rows and database errors made by the driver from canned data.
+//!
+//! The driver does not construct rows and errors outside of the protocol exchange, thus they are produced by
+//! a driver connection to an in-memory server that answers every statement with the canned data.
+
+use crate::{util::{Annotations, MethodError}, Param};
+use ::futures_util::task::noop_waker_ref;
+use ::std::{collections::VecDeque, convert::TryFrom, future::Future, io, pin::Pin, task::{Context, Poll}};
+use ::tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use ::tokio_postgres::{config::SslMode, error::SqlState, types::Type, Client, Config, Error, NoTls, Row};
+
+/// Column names and types and the binary encoded values of the canned rows.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Rows {
+    pub columns: Vec<(String, Type)>,
+    pub values: Vec<Vec<Option<Vec<u8>>>>,
+}
+
+/// Database error that a canned call fails with.
+#[derive(Debug, Clone)]
+pub(crate) struct DbFailure {
+    pub code: SqlState,
+    pub message: String,
+    pub constraint: Option<String>,
+}
+
+/// What the in-memory server answers to every statement.
+#[derive(Clone, Copy)]
+pub(crate) enum Reply<'a> {
+    Rows(&'a Rows),
+    Error(&'a DbFailure),
+}
+
+/// Canned result of a generated method call.
+#[doc(hidden)]
+pub struct Answer<'a> {
+    pub(crate) sql_trait: &'static str,
+    pub(crate) name: &'static str,
+    pub(crate) annotations: &'static Annotations,
+    pub(crate) params: &'a [(&'static str, Param<'a>)],
+    pub(crate) rows: Rows,
+    pub(crate) num_rows: u64,
+    pub(crate) error: Option<DbFailure>,
+}
+
+impl Answer<'_> {
+    fn reply(&self) -> Reply<'_> {
+        match &self.error {
+            Some(failure) => Reply::Error(failure),
+            None => Reply::Rows(&self.rows),
+        }
+    }
+
+    fn method_error<M: MethodError>(&self, err: Error) -> M {
+        M::from_call(err, self.sql_trait, self.name, self.annotations, "", self.params)
+    }
+
+    /// Returns the rows of a `?` or `%` method call.
+    pub fn fetch<M: MethodError>(&self) -> Result<Vec<Row>, M> {
+        query(self.reply()).map_err(|err| self.method_error(err))
+    }
+
+    /// Returns the result of a `^` method call.
+    #[cfg(not(feature = "tokio"))]
+    pub fn rows<'r, M: MethodError>(self) -> Result<crate::RowIter<'r>, M> {
+        self.fetch().map(crate::RowIter::canned)
+    }
+
+    /// Returns the result of a `^` method call.
+    #[cfg(feature = "tokio")]
+    pub fn rows<M: MethodError>(self) -> Result<crate::RowStream, M> {
+        self.fetch().map(crate::RowStream::canned)
+    }
+
+    /// Returns the result of a `->` method call.
+    pub fn row<M: MethodError>(self) -> Result<Row, M> {
+        query_one(self.reply()).map_err(|err| self.method_error(err))
+    }
+
+    /// Returns the result of a `!` method call.
+    pub fn rows_affected<M: MethodError>(self) -> Result<u64, M> {
+        match &self.error {
+            Some(failure) => Err(self.method_error(db_error(failure))),
+            None => Ok(self.num_rows),
+        }
+    }
+}
+
+/// Passes the fetched rows to the row callback of a `?` method.
+#[doc(hidden)]
+pub fn process<F, E, M>(rows: Result<Vec<Row>, M>, mut row_cb: F) -> Result<(), E>
+where F: FnMut(Row) -> Result<(), E>, E: From<M>
+{
+    for row in rows? {
+        row_cb(row)?;
+    }
+    Ok(())
+}
+
+/// Converts the fetched rows of a `%` method.
+#[doc(hidden)]
+pub fn collect<R, E, M>(rows: Result<Vec<Row>, M>) -> Result<Vec<R>, E>
+where R: TryFrom<Row>, E: From<R::Error> + From<M>
+{
+    let mut res = Vec::new();
+    for row in rows? {
+        res.push(R::try_from(row)?);
+    }
+    Ok(res)
+}
+
+/// Returns all rows of the reply.
+pub(crate) fn query(reply: Reply) -> Result<Vec<Row>, Error> {
+    exchange(reply, |client| Box::pin(client.query("", &[])))
+}
+
+/// Returns the only row of the reply. Fails the same way `query_one` does when the reply has no or several rows.
+pub(crate) fn query_one(reply: Reply) -> Result<Row, Error> {
+    exchange(reply, |client| Box::pin(client.query_one("", &[])))
+}
+
+/// Returns the database error that the driver reports for the failure.
+pub(crate) fn db_error(failure: &DbFailure) -> Error {
+    match query(Reply::Error(failure)) {
+        Err(err) => err,
+        Ok(_) => unreachable!("statement succeeded despite the error reply"),
+    }
+}
+
+type Fetch<'c, T> = Pin<Box<dyn Future<Output = Result<T, Error>> + 'c>>;
+
+/// Connects the driver to the in-memory server and drives the `fetch` to completion.
+///
+/// The server answers every message as soon as it is written, so the exchange does not wait for anything and
+/// the futures are simply polled in turns until the fetch completes.
+fn exchange<T>(reply: Reply, fetch: impl for<'c> FnOnce(&'c Client) -> Fetch<'c, T>) -> Result<T, Error> {
+    let mut cx = Context::from_waker(noop_waker_ref());
+    let mut config = Config::new();
+    config.user("canned").ssl_mode(SslMode::Disable);
+    let mut connect = Box::pin(config.connect_raw(Server::new(reply), NoTls));
+    let (client, connection) = loop {
+        if let Poll::Ready(res) = connect.as_mut().poll(&mut cx) {
+            break res?;
+        }
+    };
+    let mut connection = Box::pin(connection);
+    let mut fetch = fetch(&client);
+    loop {
+        if let Poll::Ready(res) = fetch.as_mut().poll(&mut cx) {
+            return res;
+        }
+        if let Poll::Ready(Err(err)) = connection.as_mut().poll(&mut cx) {
+            return Err(err);
+        }
+    }
+}
+
+/// In-memory server that answers the messages of the extended query protocol with the reply.
+struct Server<'a> {
+    reply: Reply<'a>,
+    started: bool,
+    failed: bool,
+    input: Vec<u8>,
+    output: VecDeque<u8>,
+}
+
+impl<'a> Server<'a> {
+    fn new(reply: Reply<'a>) -> Self {
+        Self { reply, started: false, failed: false, input: Vec::new(), output: VecDeque::new() }
+    }
+
+    /// Answers all complete messages that the driver has written so far.
+    fn answer(&mut self) {
+        let mut pos = 0;
+        loop {
+            let (tag, start) = if self.started { (self.input.get(pos).copied(), pos + 1) } else { (None, pos) };
+            let len = match self.input.get(start..start + 4) {
+                Some(len) => u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize,
+                None => break,
+            };
+            if self.input.len() < start + len {
+                break;
+            }
+            pos = start + len;
+            match tag {
+                None => {
+                    self.started = true;
+                    self.message(b'R', &0i32.to_be_bytes());
+                    self.message(b'Z', b"I");
+                }
+                Some(b'S') => {
+                    self.failed = false;
+                    self.message(b'Z', b"I");
+                }
+                Some(_) if self.failed => {}
+                Some(tag) => self.answer_message(tag),
+            }
+        }
+        self.input.drain(..pos);
+    }
+
+    fn answer_message(&mut self, tag: u8) {
+        let rows = match self.reply {
+            Reply::Rows(rows) => rows,
+            Reply::Error(failure) => {
+                let mut body = Vec::new();
+                for (field, value) in [(b'S', "ERROR"), (b'V', "ERROR"), (b'C', failure.code.code()), (b'M', &failure.message)] {
+                    body.push(field);
+                    push_str(&mut body, value);
+                }
+                if let Some(constraint) = &failure.constraint {
+                    body.push(b'n');
+                    push_str(&mut body, constraint);
+                }
+                body.push(0);
+                self.message(b'E', &body);
+                self.failed = true;
+                return;
+            }
+        };
+        match tag {
+            b'P' => self.message(b'1', &[]),
+            b'D' => {
+                self.message(b't', &0i16.to_be_bytes());
+                let mut body = (rows.columns.len() as i16).to_be_bytes().to_vec();
+                for (name, ty) in &rows.columns {
+                    push_str(&mut body, name);
+                    body.extend_from_slice(&0i32.to_be_bytes());
+                    body.extend_from_slice(&0i16.to_be_bytes());
+                    body.extend_from_slice(&ty.oid().to_be_bytes());
+                    body.extend_from_slice(&(-1i16).to_be_bytes());
+                    body.extend_from_slice(&(-1i32).to_be_bytes());
+                    body.extend_from_slice(&1i16.to_be_bytes());
+                }
+                self.message(b'T', &body);
+            }
+            b'B' => self.message(b'2', &[]),
+            b'E' => {
+                for values in &rows.values {
+                    let mut body = (values.len() as i16).to_be_bytes().to_vec();
+                    for value in values {
+                        match value {
+                            Some(value) => {
+                                body.extend_from_slice(&(value.len() as i32).to_be_bytes());
+                                body.extend_from_slice(value);
+                            }
+                            None => body.extend_from_slice(&(-1i32).to_be_bytes()),
+                        }
+                    }
+                    self.message(b'D', &body);
+                }
+                let mut body = Vec::new();
+                push_str(&mut body, &format!("SELECT {}", rows.values.len()));
+                self.message(b'C', &body);
+            }
+            b'C' => self.message(b'3', &[]),
+            _ => {}
+        }
+    }
+
+    fn message(&mut self, tag: u8, body: &[u8]) {
+        self.output.push_back(tag);
+        self.output.extend(((body.len() + 4) as u32).to_be_bytes());
+        self.output.extend(body);
+    }
+}
+
+fn push_str(buf: &mut Vec<u8>, s: &str) {
+    buf.extend_from_slice(s.as_bytes());
+    buf.push(0);
+}
+
+impl AsyncRead for Server<'_> {
+    fn poll_read(mut self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        if self.output.is_empty() {
+            return Poll::Pending;
+        }
+        let len = buf.remaining().min(self.output.len());
+        let bytes: Vec<u8> = self.output.drain(..len).collect();
+        buf.put_slice(&bytes);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for Server<'_> {
+    fn poll_write(mut self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        self.input.extend_from_slice(buf);
+        self.answer();
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
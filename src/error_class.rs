@@ -0,0 +1,120 @@
+use std::fmt;
+use tokio_postgres::error::{DbError, SqlState};
+
+/**
+Classification of an error returned by a generated method.
+
+```rust , ignore
+match ErrorClass::of(&err) {
+    ErrorClass::UniqueViolation { constraint, .. } if constraint.as_deref() == Some("library_isbn_key") => {
+        return Err(LibraryError::DuplicateIsbn);
+    }
+    ErrorClass::SerializationFailure | ErrorClass::Deadlock => return retry(),
+    _ => return Err(err.into()),
+}
+```
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// `23505` (unique_violation)
+    UniqueViolation {
+        constraint: Option<String>,
+        table: Option<String>,
+    },
+    /// `23503` (foreign_key_violation)
+    ForeignKeyViolation {
+        constraint: Option<String>,
+        table: Option<String>,
+    },
+    /// `23514` (check_violation)
+    CheckViolation {
+        constraint: Option<String>,
+        table: Option<String>,
+    },
+    /// `23502` (not_null_violation)
+    NotNullViolation {
+        table: Option<String>,
+        column: Option<String>,
+    },
+    /// `40001` (serialization_failure)
+    SerializationFailure,
+    /// `40P01` (deadlock_detected)
+    Deadlock,
+    /// An `optimistic lock` statement did not update the row because it was changed after it was read
+    /// (see `CheckFailure::Conflict`).
+    Conflict,
+    /// `57014` (query_canceled) - the statement was canceled by the client or by `statement_timeout`.
+    QueryCanceled,
+    /// The connection to the server was closed or lost, or the server terminated the session.
+    ConnectionClosed,
+    /// Any other error. Holds the SQLSTATE of the error if the error was reported by the database.
+    Other(Option<SqlState>),
+}
+
+impl ErrorClass {
+    /// Returns the class of the specified error.
+    ///
+    /// The error is classified by the first Postgres error found in its chain of sources, thus errors
+    /// that wrap the error of a generated method, like `StatementError`, can be classified as well.
+    /// A `StatementError` of a failed check is classified by the check.
+    pub fn of(err: &(dyn std::error::Error + 'static)) -> Self {
+        let mut source = Some(err);
+        while let Some(err) = source {
+            if let Some(failure) = err.downcast_ref::<crate::StatementError>().and_then(crate::StatementError::check_failure) {
+                return match failure {
+                    crate::CheckFailure::Conflict => Self::Conflict,
+                    _ => Self::Other(None),
+                };
+            }
+            if let Some(err) = err.downcast_ref::<tokio_postgres::Error>() {
+                return Self::of_driver_error(err);
+            }
+            source = err.source();
+        }
+        Self::Other(None)
+    }
+
+    fn of_driver_error(err: &tokio_postgres::Error) -> Self {
+        let io_failed = std::error::Error::source(err).is_some_and(|err| err.is::<std::io::Error>());
+        if io_failed || crate::util::session_terminated(err) {
+            return Self::ConnectionClosed;
+        }
+        match err.as_db_error() {
+            Some(db_err) => Self::of_db_error(db_err),
+            None => Self::Other(err.code().cloned()),
+        }
+    }
+
+    fn of_db_error(err: &DbError) -> Self {
+        let constraint = || err.constraint().map(str::to_string);
+        let table = || err.table().map(str::to_string);
+        match err.code() {
+            code if *code == SqlState::UNIQUE_VIOLATION => Self::UniqueViolation { constraint: constraint(), table: table() },
+            code if *code == SqlState::FOREIGN_KEY_VIOLATION => Self::ForeignKeyViolation { constraint: constraint(), table: table() },
+            code if *code == SqlState::CHECK_VIOLATION => Self::CheckViolation { constraint: constraint(), table: table() },
+            code if *code == SqlState::NOT_NULL_VIOLATION => Self::NotNullViolation { table: table(), column: err.column().map(str::to_string) },
+            code if *code == SqlState::T_R_SERIALIZATION_FAILURE => Self::SerializationFailure,
+            code if *code == SqlState::T_R_DEADLOCK_DETECTED => Self::Deadlock,
+            code if *code == SqlState::QUERY_CANCELED => Self::QueryCanceled,
+            code => Self::Other(Some(code.clone())),
+        }
+    }
+}
+
+impl fmt::Display for ErrorClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UniqueViolation { .. } => f.write_str("unique violation"),
+            Self::ForeignKeyViolation { .. } => f.write_str("foreign key violation"),
+            Self::CheckViolation { .. } => f.write_str("check violation"),
+            Self::NotNullViolation { .. } => f.write_str("not null violation"),
+            Self::SerializationFailure => f.write_str("serialization failure"),
+            Self::Deadlock => f.write_str("deadlock detected"),
+            Self::Conflict => f.write_str("optimistic lock conflict"),
+            Self::QueryCanceled => f.write_str("query canceled"),
+            Self::ConnectionClosed => f.write_str("connection closed"),
+            Self::Other(Some(code)) => write!(f, "error {}", code.code()),
+            Self::Other(None) => f.write_str("other error"),
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use std::{fmt, sync::{atomic::{AtomicBool, Ordering}, Arc, RwLock}, time::Duration};
+use tokio_postgres::error::SqlState;
+use crate::util::{Failure, RowCount};
+
+/// Value of a statement parameter as seen by the `Interceptor`.
+#[cfg(not(feature = "tokio"))]
+pub type Param<'a> = &'a dyn fmt::Debug;
+
+/// Value of a statement parameter as seen by the `Interceptor`.
+#[cfg(feature = "tokio")]
+pub type Param<'a> = &'a (dyn fmt::Debug + Sync);
+
+/// A generated method call as seen by the `Interceptor`.
+#[derive(Debug, Clone, Copy)]
+pub struct Call<'a> {
+    /// Name of the generated trait, like `LibrarySql`.
+    pub sql_trait: &'static str,
+    /// Name of the statement in the SQL file.
+    pub name: &'static str,
+    /// SQL text of the statement as it is sent to the database.
+    pub statement: &'a str,
+    /// Names of the statement parameters and their values.
+    pub parameters: &'a [(&'static str, Param<'a>)],
+}
+
+/// Outcome of a generated method call.
+#[derive(Debug, Clone, Copy)]
+pub enum Outcome<'a> {
+    /// The call succeeded. Holds the number of rows processed by the `?` callback, collected by `%`,
+    /// returned by `->` or `^`, or affected by `!` statements.
+    Succeeded(Option<u64>),
+    /// The call failed.
+    Failed(&'a tokio_postgres::Error),
+    /// The call failed a check of the statement annotations, like `try_advisory_lock:`, or the `before` hook rejected it.
+    CheckFailed(&'a crate::CheckFailure),
+    /// The row callback of a `?` method or the row conversion of a `%` method failed with an application error.
+    Aborted,
+}
+
+impl<'a> Outcome<'a> {
+    #[doc(hidden)]
+    pub fn of<T: RowCount, X: Failure>(res: &'a Result<T, X>) -> Self {
+        match res {
+            Ok(data) => Self::Succeeded(data.row_count()),
+            Err(err) => match (err.db_error(), err.check_failure()) {
+                (Some(err), _) => Self::Failed(err),
+                (None, Some(failure)) => Self::CheckFailed(failure),
+                (None, None) => Self::Aborted,
+            },
+        }
+    }
+}
+
+/// Tells the generated method to fail the call with `CheckFailure::Rejected` instead of executing the statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rejection {
+    code: SqlState,
+    message: String,
+}
+
+impl Rejection {
+    /// Creates a rejection with the specified SQLSTATE and error message.
+    pub fn new(code: SqlState, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+
+    /// Returns the SQLSTATE that the interceptor rejected the call with.
+    pub fn code(&self) -> &SqlState {
+        &self.code
+    }
+
+    /// Returns the message that the interceptor rejected the call with.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.message, self.code.code())
+    }
+}
+
+/**
+Hooks that are called around every call of the methods that `impl_sql` generates when it is given
+the `interceptor` option.
+
+```rust , ignore
+struct Audit;
+
+impl Interceptor for Audit {
+    fn before(&self, call: &Call) -> Result<(), Rejection> {
+        log::info!("{}::{} with {:?}", call.sql_trait, call.name, call.parameters);
+        Ok(())
+    }
+}
+
+set_interceptor(Audit);
+```
+*/
+#[cfg(not(feature = "tokio"))]
+pub trait Interceptor: Send + Sync + 'static {
+    /// Called before the statement is executed. When it returns a `Rejection` the statement is not
+    /// executed and the call fails with `CheckFailure::Rejected`.
+    fn before(&self, _call: &Call) -> Result<(), Rejection> {
+        Ok(())
+    }
+
+    /// Called when the call is completed.
+    fn after(&self, _call: &Call, _outcome: Outcome, _elapsed: Duration) {}
+}
+
+/// Future returned by the `Interceptor` hooks.
+#[cfg(feature = "tokio")]
+pub type BoxFuture<'a, T> = std::pin::Pin<Box<dyn std::future::Future<Output = T> + Send + 'a>>;
+
+/**
+Hooks that are called around every call of the methods that `impl_sql` generates when it is given
+the `interceptor` option.
+
+```rust , ignore
+struct Audit;
+
+impl Interceptor for Audit {
+    fn before<'a>(&'a self, call: &'a Call<'a>) -> BoxFuture<'a, Result<(), Rejection>> {
+        Box::pin(async move {
+            audit_log(call.sql_trait, call.name, format!("{:?}", call.parameters)).await;
+            Ok(())
+        })
+    }
+}
+
+set_interceptor(Audit);
+```
+*/
+#[cfg(feature = "tokio")]
+pub trait Interceptor: Send + Sync + 'static {
+    /// Called before the statement is executed. When it resolves to a `Rejection` the statement is not
+    /// executed and the call fails with `CheckFailure::Rejected`.
+    fn before<'a>(&'a self, _call: &'a Call<'a>) -> BoxFuture<'a, Result<(), Rejection>> {
+        Box::pin(std::future::ready(Ok(())))
+    }
+
+    /// Called when the call is completed.
+    fn after<'a>(&'a self, _call: &'a Call<'a>, _outcome: Outcome<'a>, _elapsed: Duration) -> BoxFuture<'a, ()> {
+        Box::pin(std::future::ready(()))
+    }
+}
+
+static INTERCEPTOR: RwLock<Option<Arc<dyn Interceptor>>> = RwLock::new(None);
+
+/// Tells whether an interceptor is set, so that calls do not take the lock when it is not.
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/**
+Makes generated methods call the `interceptor` hooks. Replaces the previously set interceptor.
+
+Only methods that `impl_sql` generates when it is given the `interceptor` option call the hooks. The option
+requires the `statement_error` option as calls that the interceptor rejects fail with `CheckFailure::Rejected`:
+
+```compile_fail
+use include_postgres_sql::include_sql;
+
+macro_rules! impl_sql {
+    ( $($tt:tt)+ ) => {
+        include_postgres_sql::impl_sql!{ #[options(interceptor)] $($tt)+ }
+    };
+}
+
+include_sql!("tests/sql/query_interceptor.sql");
+```
+*/
+pub fn set_interceptor<I: Interceptor>(interceptor: I) {
+    *INTERCEPTOR.write().unwrap_or_else(|err| err.into_inner()) = Some(Arc::new(interceptor));
+    INSTALLED.store(true, Ordering::Release);
+}
+
+/// Stops calling the interceptor hooks.
+pub fn clear_interceptor() {
+    INSTALLED.store(false, Ordering::Release);
+    *INTERCEPTOR.write().unwrap_or_else(|err| err.into_inner()) = None;
+}
+
+/// Returns the interceptor that generated methods call.
+#[doc(hidden)]
+pub fn interceptor() -> Option<Arc<dyn Interceptor>> {
+    if !INSTALLED.load(Ordering::Acquire) {
+        return None;
+    }
+    INTERCEPTOR.read().unwrap_or_else(|err| err.into_inner()).clone()
+}
+
+/// Returns the interceptor that the generated method calls, which is always `None` unless `impl_sql`
+/// is given the `interceptor` option.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! hooks {
+    (yes) => {
+        $crate::interceptor::interceptor()
+    };
+    (no) => {
+        ::std::option::Option::<::std::sync::Arc<dyn $crate::Interceptor>>::None
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! call_params {
+    () => {
+        [] as [(&'static str, $crate::interceptor::Param); 0]
+    };
+    ($($pv:tt $param:ident)+) => {
+        [ $( (::std::stringify!($param), & $param as $crate::interceptor::Param) ),+ ]
+    };
+}
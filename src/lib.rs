@@ -6,10 +6,75 @@ pub use include_sql::include_sql;
 #[doc(hidden)]
 pub mod util;
 
+mod retry;
+pub use retry::{Retry, RetryReason};
+
+mod error_class;
+pub use error_class::ErrorClass;
+
+mod router;
+pub use router::Router;
+
+mod two_phase;
+pub use two_phase::PreparedTransaction;
+
+mod slow_query;
+pub use slow_query::{SlowQuery, SlowQueryLog};
+
+#[doc(hidden)]
+pub mod interceptor;
+pub use interceptor::{clear_interceptor, set_interceptor, Call, Interceptor, Outcome, Param, Rejection};
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+pub use interceptor::BoxFuture;
+
+mod statement_error;
+pub use statement_error::{CheckFailure, StatementError};
+
+#[cfg(feature = "mock")]
+#[doc(hidden)]
+pub mod mock;
+
+#[cfg(feature = "mock")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mock")))]
+pub use mock::{Expectation, MockCall, MockRows};
+
+#[cfg(any(feature = "mock", feature = "replay"))]
+#[doc(hidden)]
+pub mod canned;
+
+#[cfg(feature = "replay")]
+#[doc(hidden)]
+pub mod replay;
+
+#[cfg(feature = "sqlcommenter")]
+mod sql_comment;
+
+#[cfg(feature = "sqlcommenter")]
+#[cfg_attr(docsrs, doc(cfg(feature = "sqlcommenter")))]
+pub use sql_comment::{clear_trace_context, set_trace_context};
+
+#[cfg(not(any(feature = "sync", feature = "tokio")))]
+compile_error!("either the `sync` (default) or the `tokio` feature must be enabled");
+
 #[cfg(feature = "tokio")]
 #[doc(hidden)]
 pub mod async_await;
 
-#[cfg(not(feature = "tokio"))]
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+pub use async_await::{
+    cancel_on_drop, commit_prepared, prepare_transaction, prepared_transactions, rollback_prepared, test_transaction, timeout,
+    with_transaction, AdvisoryLock, CancelOnDrop, Cancelable, ReconnectingClient, RowStream, TimeoutError,
+};
+
+#[cfg(all(feature = "sync", not(feature = "tokio")))]
 #[doc(hidden)]
 pub mod sync;
+
+#[cfg(all(feature = "sync", not(feature = "tokio")))]
+pub use sync::{
+    commit_prepared, prepare_transaction, prepared_transactions, rollback_prepared, test_transaction, with_transaction,
+    AdvisoryLock, ReconnectingClient, RowIter,
+};
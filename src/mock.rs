@@ -0,0 +1,245 @@
+use crate::{canned::{Answer, DbFailure}, util::Annotations, Param};
+use ::bytes::BytesMut;
+use ::std::{fmt, sync::Mutex};
+use ::tokio_postgres::{error::SqlState, types::{IsNull, ToSql, Type}};
+
+/// A generated method call recorded by the mock.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockCall {
+    /// Name of the statement in the SQL file.
+    pub name: &'static str,
+    /// Names of the statement parameters and their debug-formatted values.
+    pub parameters: Vec<(&'static str, String)>,
+}
+
+impl fmt::Display for MockCall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}(", self.name)?;
+        for (i, (name, value)) in self.parameters.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{} = {}", name, value)?;
+        }
+        f.write_str(")")
+    }
+}
+
+type Matcher = dyn Fn(&[(&'static str, String)]) -> bool + Send;
+
+/**
+Rows that the matched calls return.
+
+Values are encoded by the driver as the values of statement parameters would be, thus columns can be of any
+type that is built into the driver.
+
+```rust , ignore
+let rows = MockRows::new(&[("book_id", Type::INT4), ("book_title", Type::TEXT)])
+    .row(&[&1, &"War and Peace"])
+    .row(&[&2, &"Anna Karenina"]);
+```
+*/
+#[derive(Debug, Clone, Default)]
+pub struct MockRows {
+    rows: crate::canned::Rows,
+}
+
+impl MockRows {
+    /// Creates rows with the specified column names and types.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a column type is not built into the driver.
+    pub fn new(columns: &[(&str, Type)]) -> Self {
+        for (name, ty) in columns {
+            if Type::from_oid(ty.oid()).is_none() {
+                panic!("column {} is of type {}, which is not built into the driver", name, ty);
+            }
+        }
+        let columns = columns.iter().map(|(name, ty)| (name.to_string(), ty.clone())).collect();
+        Self { rows: crate::canned::Rows { columns, values: Vec::new() } }
+    }
+
+    /// Adds a row with the specified column values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of values does not match the number of columns or a value cannot be encoded as
+    /// a value of the column type.
+    pub fn row(mut self, values: &[&(dyn ToSql + Sync)]) -> Self {
+        let columns = &self.rows.columns;
+        if values.len() != columns.len() {
+            panic!("row has {} values for {} columns", values.len(), columns.len());
+        }
+        let row = columns.iter().zip(values).map(|((name, ty), value)| {
+            let mut buf = BytesMut::new();
+            match value.to_sql_checked(ty, &mut buf) {
+                Ok(IsNull::No) => Some(buf.to_vec()),
+                Ok(IsNull::Yes) => None,
+                Err(err) => panic!("{:?} is not a value of column {}: {}", value, name, err),
+            }
+        }).collect();
+        self.rows.values.push(row);
+        self
+    }
+}
+
+/**
+Expected call of a generated method and what the call returns.
+
+```rust , ignore
+let mut db = MockLibrarySql::new();
+db.expect_loan_books()
+    .with_values(&["[\"War and Peace\"]", "\"Sheldon Cooper\""])
+    .times(1)
+    .affects(1);
+```
+*/
+pub struct Expectation {
+    name: &'static str,
+    matcher: Option<Box<Matcher>>,
+    rows: Option<MockRows>,
+    num_rows: Option<u64>,
+    error: Option<DbFailure>,
+    times: Option<usize>,
+    calls: usize,
+}
+
+impl Expectation {
+    fn new(name: &'static str) -> Self {
+        Self { name, matcher: None, rows: None, num_rows: None, error: None, times: None, calls: 0 }
+    }
+
+    /// Makes the expectation match only the calls which parameters satisfy the matcher. The matcher receives
+    /// the names of the parameters and their debug-formatted values.
+    pub fn with<M>(&mut self, matcher: M) -> &mut Self
+    where M: Fn(&[(&'static str, String)]) -> bool + Send + 'static
+    {
+        self.matcher = Some(Box::new(matcher));
+        self
+    }
+
+    /// Makes the expectation match only the calls which debug-formatted parameter values are equal to `values`.
+    pub fn with_values(&mut self, values: &[&str]) -> &mut Self {
+        let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+        self.with(move |params| params.iter().map(|(_, value)| value).eq(values.iter()))
+    }
+
+    /// Sets the number of calls that the expectation matches.
+    pub fn times(&mut self, times: usize) -> &mut Self {
+        self.times = Some(times);
+        self
+    }
+
+    /// Sets the rows that the matched calls of `?`, `%`, `^` and `->` methods return. `?` methods pass them
+    /// to the row callback and `%` methods convert them into the rows of the caller's type.
+    ///
+    /// Without rows the calls return no rows, which `->` methods report as the driver would - as an error.
+    pub fn returns(&mut self, rows: MockRows) -> &mut Self {
+        self.rows = Some(rows);
+        self
+    }
+
+    /// Sets the number of rows that the matched calls of `!` methods affect. Without it the calls affect no rows.
+    pub fn affects(&mut self, num_rows: u64) -> &mut Self {
+        self.num_rows = Some(num_rows);
+        self
+    }
+
+    /// Makes the matched calls fail with the database error with the specified SQLSTATE code and message.
+    pub fn fails(&mut self, code: SqlState, message: &str) -> &mut Self {
+        self.error = Some(DbFailure { code, message: message.to_string(), constraint: None });
+        self
+    }
+
+    fn matches(&self, name: &str, params: &[(&'static str, String)]) -> bool {
+        self.name == name
+            && !matches!(self.times, Some(times) if self.calls >= times)
+            && !matches!(&self.matcher, Some(matcher) if !matcher(params))
+    }
+}
+
+struct State {
+    expectations: Vec<Expectation>,
+    calls: Vec<MockCall>,
+}
+
+/// Expectations and recorded calls of a generated mock.
+#[doc(hidden)]
+pub struct Mock {
+    sql_trait: &'static str,
+    state: Mutex<State>,
+}
+
+impl Mock {
+    pub fn new(sql_trait: &'static str) -> Self {
+        Self { sql_trait, state: Mutex::new(State { expectations: Vec::new(), calls: Vec::new() }) }
+    }
+
+    /// Adds the expectation of a call of the named method.
+    pub fn expect(&mut self, name: &'static str) -> &mut Expectation {
+        let state = self.state.get_mut().unwrap_or_else(|err| err.into_inner());
+        state.expectations.push(Expectation::new(name));
+        state.expectations.last_mut().expect("added expectation")
+    }
+
+    /// Returns the calls made so far.
+    pub fn calls(&self) -> Vec<MockCall> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner()).calls.clone()
+    }
+
+    /// Verifies that all expectations were satisfied and removes them.
+    ///
+    /// An expectation is satisfied when it matched as many calls as set by `times`, or at least one call if
+    /// the number of calls was not set.
+    pub fn checkpoint(&mut self) {
+        let state = self.state.get_mut().unwrap_or_else(|err| err.into_inner());
+        let expectations = std::mem::take(&mut state.expectations);
+        for expectation in expectations {
+            match expectation.times {
+                Some(times) if expectation.calls != times => panic!(
+                    "{}::{} was expected to be called {} times, but was called {} times",
+                    self.sql_trait, expectation.name, times, expectation.calls
+                ),
+                None if expectation.calls == 0 => panic!(
+                    "{}::{} was expected to be called, but was not", self.sql_trait, expectation.name
+                ),
+                _ => {}
+            }
+        }
+    }
+
+    /// Records the call of the named method and returns what the first matching expectation returns.
+    pub fn call<'a>(&self, name: &'static str, annotations: &'static Annotations, params: &'a [(&'static str, Param<'a>)]) -> Answer<'a> {
+        let parameters: Vec<_> = params.iter().map(|&(name, value)| (name, format!("{:?}", value))).collect();
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+        let call = MockCall { name, parameters };
+        let expectation = match state.expectations.iter_mut().find(|expectation| expectation.matches(name, &call.parameters)) {
+            Some(expectation) => expectation,
+            None => {
+                drop(state);
+                panic!("unexpected call {}::{}", self.sql_trait, call)
+            }
+        };
+        expectation.calls += 1;
+        let answer = Answer {
+            sql_trait: self.sql_trait,
+            name,
+            annotations,
+            params,
+            rows: expectation.rows.clone().unwrap_or_default().rows,
+            num_rows: expectation.num_rows.unwrap_or(0),
+            error: expectation.error.clone(),
+        };
+        state.calls.push(call);
+        answer
+    }
+}
+
+impl Drop for Mock {
+    fn drop(&mut self) {
+        if !std::thread::panicking() {
+            self.checkpoint();
+        }
+    }
+}
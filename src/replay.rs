@@ -0,0 +1,672 @@
+use crate::{canned::{self, Answer, DbFailure, Reply, Rows}, util::{Annotations, MethodError}, Param};
+use ::bytes::BytesMut;
+use ::std::{
+    collections::{HashMap, VecDeque},
+    convert::TryFrom,
+    error::{self, Error as _},
+    fmt::{self, Write as _},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+use ::tokio_postgres::{error::SqlState, types::{FromSql, IsNull, ToSql, Type}, Error, Row};
+
+/// Environment variable that makes `start` record even if the fixture exists.
+const RECORD_VAR: &str = "INCLUDE_POSTGRES_SQL_RECORD";
+
+/// Types with OIDs below this one are built into the database.
+const FIRST_NORMAL_OID: u32 = 16384;
+
+/// Recorded result of a generated method call.
+#[derive(Debug, Clone)]
+enum Recorded {
+    Rows(Rows),
+    Affected(u64),
+    Failed(DbFailure),
+}
+
+enum Mode<C> {
+    Record { client: C, recorder: Recorder },
+    Play { answers: Mutex<HashMap<String, VecDeque<Recorded>>> },
+}
+
+/// Records results of generated method calls to a fixture file or answers the calls with the recorded results.
+///
+/// `Replay<TraitName>`, that `impl_sql` generates when the `replay` feature is selected, implements the trait
+/// with it.
+#[doc(hidden)]
+pub struct Replay<C> {
+    sql_trait: &'static str,
+    path: PathBuf,
+    mode: Mode<C>,
+}
+
+/// Tells whether the fixture exists and the `INCLUDE_POSTGRES_SQL_RECORD` environment variable does not ask
+/// to record it again.
+pub fn is_recorded(path: &Path) -> bool {
+    path.exists() && std::env::var_os(RECORD_VAR).is_none()
+}
+
+impl<C> Replay<C> {
+    /// Replays the fixture if it exists, otherwise records one with the client that `connect` returns.
+    /// Setting the `INCLUDE_POSTGRES_SQL_RECORD` environment variable makes it record the fixture again.
+    pub fn start<F, E>(sql_trait: &'static str, path: impl AsRef<Path>, connect: F) -> Result<Self, Box<dyn error::Error>>
+    where F: FnOnce() -> Result<C, E>, E: Into<Box<dyn error::Error>>
+    {
+        let path = path.as_ref();
+        if is_recorded(path) {
+            Ok(Self::play(sql_trait, path)?)
+        } else {
+            Ok(Self::record(sql_trait, path, connect().map_err(Into::into)?))
+        }
+    }
+
+    /// Passes calls to the client and writes their results into the fixture when dropped.
+    pub fn record(sql_trait: &'static str, path: impl AsRef<Path>, client: C) -> Self {
+        let mode = Mode::Record { client, recorder: Recorder { sql_trait, calls: Mutex::new(Vec::new()) } };
+        Self { sql_trait, path: path.as_ref().to_path_buf(), mode }
+    }
+
+    /// Answers calls with the results recorded in the fixture.
+    pub fn play(sql_trait: &'static str, path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        let mut answers = HashMap::<_, VecDeque<_>>::new();
+        for (call, recorded) in parse(&text).map_err(|(line, msg)| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("{}:{}: {}", path.display(), line, msg))
+        })? {
+            answers.entry(call).or_default().push_back(recorded);
+        }
+        let mode = Mode::Play { answers: Mutex::new(answers) };
+        Ok(Self { sql_trait, path: path.to_path_buf(), mode })
+    }
+
+    /// Tells whether calls are passed to the database and recorded.
+    pub fn is_recording(&self) -> bool {
+        matches!(self.mode, Mode::Record { .. })
+    }
+
+    /// Returns the client and the recorder of its call results when recording.
+    pub fn recorder(&self) -> Option<(&C, &Recorder)> {
+        match &self.mode {
+            Mode::Record { client, recorder } => Some((client, recorder)),
+            Mode::Play { .. } => None,
+        }
+    }
+
+    /// Returns the client and the recorder of its call results when recording.
+    pub fn recorder_mut(&mut self) -> Option<(&mut C, &Recorder)> {
+        match &mut self.mode {
+            Mode::Record { client, recorder } => Some((client, recorder)),
+            Mode::Play { .. } => None,
+        }
+    }
+
+    /// Returns the recorded result of the call.
+    ///
+    /// Results of a call that was recorded several times are returned in the recorded order and the last one
+    /// answers any further calls. A call that was not recorded fails with the `XX000` (internal error) SQLSTATE.
+    pub fn answer<'a>(&self, name: &'static str, annotations: &'static Annotations, params: &'a [(&'static str, Param<'a>)]) -> Answer<'a> {
+        let call = call_text(name, params);
+        let recorded = match &self.mode {
+            Mode::Play { answers } => {
+                let mut answers = answers.lock().unwrap_or_else(|err| err.into_inner());
+                answers.get_mut(&call).and_then(|results| if results.len() > 1 { results.pop_front() } else { results.front().cloned() })
+            }
+            Mode::Record { .. } => None,
+        };
+        let mut answer = Answer { sql_trait: self.sql_trait, name, annotations, params, rows: Rows::default(), num_rows: 0, error: None };
+        match recorded {
+            Some(Recorded::Rows(rows)) => answer.rows = rows,
+            Some(Recorded::Affected(num_rows)) => answer.num_rows = num_rows,
+            Some(Recorded::Failed(failure)) => answer.error = Some(failure),
+            None => {
+                let message = format!("{}::{} was not recorded in {}", self.sql_trait, call, self.path.display());
+                answer.error = Some(DbFailure { code: SqlState::INTERNAL_ERROR, message, constraint: None });
+            }
+        }
+        answer
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let calls = match &self.mode {
+            Mode::Record { recorder, .. } => recorder.calls.lock().unwrap_or_else(|err| err.into_inner()),
+            Mode::Play { .. } => return Ok(()),
+        };
+        let mut text = format!(
+            "# Results of {} calls. Delete this file or set {} to record them again.\n",
+            self.sql_trait, RECORD_VAR
+        );
+        for (call, recorded) in calls.iter() {
+            write_call(&mut text, call, recorded).expect("written to string");
+        }
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, text)
+    }
+}
+
+impl<C> Drop for Replay<C> {
+    fn drop(&mut self) {
+        // A test that failed might have stopped midway. Its recording is incomplete.
+        if !std::thread::panicking() {
+            if let Err(err) = self.save() {
+                panic!("cannot write {}: {}", self.path.display(), err);
+            }
+        }
+    }
+}
+
+/// Records results of the calls that the client executes.
+#[doc(hidden)]
+pub struct Recorder {
+    sql_trait: &'static str,
+    calls: Mutex<Vec<(String, Recorded)>>,
+}
+
+impl Recorder {
+    fn save_call(&self, call: String, recorded: Recorded) {
+        self.calls.lock().unwrap_or_else(|err| err.into_inner()).push((call, recorded));
+    }
+
+    fn save_failure<M: MethodError>(&self, call: String, err: &M) {
+        if let Some(failure) = err.driver_error().and_then(db_failure) {
+            self.save_call(call, Recorded::Failed(failure));
+        }
+    }
+
+    /// Records the rows that were passed to the row callback of a `?` method.
+    ///
+    /// When the callback fails the rows it has seen so far are recorded, so the replayed callback fails the same way.
+    pub fn record_processed<E, M>(&self, recording: Recording, res: Result<(), CallbackError<E, M>>) -> Result<(), E>
+    where E: From<M>, M: MethodError
+    {
+        match res {
+            Ok(()) => {
+                self.save_call(recording.call, Recorded::Rows(recording.rows));
+                Ok(())
+            }
+            Err(CallbackError::Callback(err)) => {
+                self.save_call(recording.call, Recorded::Rows(recording.rows));
+                Err(err)
+            }
+            Err(CallbackError::Call(err)) => {
+                self.save_failure(recording.call, &err);
+                Err(E::from(err))
+            }
+        }
+    }
+
+    /// Records the rows that a `%` method retrieved.
+    pub fn record_collected<M: MethodError>(&self, mut recording: Recording, res: Result<Vec<RawRow>, M>) -> Result<Vec<Row>, M> {
+        match res {
+            Ok(rows) => {
+                let rows: Vec<Row> = rows.into_iter().map(|RawRow(row)| row).collect();
+                for row in &rows {
+                    recording.row(row);
+                }
+                self.save_call(recording.call, Recorded::Rows(recording.rows));
+                Ok(rows)
+            }
+            Err(err) => {
+                self.save_failure(recording.call, &err);
+                Err(err)
+            }
+        }
+    }
+
+    /// Records the row that a `->` method retrieved.
+    ///
+    /// When the statement returned no rows the call is recorded as such, so the replayed call fails the same way.
+    pub fn record_row<M: MethodError>(&self, mut recording: Recording, res: Result<Row, M>) -> Result<Row, M> {
+        match &res {
+            Ok(row) => {
+                recording.row(row);
+                self.save_call(recording.call, Recorded::Rows(recording.rows));
+            }
+            Err(err) if err.driver_error().is_some_and(is_row_count_error) => {
+                self.save_call(recording.call, Recorded::Rows(recording.rows));
+            }
+            Err(err) => self.save_failure(recording.call, err),
+        }
+        res
+    }
+
+    /// Records the number of rows that a `!` method affected.
+    pub fn record_affected<M: MethodError>(&self, recording: Recording, res: Result<u64, M>) -> Result<u64, M> {
+        match &res {
+            Ok(num_rows) => self.save_call(recording.call, Recorded::Affected(*num_rows)),
+            Err(err) => self.save_failure(recording.call, err),
+        }
+        res
+    }
+
+    /// Records the rows of a `^` method by reading all of them, and returns the rows that were read.
+    #[cfg(not(feature = "tokio"))]
+    pub fn record_rows<'r, M: MethodError>(&self, mut recording: Recording, annotations: &Annotations, res: Result<crate::RowIter<'_>, M>) -> Result<crate::RowIter<'r>, M> {
+        use ::postgres::fallible_iterator::FallibleIterator;
+
+        let mut rows = Vec::new();
+        let mut iter = match res {
+            Ok(iter) => iter,
+            Err(err) => {
+                self.save_failure(recording.call, &err);
+                return Err(err);
+            }
+        };
+        loop {
+            match iter.next() {
+                Ok(Some(row)) => {
+                    recording.row(&row);
+                    rows.push(row);
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    let err = M::from_call(err, self.sql_trait, recording.name, annotations, "", &[]);
+                    self.save_failure(recording.call, &err);
+                    return Err(err);
+                }
+            }
+        }
+        self.save_call(recording.call, Recorded::Rows(recording.rows));
+        Ok(crate::RowIter::canned(rows))
+    }
+
+    /// Records the rows of a `^` method by reading all of them, and returns the rows that were read.
+    #[cfg(feature = "tokio")]
+    pub async fn record_rows<M: MethodError>(&self, mut recording: Recording, annotations: &Annotations, res: Result<crate::RowStream, M>) -> Result<crate::RowStream, M> {
+        use ::futures_util::TryStreamExt;
+
+        let mut rows = Vec::new();
+        let mut stream = match res {
+            Ok(stream) => stream,
+            Err(err) => {
+                self.save_failure(recording.call, &err);
+                return Err(err);
+            }
+        };
+        loop {
+            match stream.try_next().await {
+                Ok(Some(row)) => {
+                    recording.row(&row);
+                    rows.push(row);
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    let err = M::from_call(err, self.sql_trait, recording.name, annotations, "", &[]);
+                    self.save_failure(recording.call, &err);
+                    return Err(err);
+                }
+            }
+        }
+        self.save_call(recording.call, Recorded::Rows(recording.rows));
+        Ok(crate::RowStream::canned(rows))
+    }
+}
+
+/// Call of a generated method that is being recorded and the rows it has retrieved so far.
+#[doc(hidden)]
+pub struct Recording {
+    name: &'static str,
+    call: String,
+    rows: Rows,
+}
+
+impl Recording {
+    pub fn new(name: &'static str, params: &[(&'static str, Param)]) -> Self {
+        Self { name, call: call_text(name, params), rows: Rows::default() }
+    }
+
+    /// Records the row.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a column type is not built into the driver, as the replayed rows could not be made with it.
+    pub fn row(&mut self, row: &Row) {
+        if self.rows.columns.is_empty() {
+            for column in row.columns() {
+                let ty = column.type_();
+                if Type::from_oid(ty.oid()).is_none() {
+                    panic!("{}: column {} is of type {}, which is not built into the driver", self.call, column.name(), ty);
+                }
+                self.rows.columns.push((column.name().to_string(), ty.clone()));
+            }
+        }
+        let values = (0..row.len()).map(|i| {
+            row.try_get::<_, Option<RawValue>>(i).expect("raw value").map(|RawValue(value)| value.to_vec())
+        });
+        self.rows.values.push(values.collect());
+    }
+}
+
+/// Row that a `%` method retrieves for the recording.
+#[doc(hidden)]
+pub struct RawRow(Row);
+
+impl TryFrom<Row> for RawRow {
+    type Error = Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        Ok(Self(row))
+    }
+}
+
+/// Error of a `?` method call that tells the failures of the call and of the row callback apart.
+#[doc(hidden)]
+pub enum CallbackError<E, M> {
+    Call(M),
+    Callback(E),
+}
+
+impl<E, M> From<M> for CallbackError<E, M> {
+    fn from(err: M) -> Self {
+        Self::Call(err)
+    }
+}
+
+/// Binary value of a column of any type.
+struct RawValue<'a>(&'a [u8]);
+
+impl<'a> FromSql<'a> for RawValue<'a> {
+    fn from_sql(_ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn std::error::Error + Sync + Send>> {
+        Ok(Self(raw))
+    }
+
+    fn accepts(_ty: &Type) -> bool {
+        true
+    }
+}
+
+/// Formats the call as the key of its recorded results - `name(param = value, ...)` with debug-formatted values.
+fn call_text(name: &str, params: &[(&'static str, Param)]) -> String {
+    let mut text = format!("{}(", name);
+    for (i, (name, value)) in params.iter().enumerate() {
+        if i > 0 {
+            text.push_str(", ");
+        }
+        write!(text, "{} = {:?}", name, value).expect("written to string");
+    }
+    text.push(')');
+    text
+}
+
+fn db_failure(err: &Error) -> Option<DbFailure> {
+    err.as_db_error().map(|err| DbFailure {
+        code: err.code().clone(),
+        message: err.message().to_string(),
+        constraint: err.constraint().map(str::to_string),
+    })
+}
+
+/// Tells whether the error is the one that the driver reports when a `->` statement returns no or several rows.
+fn is_row_count_error(err: &Error) -> bool {
+    static ROW_COUNT_ERROR: OnceLock<String> = OnceLock::new();
+    let row_count_error = ROW_COUNT_ERROR.get_or_init(|| match canned::query_one(Reply::Rows(&Rows::default())) {
+        Err(err) => err.to_string(),
+        Ok(_) => unreachable!("driver returned a row without rows"),
+    });
+    err.as_db_error().is_none() && err.source().is_none() && err.to_string() == *row_count_error
+}
+
+/*
+Fixture format. Each recorded call starts with a line that names it and lists its parameters. Indented lines
+that follow describe its result:
+
+get_genres(max_id = 2)
+    columns: "genre_id" int4, "name" text
+    row: 1, "Rock"
+    row: 2, "Jazz"
+new_genre(id = 42, name = "Polka")
+    affected: 1
+new_genre(id = 1, name = "Rock")
+    error: 23505 "duplicate key value violates unique constraint \"genre_pkey\"" constraint "genre_pkey"
+
+Values of boolean, numeric and text types are written as literals. Values of other types are written as their
+binary representation in hex, like `\x0001`.
+*/
+
+fn write_call(text: &mut String, call: &str, recorded: &Recorded) -> fmt::Result {
+    writeln!(text, "{}", call)?;
+    match recorded {
+        Recorded::Rows(rows) => {
+            text.push_str("    columns:");
+            for (i, (name, ty)) in rows.columns.iter().enumerate() {
+                write!(text, "{} {:?} {}", if i > 0 { "," } else { "" }, name, ty.name())?;
+            }
+            text.push('\n');
+            for values in &rows.values {
+                text.push_str("    row: ");
+                for (i, (value, (_, ty))) in values.iter().zip(&rows.columns).enumerate() {
+                    if i > 0 {
+                        text.push_str(", ");
+                    }
+                    write_value(text, ty, value.as_deref())?;
+                }
+                text.push('\n');
+            }
+        }
+        Recorded::Affected(num_rows) => writeln!(text, "    affected: {}", num_rows)?,
+        Recorded::Failed(failure) => {
+            write!(text, "    error: {} {:?}", failure.code.code(), failure.message)?;
+            if let Some(constraint) = &failure.constraint {
+                write!(text, " constraint {:?}", constraint)?;
+            }
+            text.push('\n');
+        }
+    }
+    Ok(())
+}
+
+fn write_value(text: &mut String, ty: &Type, value: Option<&[u8]>) -> fmt::Result {
+    let value = match value {
+        Some(value) => value,
+        None => return text.write_str("null"),
+    };
+    let written = match *ty {
+        Type::BOOL => bool::from_sql(ty, value).map(|value| write!(text, "{}", value)),
+        Type::INT2 => i16::from_sql(ty, value).map(|value| write!(text, "{}", value)),
+        Type::INT4 => i32::from_sql(ty, value).map(|value| write!(text, "{}", value)),
+        Type::INT8 => i64::from_sql(ty, value).map(|value| write!(text, "{}", value)),
+        Type::OID => u32::from_sql(ty, value).map(|value| write!(text, "{}", value)),
+        Type::FLOAT4 => f32::from_sql(ty, value).map(|value| write!(text, "{:?}", value)),
+        Type::FLOAT8 => f64::from_sql(ty, value).map(|value| write!(text, "{:?}", value)),
+        _ if <&str as FromSql>::accepts(ty) => <&str>::from_sql(ty, value).map(|value| write!(text, "{:?}", value)),
+        _ => return write_hex(text, value),
+    };
+    match written {
+        Ok(res) => res,
+        Err(_) => write_hex(text, value),
+    }
+}
+
+fn write_hex(text: &mut String, value: &[u8]) -> fmt::Result {
+    text.write_str("\\x")?;
+    for byte in value {
+        write!(text, "{:02x}", byte)?;
+    }
+    Ok(())
+}
+
+type ParseError = (usize, String);
+
+fn parse(text: &str) -> Result<Vec<(String, Recorded)>, ParseError> {
+    let mut calls = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line_no = i + 1;
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if !line.starts_with(' ') {
+            calls.push((line_no, line.trim_end().to_string(), None));
+            continue;
+        }
+        let (_, _, recorded) = calls.last_mut().ok_or_else(|| (line_no, "result without a call".to_string()))?;
+        let line = line.trim();
+        let (key, value) = match line.find(':') {
+            Some(pos) => (&line[..pos], line[pos + 1..].trim_start()),
+            None => return Err((line_no, format!("unexpected `{}`", line))),
+        };
+        match (key, recorded.as_mut()) {
+            ("columns", None) => {
+                let columns = parse_columns(value).map_err(|msg| (line_no, msg))?;
+                *recorded = Some(Recorded::Rows(Rows { columns, values: Vec::new() }));
+            }
+            ("row", Some(Recorded::Rows(rows))) => {
+                let values = parse_row(value, &rows.columns).map_err(|msg| (line_no, msg))?;
+                rows.values.push(values);
+            }
+            ("affected", None) => {
+                let num_rows = value.parse().map_err(|_| (line_no, format!("invalid number of rows `{}`", value)))?;
+                *recorded = Some(Recorded::Affected(num_rows));
+            }
+            ("error", None) => {
+                let failure = parse_error(value).map_err(|msg| (line_no, msg))?;
+                *recorded = Some(Recorded::Failed(failure));
+            }
+            _ => return Err((line_no, format!("unexpected `{}`", line))),
+        }
+    }
+    calls.into_iter().map(|(line_no, call, recorded)| match recorded {
+        Some(recorded) => Ok((call, recorded)),
+        None => Err((line_no, format!("{} has no recorded result", call))),
+    }).collect()
+}
+
+fn parse_columns(mut text: &str) -> Result<Vec<(String, Type)>, String> {
+    let mut columns = Vec::new();
+    while !text.is_empty() {
+        let (name, rest) = parse_string(text)?;
+        let rest = rest.strip_prefix(' ').ok_or_else(|| format!("column {} has no type", name))?;
+        let (type_name, rest) = rest.split_at(rest.find(',').unwrap_or(rest.len()));
+        let ty = type_by_name(type_name).ok_or_else(|| format!("column {} is of unknown type {}", name, type_name))?;
+        columns.push((name, ty));
+        text = rest.strip_prefix(',').map_or(rest, str::trim_start);
+    }
+    Ok(columns)
+}
+
+fn parse_row(mut text: &str, columns: &[(String, Type)]) -> Result<Vec<Option<Vec<u8>>>, String> {
+    let mut values = Vec::new();
+    for (name, ty) in columns {
+        if text.is_empty() {
+            return Err(format!("row has no value of column {}", name));
+        }
+        let (value, rest) = if text.starts_with('"') {
+            let (value, rest) = parse_string(text)?;
+            (encode(ty, &value.as_str()), rest)
+        } else {
+            let (value, rest) = text.split_at(text.find(',').unwrap_or(text.len()));
+            (parse_literal(ty, value), rest)
+        };
+        values.push(value.map_err(|err| format!("invalid value of column {}: {}", name, err))?);
+        text = rest.strip_prefix(',').map_or(rest, str::trim_start);
+    }
+    if text.is_empty() {
+        Ok(values)
+    } else {
+        Err(format!("row has more values than the {} columns", columns.len()))
+    }
+}
+
+fn parse_literal(ty: &Type, value: &str) -> Result<Option<Vec<u8>>, String> {
+    if value == "null" {
+        return Ok(None);
+    }
+    if let Some(hex) = value.strip_prefix("\\x") {
+        return parse_hex(hex).map(Some);
+    }
+    fn parsed<T: std::str::FromStr + ToSql>(ty: &Type, value: &str) -> Result<Option<Vec<u8>>, String> {
+        let value: T = value.parse().map_err(|_| format!("`{}` is not a {} value", value, ty))?;
+        encode(ty, &value)
+    }
+    match *ty {
+        Type::BOOL => parsed::<bool>(ty, value),
+        Type::INT2 => parsed::<i16>(ty, value),
+        Type::INT4 => parsed::<i32>(ty, value),
+        Type::INT8 => parsed::<i64>(ty, value),
+        Type::OID => parsed::<u32>(ty, value),
+        Type::FLOAT4 => parsed::<f32>(ty, value),
+        Type::FLOAT8 => parsed::<f64>(ty, value),
+        _ => Err(format!("`{}` is not a {} value", value, ty)),
+    }
+}
+
+fn encode(ty: &Type, value: &dyn ToSql) -> Result<Option<Vec<u8>>, String> {
+    let mut buf = BytesMut::new();
+    match value.to_sql_checked(ty, &mut buf) {
+        Ok(IsNull::No) => Ok(Some(buf.to_vec())),
+        Ok(IsNull::Yes) => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+fn parse_hex(hex: &str) -> Result<Vec<u8>, String> {
+    hex.as_bytes().chunks(2).map(|digits| {
+        let byte = std::str::from_utf8(digits).ok().filter(|_| digits.len() == 2);
+        byte.and_then(|byte| u8::from_str_radix(byte, 16).ok()).ok_or_else(|| format!("invalid hex digits in `{}`", hex))
+    }).collect()
+}
+
+fn parse_error(text: &str) -> Result<DbFailure, String> {
+    let (code, rest) = text.split_at(text.find(' ').unwrap_or(text.len()));
+    let (message, rest) = parse_string(rest.trim_start())?;
+    let constraint = match rest.trim_start() {
+        "" => None,
+        rest => match rest.strip_prefix("constraint ") {
+            Some(rest) => match parse_string(rest)? {
+                (constraint, "") => Some(constraint),
+                (_, rest) => return Err(format!("unexpected `{}`", rest)),
+            },
+            None => return Err(format!("unexpected `{}`", rest)),
+        },
+    };
+    Ok(DbFailure { code: SqlState::from_code(code), message, constraint })
+}
+
+/// Parses the debug-formatted string at the start of the text. Returns the string and the rest of the text.
+fn parse_string(text: &str) -> Result<(String, &str), String> {
+    let mut chars = text.char_indices();
+    if chars.next().map(|(_, c)| c) != Some('"') {
+        return Err(format!("expected a quoted string at `{}`", text));
+    }
+    let mut value = String::new();
+    while let Some((pos, c)) = chars.next() {
+        match c {
+            '"' => return Ok((value, &text[pos + 1..])),
+            '\\' => match chars.next().map(|(_, c)| c) {
+                Some('n') => value.push('\n'),
+                Some('r') => value.push('\r'),
+                Some('t') => value.push('\t'),
+                Some('0') => value.push('\0'),
+                Some(c @ ('\\' | '"' | '\'')) => value.push(c),
+                Some('u') => {
+                    let rest = &text[pos + 2..];
+                    let code = rest.strip_prefix('{').and_then(|rest| rest.find('}').map(|end| &rest[..end]));
+                    let c = code.and_then(|code| u32::from_str_radix(code, 16).ok()).and_then(char::from_u32);
+                    match (code, c) {
+                        (Some(code), Some(c)) => {
+                            value.push(c);
+                            for _ in 0..code.len() + 2 {
+                                chars.next();
+                            }
+                        }
+                        _ => return Err(format!("invalid unicode escape in `{}`", text)),
+                    }
+                }
+                _ => return Err(format!("invalid escape in `{}`", text)),
+            },
+            _ => value.push(c),
+        }
+    }
+    Err(format!("unterminated string `{}`", text))
+}
+
+/// Returns the built-in type with the specified name.
+fn type_by_name(name: &str) -> Option<Type> {
+    static TYPES: OnceLock<HashMap<String, Type>> = OnceLock::new();
+    let types = TYPES.get_or_init(|| {
+        (0..FIRST_NORMAL_OID).filter_map(Type::from_oid).map(|ty| (ty.name().to_string(), ty)).collect()
+    });
+    types.get(name).cloned()
+}
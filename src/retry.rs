@@ -0,0 +1,173 @@
+use std::{any::Any, collections::hash_map::RandomState, error::Error, fmt, hash::{BuildHasher, Hasher}, time::Duration};
+use tokio_postgres::{error::SqlState, IsolationLevel};
+
+/// The reason a transaction is retried by `with_transaction`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryReason {
+    /// The transaction failed with `40001` (serialization_failure).
+    SerializationFailure,
+    /// The transaction failed with `40P01` (deadlock_detected).
+    DeadlockDetected,
+    /// An `optimistic lock` statement of the transaction failed with `CheckFailure::Conflict`.
+    Conflict,
+}
+
+impl RetryReason {
+    /// Returns the reason to retry a transaction that failed with the specified error
+    /// or `None` if the transaction should not be retried.
+    ///
+    /// The error is considered retryable if it is, or if it was caused by, a Postgres error
+    /// with either `40001` or `40P01` SQLSTATE, or a `StatementError` of an optimistic lock conflict.
+    pub fn of(err: &(dyn Error + 'static)) -> Option<Self> {
+        let mut source = Some(err);
+        while let Some(err) = source {
+            if let Some(crate::CheckFailure::Conflict) = err.downcast_ref::<crate::StatementError>().and_then(crate::StatementError::check_failure) {
+                return Some(Self::Conflict);
+            }
+            if let Some(code) = err.downcast_ref::<tokio_postgres::Error>().and_then(tokio_postgres::Error::code) {
+                if *code == SqlState::T_R_SERIALIZATION_FAILURE {
+                    return Some(Self::SerializationFailure);
+                }
+                if *code == SqlState::T_R_DEADLOCK_DETECTED {
+                    return Some(Self::DeadlockDetected);
+                }
+            }
+            source = err.source();
+        }
+        None
+    }
+
+    /// Returns the reason to retry a transaction that failed with the specified error if the error is
+    /// a `postgres::Error`, a `StatementError`, or a boxed `std::error::Error`.
+    fn of_any(err: &dyn Any) -> Option<Self> {
+        if let Some(err) = err.downcast_ref::<tokio_postgres::Error>() {
+            return Self::of(err);
+        }
+        if let Some(err) = err.downcast_ref::<crate::StatementError>() {
+            return Self::of(err);
+        }
+        if let Some(err) = err.downcast_ref::<Box<dyn Error + Send + Sync>>() {
+            return Self::of(err.as_ref());
+        }
+        if let Some(err) = err.downcast_ref::<Box<dyn Error>>() {
+            return Self::of(err.as_ref());
+        }
+        None
+    }
+}
+
+impl fmt::Display for RetryReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SerializationFailure => f.write_str("serialization failure"),
+            Self::DeadlockDetected => f.write_str("deadlock detected"),
+            Self::Conflict => f.write_str("optimistic lock conflict"),
+        }
+    }
+}
+
+type OnRetry = dyn Fn(RetryReason, u32, Duration) + Send + Sync;
+
+type Inspect = dyn Fn(&dyn Any) -> Option<Option<RetryReason>> + Send + Sync;
+
+/**
+Transaction characteristics and retry policy of `with_transaction`.
+
+```rust , ignore
+let retry = Retry::new(IsolationLevel::Serializable)
+    .max_attempts(10)
+    .backoff(Duration::from_millis(5), Duration::from_millis(500))
+    .on_retry(|reason, attempt, delay| eprintln!("attempt {attempt} failed with {reason}, retrying in {delay:?}"));
+```
+*/
+pub struct Retry {
+    pub(crate) isolation: IsolationLevel,
+    max_attempts: u32,
+    min_delay: Duration,
+    max_delay: Duration,
+    on_retry: Option<Box<OnRetry>>,
+    inspect: Option<Box<Inspect>>,
+}
+
+impl Retry {
+    /// Creates a policy that executes transactions with the specified isolation level,
+    /// makes up to 5 attempts and waits between 10ms and 1s, doubling the delay after each attempt, before retrying.
+    ///
+    /// The actual delay is randomized - it is between a half and the whole of the backoff delay - so that
+    /// transactions that conflicted with each other do not collide again when they are retried.
+    pub fn new(isolation: IsolationLevel) -> Self {
+        Self {
+            isolation,
+            max_attempts: 5,
+            min_delay: Duration::from_millis(10),
+            max_delay: Duration::from_secs(1),
+            on_retry: None,
+            inspect: None,
+        }
+    }
+
+    /// Sets the maximum number of attempts (including the first one) to execute the transaction.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Sets the delay before the first retry and the limit for the delays before subsequent retries.
+    pub fn backoff(mut self, min_delay: Duration, max_delay: Duration) -> Self {
+        self.min_delay = min_delay;
+        self.max_delay = max_delay.max(min_delay);
+        self
+    }
+
+    /// Sets the callback that is called before each retry with the reason for the retry,
+    /// the number of the failed attempt and the delay before the next one.
+    pub fn on_retry<F>(mut self, on_retry: F) -> Self
+    where F: Fn(RetryReason, u32, Duration) + Send + Sync + 'static
+    {
+        self.on_retry = Some(Box::new(on_retry));
+        self
+    }
+
+    /// Sets the function that decides whether a transaction that failed with an error of type `E` should be retried.
+    ///
+    /// Errors that are `postgres::Error`, `StatementError`, or boxed `std::error::Error` are inspected without it.
+    /// Other error types, like `anyhow::Error`, need it to be retried:
+    ///
+    /// ```rust , ignore
+    /// let retry = Retry::new(IsolationLevel::Serializable)
+    ///     .inspect(|err: &anyhow::Error| RetryReason::of(err.as_ref()));
+    /// ```
+    pub fn inspect<E, F>(mut self, inspect: F) -> Self
+    where
+        E: 'static,
+        F: Fn(&E) -> Option<RetryReason> + Send + Sync + 'static,
+    {
+        self.inspect = Some(Box::new(move |err: &dyn Any| err.downcast_ref::<E>().map(&inspect)));
+        self
+    }
+
+    /// Returns the delay before the next attempt if the transaction that failed with the specified error
+    /// on the specified attempt should be retried.
+    pub(crate) fn next_delay(&self, err: &dyn Any, attempt: u32) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+        let reason = match self.inspect.as_ref().and_then(|inspect| inspect(err)) {
+            Some(reason) => reason?,
+            None => RetryReason::of_any(err)?,
+        };
+        let backoff = self.min_delay.checked_mul(1 << (attempt - 1).min(16)).map_or(self.max_delay, |delay| delay.min(self.max_delay));
+        let delay = backoff / 2 + jitter(backoff / 2);
+        if let Some(on_retry) = &self.on_retry {
+            on_retry(reason, attempt, delay);
+        }
+        Some(delay)
+    }
+}
+
+/// Returns a random duration that does not exceed `max`.
+fn jitter(max: Duration) -> Duration {
+    // `RandomState` is seeded with different keys every time it is created
+    let random = RandomState::new().build_hasher().finish();
+    max.mul_f64((random >> 11) as f64 / (1u64 << 53) as f64)
+}
@@ -0,0 +1,71 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/**
+Routes generated method calls between the primary and replica clients.
+
+`impl_sql` implements the generated trait for `Router` over a `Client` when it is given the `router` option.
+Methods of `?`, `^`, and `%` statements, as well as methods of statements annotated with `replica: ok`, are
+executed on one of the replicas, which are picked in the round-robin order. All other methods - and methods
+of statements annotated with `replica: no` - are executed on the primary. When there are no replicas all
+methods are executed on the primary.
+
+Transactions are always started on the primary.
+*/
+pub struct Router<C> {
+    primary: C,
+    replicas: Vec<C>,
+    next: AtomicUsize,
+}
+
+impl<C> Router<C> {
+    /// Creates a router over the primary and replica clients.
+    pub fn new(primary: C, replicas: Vec<C>) -> Self {
+        Self { primary, replicas, next: AtomicUsize::new(0) }
+    }
+
+    /// Returns the primary client.
+    pub fn primary(&self) -> &C {
+        &self.primary
+    }
+
+    /// Returns the primary client.
+    pub fn primary_mut(&mut self) -> &mut C {
+        &mut self.primary
+    }
+
+    /// Returns the next replica client or the primary if there are no replicas.
+    pub fn replica(&self) -> &C {
+        match self.next_replica() {
+            Some(i) => &self.replicas[i],
+            None => &self.primary,
+        }
+    }
+
+    /// Returns the next replica client or the primary if there are no replicas.
+    pub fn replica_mut(&mut self) -> &mut C {
+        match self.next_replica() {
+            Some(i) => &mut self.replicas[i],
+            None => &mut self.primary,
+        }
+    }
+
+    /// Returns the client that executes the statement.
+    #[doc(hidden)]
+    pub fn route(&self, replica: bool) -> &C {
+        if replica { self.replica() } else { self.primary() }
+    }
+
+    /// Returns the client that executes the statement.
+    #[doc(hidden)]
+    pub fn route_mut(&mut self, replica: bool) -> &mut C {
+        if replica { self.replica_mut() } else { self.primary_mut() }
+    }
+
+    fn next_replica(&self) -> Option<usize> {
+        if self.replicas.is_empty() {
+            None
+        } else {
+            Some(self.next.fetch_add(1, Ordering::Relaxed) % self.replicas.len())
+        }
+    }
+}
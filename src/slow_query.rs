@@ -0,0 +1,164 @@
+use std::{error::Error, fmt, sync::{Arc, RwLock}, time::{Duration, Instant}};
+use tokio_postgres::types::{FromSql, Type};
+
+/// A generated method call that took longer than the `SlowQueryLog` threshold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlowQuery {
+    /// Name of the generated trait, like `LibrarySql`.
+    pub sql_trait: &'static str,
+    /// Name of the statement in the SQL file.
+    pub name: &'static str,
+    /// SQL text of the statement as it was sent to the database.
+    pub statement: String,
+    /// Names of the statement parameters and their values. Values are redacted unless
+    /// the log was configured to show them.
+    pub parameters: Vec<(&'static str, String)>,
+    /// Duration of the call.
+    pub duration: Duration,
+    /// Execution plan of the statement in JSON format if the log was configured to capture it.
+    pub plan: Option<String>,
+}
+
+impl fmt::Display for SlowQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}::{} took {:?}: {}", self.sql_trait, self.name, self.duration, self.statement)?;
+        if !self.parameters.is_empty() {
+            f.write_str(" with ")?;
+            for (i, (name, value)) in self.parameters.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "{} = {}", name, value)?;
+            }
+        }
+        if let Some(plan) = &self.plan {
+            write!(f, "\n{}", plan)?;
+        }
+        Ok(())
+    }
+}
+
+type OnSlowQuery = dyn Fn(&SlowQuery) + Send + Sync;
+
+static SLOW_QUERY_LOG: RwLock<Option<Arc<SlowQueryLog>>> = RwLock::new(None);
+
+/**
+Reports generated method calls that take longer than the specified threshold.
+
+```rust , ignore
+SlowQueryLog::new(Duration::from_millis(500), |query| log::warn!("{}", query))
+    .explain(true)
+    .install();
+```
+*/
+pub struct SlowQueryLog {
+    threshold: Duration,
+    explain: bool,
+    show_parameter_values: bool,
+    on_slow_query: Box<OnSlowQuery>,
+}
+
+impl SlowQueryLog {
+    /// Creates a log that calls `on_slow_query` for each generated method call that took longer than `threshold`.
+    pub fn new<F>(threshold: Duration, on_slow_query: F) -> Self
+    where F: Fn(&SlowQuery) + Send + Sync + 'static
+    {
+        Self { threshold, explain: false, show_parameter_values: false, on_slow_query: Box::new(on_slow_query) }
+    }
+
+    /// Sets whether the execution plan of a slow statement is captured by running `EXPLAIN (FORMAT JSON)`
+    /// for it with the same parameters.
+    ///
+    /// Only statements that `EXPLAIN` accepts - `SELECT`, `WITH`, `INSERT`, `UPDATE`, `DELETE`, `MERGE`, and
+    /// `VALUES` - are explained. Within a transaction the `EXPLAIN` is executed under a savepoint, thus its failure
+    /// does not abort the transaction. The plan is captured after the call, so for a statement that the method
+    /// executed in its own transaction it is planned without the `set:` settings of the statement.
+    pub fn explain(mut self, explain: bool) -> Self {
+        self.explain = explain;
+        self
+    }
+
+    /// Sets whether parameter values of a slow statement are reported. When they are not, which is the default,
+    /// only the names of the parameters are reported.
+    pub fn show_parameter_values(mut self, show: bool) -> Self {
+        self.show_parameter_values = show;
+        self
+    }
+
+    /// Makes the log report slow calls of all generated methods. Replaces the previously installed log.
+    pub fn install(self) {
+        *SLOW_QUERY_LOG.write().unwrap_or_else(|err| err.into_inner()) = Some(Arc::new(self));
+    }
+
+    /// Stops reporting slow calls.
+    pub fn uninstall() {
+        *SLOW_QUERY_LOG.write().unwrap_or_else(|err| err.into_inner()) = None;
+    }
+
+    /// Returns the installed log if the call that started at the specified time took longer than its threshold.
+    #[doc(hidden)]
+    pub fn exceeded(started: Instant) -> Option<Arc<Self>> {
+        let log = SLOW_QUERY_LOG.read().unwrap_or_else(|err| err.into_inner());
+        log.as_ref().filter(|log| started.elapsed() >= log.threshold).cloned()
+    }
+
+    #[doc(hidden)]
+    pub fn explains(&self) -> bool {
+        self.explain
+    }
+
+    #[doc(hidden)]
+    pub fn slow_query(&self, sql_trait: &'static str, name: &'static str, statement: &str, started: Instant, parameters: &[(&'static str, crate::interceptor::Param)]) -> SlowQuery {
+        let parameters = parameters.iter().map(|&(name, value)| {
+            let value = if self.show_parameter_values { format!("{:?}", value) } else { "<redacted>".to_string() };
+            (name, value)
+        }).collect();
+        SlowQuery { sql_trait, name, statement: statement.to_string(), parameters, duration: started.elapsed(), plan: None }
+    }
+
+    #[doc(hidden)]
+    pub fn report(&self, query: &SlowQuery) {
+        (self.on_slow_query)(query)
+    }
+}
+
+/// Returns the `EXPLAIN` command that captures the execution plan of the statement or `None` if the statement
+/// is not one that `EXPLAIN` accepts, like DDL, `CALL`, or `DO`.
+pub(crate) fn explain_command(statement: &str) -> Option<String> {
+    const EXPLAINABLE: [&str; 7] = ["SELECT", "WITH", "INSERT", "UPDATE", "DELETE", "MERGE", "VALUES"];
+
+    let mut sql = statement.trim_start();
+    loop {
+        if let Some(comment) = sql.strip_prefix("--") {
+            sql = comment.split_once('\n').map_or("", |(_, rest)| rest).trim_start();
+        } else if let Some(comment) = sql.strip_prefix("/*") {
+            sql = comment.split_once("*/").map_or("", |(_, rest)| rest).trim_start();
+        } else {
+            break;
+        }
+    }
+    let keyword = sql.split(|c: char| !c.is_ascii_alphabetic()).next().unwrap_or_default();
+    if EXPLAINABLE.iter().any(|explainable| keyword.eq_ignore_ascii_case(explainable)) {
+        Some(format!("EXPLAIN (FORMAT JSON) {}", statement))
+    } else {
+        None
+    }
+}
+
+/// Savepoint that isolates the `EXPLAIN` from the transaction it is executed in, so a failed `EXPLAIN`
+/// does not abort the transaction.
+pub(crate) const EXPLAIN_SAVEPOINT: &str = "SAVEPOINT include_postgres_sql_explain";
+pub(crate) const EXPLAIN_ROLLBACK: &str = "ROLLBACK TO SAVEPOINT include_postgres_sql_explain; RELEASE SAVEPOINT include_postgres_sql_explain";
+
+/// Execution plan as returned by `EXPLAIN (FORMAT JSON)`.
+pub(crate) struct Plan(pub(crate) String);
+
+impl<'a> FromSql<'a> for Plan {
+    fn from_sql(_ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(Self(std::str::from_utf8(raw)?.to_string()))
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        *ty == Type::JSON
+    }
+}
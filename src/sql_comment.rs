@@ -0,0 +1,60 @@
+use std::{borrow::Cow, fmt::Write, sync::RwLock};
+
+type TraceContext = dyn Fn() -> Option<String> + Send + Sync;
+
+static TRACE_CONTEXT: RwLock<Option<Box<TraceContext>>> = RwLock::new(None);
+
+/**
+Sets the function that returns the W3C `traceparent` of the current trace context.
+
+When the function returns a value, it is added to the comment of the executed statement as `traceparent`:
+
+```rust , ignore
+set_trace_context(|| current_span_context().map(|cx| format!("00-{}-{}-01", cx.trace_id(), cx.span_id())));
+```
+*/
+pub fn set_trace_context<F>(traceparent: F)
+where F: Fn() -> Option<String> + Send + Sync + 'static
+{
+    *TRACE_CONTEXT.write().unwrap_or_else(|err| err.into_inner()) = Some(Box::new(traceparent));
+}
+
+/// Stops adding the trace context to statement comments.
+pub fn clear_trace_context() {
+    *TRACE_CONTEXT.write().unwrap_or_else(|err| err.into_inner()) = None;
+}
+
+/// Returns the `traceparent` tag of the current trace context.
+fn traceparent() -> Option<String> {
+    let trace_context = TRACE_CONTEXT.read().unwrap_or_else(|err| err.into_inner());
+    let traceparent = trace_context.as_ref().and_then(|traceparent| traceparent())?;
+    let mut tag = String::with_capacity(traceparent.len() + 16);
+    tag.push_str(",traceparent='");
+    for b in traceparent.bytes() {
+        if b.is_ascii_alphanumeric() || b"-_.~".contains(&b) {
+            tag.push(b as char);
+        } else {
+            let _ = write!(tag, "%{:02X}", b);
+        }
+    }
+    tag.push('\'');
+    Some(tag)
+}
+
+/// Adds the current trace context to the comment at the end of the tagged static statement.
+pub(crate) fn with_trace_context(sql: &'static str) -> Cow<'static, str> {
+    match traceparent() {
+        Some(tag) => {
+            let (text, end) = sql.split_at(sql.len() - 3);
+            Cow::Owned([text, &tag, end].concat())
+        }
+        None => Cow::Borrowed(sql)
+    }
+}
+
+/// Adds the current trace context to the comment at the end of the tagged dynamic statement.
+pub(crate) fn add_trace_context(sql: &mut String) {
+    if let Some(tag) = traceparent() {
+        sql.insert_str(sql.len() - 3, &tag);
+    }
+}
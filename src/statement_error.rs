@@ -0,0 +1,181 @@
+use std::{error::Error, fmt, ops::RangeInclusive, sync::atomic::{AtomicBool, Ordering}};
+use tokio_postgres::error::{DbError, SqlState};
+use crate::interceptor::{Param, Rejection};
+
+static SHOW_PARAMETER_VALUES: AtomicBool = AtomicBool::new(false);
+
+/**
+Error returned by generated methods when `impl_sql` is given the `statement_error` option.
+
+Wraps the error reported by the driver, or the check of the statement annotations that the method failed,
+and adds what the failed method executed. `code()` and `as_db_error()` return what the driver error returns,
+or `None` when the call failed a check.
+
+Statements with annotations that only `StatementError` can report - `on_constraint:`, `try_advisory_lock:`,
+`expect_rows:`, and `optimistic lock` - do not compile without the option:
+
+```compile_fail
+use include_postgres_sql::{impl_sql, include_sql};
+
+include_sql!("tests/sql/dml_on_constraint.sql");
+```
+*/
+#[derive(Debug)]
+pub struct StatementError {
+    /// Name of the generated trait, like `LibrarySql`.
+    pub sql_trait: &'static str,
+    /// Name of the statement in the SQL file.
+    pub name: &'static str,
+    /// SQL text of the statement as it was sent to the database. It is empty when the call failed before
+    /// the statement was sent, for example when `ReconnectingClient` could not connect.
+    pub statement: String,
+    /// Names of the statement parameters and their values. Values are redacted unless they were
+    /// configured to be shown.
+    pub parameters: Vec<(&'static str, String)>,
+    /// Name that the `on_constraint:` annotation of the statement maps the violated constraint to,
+    /// like `DuplicateIsbn`, or `None` if the error is not a violation of a mapped constraint.
+    pub violation: Option<&'static str>,
+    cause: Cause,
+}
+
+#[derive(Debug)]
+enum Cause {
+    Driver(tokio_postgres::Error),
+    Check(Box<CheckFailure>),
+}
+
+/// Check of the statement annotations that a generated method failed without the database reporting an error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CheckFailure {
+    /// The `try_advisory_lock:` lock is held by another session. Holds the lock key as it is written in the annotation.
+    Busy { key: &'static str },
+    /// The `!` statement affected a number of rows that its `expect_rows:` or `optimistic lock` annotation does not allow.
+    RowCount { expected: RangeInclusive<u64>, actual: u64 },
+    /// The `optimistic lock` statement did not affect any rows, because the row was changed after it was read.
+    Conflict,
+    /// The `before` hook of the interceptor rejected the call, thus the statement was not executed.
+    Rejected(Rejection),
+}
+
+impl CheckFailure {
+    /// Returns the name of the check that labels the failed call in metrics.
+    #[cfg(feature = "metrics")]
+    pub(crate) fn label(&self) -> &'static str {
+        match self {
+            Self::Busy { .. } => "busy",
+            Self::RowCount { .. } => "row_count",
+            Self::Conflict => "conflict",
+            Self::Rejected(_) => "rejected",
+        }
+    }
+}
+
+impl fmt::Display for CheckFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Busy { key } => write!(f, "advisory lock {} is held by another session", key),
+            Self::RowCount { expected, actual } => match (*expected.start(), *expected.end()) {
+                (min, max) if min == max => write!(f, "affected {} rows, expected {}", actual, min),
+                (min, u64::MAX) => write!(f, "affected {} rows, expected at least {}", actual, min),
+                (min, max) => write!(f, "affected {} rows, expected between {} and {}", actual, min, max),
+            },
+            Self::Conflict => f.write_str("conflicts with a concurrent update"),
+            Self::Rejected(rejection) => write!(f, "rejected by the interceptor: {}", rejection),
+        }
+    }
+}
+
+impl StatementError {
+    #[doc(hidden)]
+    pub fn new(sql_trait: &'static str, name: &'static str, statement: &str, parameters: &[(&'static str, Param)], source: tokio_postgres::Error) -> Self {
+        Self::with_cause(sql_trait, name, statement, parameters, Cause::Driver(source))
+    }
+
+    #[doc(hidden)]
+    pub fn failed_check(sql_trait: &'static str, name: &'static str, statement: &str, parameters: &[(&'static str, Param)], failure: CheckFailure) -> Self {
+        Self::with_cause(sql_trait, name, statement, parameters, Cause::Check(Box::new(failure)))
+    }
+
+    fn with_cause(sql_trait: &'static str, name: &'static str, statement: &str, parameters: &[(&'static str, Param)], cause: Cause) -> Self {
+        let show = SHOW_PARAMETER_VALUES.load(Ordering::Relaxed);
+        let parameters = parameters.iter().map(|&(name, value)| {
+            let value = if show { format!("{:?}", value) } else { "<redacted>".to_string() };
+            (name, value)
+        }).collect();
+        Self { sql_trait, name, statement: statement.to_string(), parameters, violation: None, cause }
+    }
+
+    /// Sets whether parameter values are included in errors. When they are not, which is the default,
+    /// only the names of the parameters are included.
+    pub fn show_parameter_values(show: bool) {
+        SHOW_PARAMETER_VALUES.store(show, Ordering::Relaxed);
+    }
+
+    /// Returns the error reported by the driver or `None` if the call failed a check.
+    pub fn driver_error(&self) -> Option<&tokio_postgres::Error> {
+        match &self.cause {
+            Cause::Driver(err) => Some(err),
+            Cause::Check(_) => None,
+        }
+    }
+
+    /// Returns the check that the call failed or `None` if the error was reported by the driver.
+    pub fn check_failure(&self) -> Option<&CheckFailure> {
+        match &self.cause {
+            Cause::Driver(_) => None,
+            Cause::Check(failure) => Some(failure),
+        }
+    }
+
+    /// Returns the SQLSTATE of the error if it was reported by the database.
+    pub fn code(&self) -> Option<&SqlState> {
+        self.driver_error()?.code()
+    }
+
+    /// Returns the error reported by the database, if there is one.
+    pub fn as_db_error(&self) -> Option<&DbError> {
+        self.driver_error()?.as_db_error()
+    }
+
+    /// Returns the error reported by the driver or `None` if the call failed a check.
+    pub fn into_driver_error(self) -> Option<tokio_postgres::Error> {
+        match self.cause {
+            Cause::Driver(err) => Some(err),
+            Cause::Check(_) => None,
+        }
+    }
+}
+
+/// Wraps the driver error that was not caused by a generated method, for example when `with_transaction`
+/// fails to commit. The names of the trait and the statement of such an error are empty.
+impl From<tokio_postgres::Error> for StatementError {
+    fn from(err: tokio_postgres::Error) -> Self {
+        Self::new("", "", "", &[], err)
+    }
+}
+
+impl fmt::Display for StatementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}::{} failed", self.sql_trait, self.name)?;
+        if let Some(violation) = self.violation {
+            write!(f, " ({})", violation)?;
+        }
+        match &self.cause {
+            Cause::Driver(err) => match err.as_db_error() {
+                Some(db_err) => write!(f, ": {}", db_err),
+                None => write!(f, ": {}", err),
+            },
+            Cause::Check(failure) => write!(f, ": {}", failure),
+        }
+    }
+}
+
+impl Error for StatementError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.cause {
+            Cause::Driver(err) => Some(err),
+            Cause::Check(_) => None,
+        }
+    }
+}
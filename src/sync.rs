@@ -3,6 +3,13 @@ Generates Rust code to use included SQL.
 
 This macro defines a trait with methods to access data and implements it for `postgres::Client` and `postgres::Transaction`.
 
+Options that are given to the macro in the `#[options(...)]` attribute before the name of the trait select additional
+implementations:
+* `router` - implements the trait for `Router` over `postgres::Client`,
+* `reconnect` - implements the trait for `ReconnectingClient`,
+* `statement_error` - makes the methods return `StatementError` instead of `postgres::Error`,
+* `app_errors` - makes `?` and `%` methods generic over the error that the row callback or the row conversion return.
+
 This macro recognizes and generates 5 variants of database access methods using the following selectors:
 * `?` - methods that process rows retrieved by `SELECT`,
 * `^` - methods that return raw rows retrieved by `SELECT`,
@@ -25,6 +32,9 @@ fn get_loaned_books<F>(&self, user_id: &str, row_callback: F) -> Result<(),postg
 where F: FnMut(postgres::Row) -> Result<(),postgres::Error>;
 ```
 
+With the `app_errors` option the callback can fail with any error that the driver error converts into,
+and the method returns that error.
+
 For `SELECT` statements (`^`):
 
 ```sql
@@ -36,7 +46,7 @@ SELECT book_title FROM library WHERE loaned_to = :user_id
 The method with the following signature is generated:
 
 ```rust , ignore
-fn get_loaned_books<'a>(&'a self, user_id: &str) -> Result<postgres::RowIter<'a>,postgres::Error>;
+fn get_loaned_books<'a>(&'a self, user_id: &str) -> Result<include_postgres_sql::RowIter<'a>,postgres::Error>;
 ```
 
 For `SELECT` statements (`%`):
@@ -51,7 +61,7 @@ The method with the following signature is generated:
 
 ```rust , ignore
 fn get_loaned_books<R>(&self, user_id: &str) -> Result<Vec<R>,postgres::Error>
-where R: TryFrom<postres::Row>, postgres::Error: From<R::Error>;
+where R: TryFrom<postgres::Row>, postgres::Error: From<R::Error>;
 ```
 
 For non-select statements (`!`) - INSERT, UPDATE, DELETE, etc. - like:
@@ -95,79 +105,482 @@ fn add_new_book(&self, isbn: &str, book_title: &str) -> Result<postgres::Row,pos
 */
 #[macro_export]
 macro_rules! impl_sql {
-    ( $sql_name:ident = $( { $kind:tt $name:ident ($($variant:tt $param:ident $ptype:tt)*) $doc:literal $s:tt $( $text:tt )+ } ),+ ) => {
+    ( #[options($($option:ident),* $(,)?)] $sql_name:ident = $($body:tt)+ ) => {
+        $crate::check_options!{ $($option)* }
+        $crate::with_option!{ statement_error [$($option)*] {
+            $crate::impl_sql!{ @app_errors ($crate::StatementError) [$($option)*] $sql_name = $($body)+ }
+        } else {
+            $crate::impl_sql!{ @app_errors (::postgres::Error) [$($option)*] $sql_name = $($body)+ }
+        } }
+    };
+    ( @app_errors ($err:ty) [$($option:ident)*] $sql_name:ident = $($body:tt)+ ) => {
+        $crate::with_option!{ app_errors [$($option)*] {
+            $crate::impl_sql!{ @hooks ($err, yes) [$($option)*] $sql_name = $($body)+ }
+        } else {
+            $crate::impl_sql!{ @hooks ($err, no) [$($option)*] $sql_name = $($body)+ }
+        } }
+    };
+    ( @hooks ($err:ty, $app:ident) [$($option:ident)*] $sql_name:ident = $($body:tt)+ ) => {
+        $crate::with_option!{ interceptor [$($option)*] {
+            $crate::with_option!{ statement_error [$($option)*] {} else {
+                ::std::compile_error!("the `interceptor` option requires the `statement_error` option as calls that the interceptor rejects fail with `CheckFailure::Rejected`");
+            } }
+            $crate::impl_sql!{ @impl ($err, $app, yes) [$($option)*] $sql_name = $($body)+ }
+        } else {
+            $crate::impl_sql!{ @impl ($err, $app, no) [$($option)*] $sql_name = $($body)+ }
+        } }
+    };
+    ( @impl ($err:ty, $app:ident, $hooks:ident) [$($option:ident)*] $sql_name:ident = $( { $kind:tt $name:ident ($($variant:tt $param:ident $ptype:tt)*) $doc:literal $s:tt $( $text:tt )+ } ),+ ) => {
         trait $sql_name {
-            $( $crate::decl_method!{ $kind $name $doc () () $($param $variant $ptype)* } )+
+            $( $crate::decl_method!{ ($err, $app) $kind $name $doc (Self: $crate::util::Within<{ $crate::within_transaction!($kind $doc) }>) () () $($param $variant $ptype)* } )+
+            $crate::with_option!{ fixtures [$($option)*] {
+                $crate::fixtures_method!{ ($err) decl $sql_name $( $kind $name $doc ($($variant $param)*) )+ }
+            } }
         }
-        impl $sql_name for ::postgres::Client {
-            $( $crate::impl_method!{ $kind $name () () ($($param $variant $ptype)*) => ($($variant $param)*) $($text)+ } )+
+        const _: () = {
+            // Implementations of the statements, which, unlike the methods of the generated trait, can be called
+            // on any client. Wrappers delegate to them.
+            trait Statements {
+                $( $crate::decl_method!{ ($err, yes) $kind $name $doc () () () $($param $variant $ptype)* } )+
+            }
+            impl Statements for ::postgres::Client {
+                $( $crate::impl_method!{ ($err, $hooks) $sql_name $kind $name $doc () () ($($param $variant $ptype)*) => ($($variant $param)*) $($text)+ } )+
+            }
+            impl Statements for ::postgres::Transaction<'_> {
+                $( $crate::impl_method!{ ($err, $hooks) $sql_name $kind $name $doc () () ($($param $variant $ptype)*) => ($($variant $param)*) $($text)+ } )+
+            }
+            impl $sql_name for ::postgres::Client {
+                $( $crate::forward_method!{ ($err, $app) this $sql_name $kind $name $doc ($($param)*) () () $($param $variant $ptype)* } )+
+                $crate::with_option!{ fixtures [$($option)*] {
+                    $crate::fixtures_method!{ ($err) this $sql_name $( $kind $name $doc ($($variant $param)*) )+ }
+                } }
+            }
+            impl $sql_name for ::postgres::Transaction<'_> {
+                $( $crate::forward_method!{ ($err, $app) this $sql_name $kind $name $doc ($($param)*) () () $($param $variant $ptype)* } )+
+                $crate::with_option!{ fixtures [$($option)*] {
+                    $crate::fixtures_method!{ ($err) this $sql_name $( $kind $name $doc ($($variant $param)*) )+ }
+                } }
+            }
+            $crate::with_option!{ router [$($option)*] {
+                impl $sql_name for $crate::Router<::postgres::Client> {
+                    $( $crate::forward_method!{ ($err, $app) route $sql_name $kind $name $doc ($($param)*) () () $($param $variant $ptype)* } )+
+                    $crate::with_option!{ fixtures [$($option)*] {
+                        $crate::fixtures_method!{ ($err) route $sql_name $( $kind $name $doc ($($variant $param)*) )+ }
+                    } }
+                }
+            } }
+            $crate::with_option!{ reconnect [$($option)*] {
+                impl $sql_name for $crate::sync::ReconnectingClient {
+                    $( $crate::reconnect_method!{ ($err, $app) $sql_name $kind $name $doc () () () $($param $variant $ptype)* } )+
+                    $crate::with_option!{ fixtures [$($option)*] {
+                        $crate::fixtures_method!{ ($err) reconnect $sql_name $( $kind $name $doc ($($variant $param)*) )+ }
+                    } }
+                }
+            } }
+            $crate::replay_sql!{ impl ($err, $app) [$($option)*] $sql_name = $( { $kind $name ($($variant $param $ptype)*) $doc } ),+ }
+        };
+        $crate::mock_sql!{ ($err, $app) [$($option)*] $sql_name = $( { $kind $name ($($variant $param $ptype)*) $doc } ),+ }
+        $crate::replay_sql!{ struct $sql_name }
+        $crate::with_option!{ statement_error [$($option)*] {
+            $( $crate::check_statement!{ (yes) $kind $name $doc } )+
+        } else {
+            $( $crate::check_statement!{ (no) $kind $name $doc } )+
+        } }
+        $( $crate::check_statement!{ @fixture $kind $name ($($param)*) $doc } )+
+        $( $crate::check_statement!{ @defaults $name $doc ($($variant $param $ptype)*) } )+
+        $crate::with_option!{ fixtures [$($option)*] {} else {
+            $( $crate::check_statement!{ @no_fixtures $name $doc } )+
+        } }
+    };
+    ( $sql_name:ident = $($body:tt)+ ) => {
+        $crate::impl_sql!{ #[options()] $sql_name = $($body)+ }
+    };
+}
+
+/// Generates `Mock<SqlTraitName>` that implements the generated trait without a database.
+#[cfg(feature = "mock")]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! mock_sql {
+    ( ($err:ty, $app:ident) [$($option:ident)*] $sql_name:ident = $( { $kind:tt $name:ident ($($variant:tt $param:ident $ptype:tt)*) $doc:literal } ),+ ) => {
+        $crate::util::paste::paste! {
+            #[allow(dead_code)]
+            struct [<Mock $sql_name>] {
+                mock: $crate::mock::Mock,
+            }
+            #[allow(dead_code)]
+            impl [<Mock $sql_name>] {
+                fn new() -> Self {
+                    Self { mock: $crate::mock::Mock::new(stringify!($sql_name)) }
+                }
+                $(
+                    fn [<expect_ $name>](&mut self) -> &mut $crate::Expectation {
+                        self.mock.expect(stringify!($name))
+                    }
+                )+
+                fn calls(&self) -> ::std::vec::Vec<$crate::MockCall> {
+                    self.mock.calls()
+                }
+                fn checkpoint(&mut self) {
+                    self.mock.checkpoint()
+                }
+            }
+            impl $sql_name for [<Mock $sql_name>] {
+                $( $crate::mock_method!{ ($err, $app) $sql_name $kind $name $doc ($($param)*) () () $($param $variant $ptype)* } )+
+                $crate::with_option!{ fixtures [$($option)*] {
+                    $crate::fixtures_method!{ ($err) mock $sql_name $( $kind $name $doc ($($variant $param)*) )+ }
+                } }
+            }
+            // The mock stands in for a transaction as well
+            impl $crate::util::Within<true> for [<Mock $sql_name>] {}
         }
-        impl $sql_name for ::postgres::Transaction<'_> {
-            $( $crate::impl_method!{ $kind $name () () ($($param $variant $ptype)*) => ($($variant $param)*) $($text)+ } )+
+    };
+}
+
+#[cfg(not(feature = "mock"))]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! mock_sql {
+    ( $($tt:tt)* ) => {};
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! fixtures_method {
+    ( ($err:ty) decl $sql_name:ident $( $kind:tt $name:ident $doc:literal ($($variant:tt $param:ident)*) )+ ) => {
+        /// Applies the named fixtures, i.e. statements annotated with `fixture:`, and the fixtures they depend on.
+        #[allow(dead_code)]
+        fn apply_fixtures(&mut self, fixtures: &[&str]) -> ::std::result::Result<(),$err>
+        where Self: $crate::util::Within<{ $crate::util::fixtures_require_transaction(&[ $($doc),+ ], false) }>;
+    };
+    ( ($err:ty) $via:ident $sql_name:ident $( $kind:tt $name:ident $doc:literal ($($variant:tt $param:ident)*) )+ ) => {
+        fn apply_fixtures(&mut self, fixtures: &[&str]) -> ::std::result::Result<(),$err> {
+            let statements = [ $( (::std::stringify!($name), $doc) ),+ ];
+            for stmt in $crate::util::fixture_order(::std::stringify!($sql_name), &statements, fixtures) {
+                $( if stmt == ::std::stringify!($name) { $crate::apply_fixture!{ ($err) $via self $sql_name $kind $name $doc ($($variant $param)*) } } )+
+            }
+            Ok(())
         }
     };
 }
 
+#[macro_export]
+#[doc(hidden)]
+macro_rules! apply_fixture {
+    ( ($err:ty) $via:ident $self:ident $sql_name:ident ! $name:ident $doc:literal () ) => {
+        $crate::forward_call!(($err) $via $self $sql_name false $doc $name ())?;
+    };
+    ( ($err:ty) $via:ident $self:ident $sql_name:ident $kind:tt $name:ident $doc:literal ($($params:tt)*) ) => {
+        ::std::unreachable!("only `!` statements without parameters can be parts of fixtures")
+    };
+}
+
+/// Calls the implementation of the statement for the client that executes it.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! forward_call {
+    ( ($err:ty) this $self:ident $sql_name:ident $reads:tt $doc:literal $name:ident ($($arg:ident)*) ) => {
+        <Self as Statements>::$name($self $(, $arg)*)
+    };
+    ( ($err:ty) route $self:ident $sql_name:ident $reads:tt $doc:literal $name:ident ($($arg:ident)*) ) => {
+        <::postgres::Client as Statements>::$name($self.route_mut($crate::annotations!($name $doc).use_replica($reads)) $(, $arg)*)
+    };
+    ( ($err:ty) reconnect $self:ident $sql_name:ident $reads:tt $doc:literal $name:ident ($($arg:ident)*) ) => {
+        <::postgres::Client as Statements>::$name(
+            $self.connected().map_err($crate::util::connect_error::<$err>(::std::stringify!($sql_name), ::std::stringify!($name)))?
+            $(, $arg)*
+        )
+    };
+    ( ($err:ty) mock $self:ident $sql_name:ident $reads:tt $doc:literal $name:ident ($($arg:ident)*) ) => {
+        <Self as $sql_name>::$name($self $(, $arg)*)
+    };
+}
+
 #[macro_export]
 #[doc(hidden)]
 macro_rules! decl_method {
-    ( ? $name:ident $doc:literal ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+    ( ($err:ty, no) ? $name:ident $doc:literal ($($bound:tt)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        #[doc=$doc]
+        fn $name<$($gen_type : ::postgres::types::ToSql ,)* F>(&mut self $($fn_params)* , row_cb: F) -> ::std::result::Result<(),$err>
+        where F: FnMut(::postgres::Row) -> ::std::result::Result<(),$err>, $($bound)*;
+    };
+    ( ($err:ty, yes) ? $name:ident $doc:literal ($($bound:tt)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
         #[doc=$doc]
-        fn $name<$($gen_type : ::postgres::types::ToSql ,)* F>(&mut self $($fn_params)* , row_cb: F) -> ::std::result::Result<(),::postgres::Error>
-        where F: FnMut(::postgres::Row) -> ::std::result::Result<(),::postgres::Error>;
+        fn $name<$($gen_type : ::postgres::types::ToSql ,)* F, E>(&mut self $($fn_params)* , row_cb: F) -> ::std::result::Result<(),E>
+        where F: FnMut(::postgres::Row) -> ::std::result::Result<(),E>, E: ::std::convert::From<$err>, $($bound)*;
     };
-    ( ^ $name:ident $doc:literal ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+    ( ($err:ty, $app:ident) ^ $name:ident $doc:literal ($($bound:tt)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
         #[doc=$doc]
-        fn $name<'a $(, $gen_type : ::postgres::types::ToSql)*>(&'a mut self $($fn_params)*) -> ::std::result::Result<::postgres::RowIter<'a>,::postgres::Error>;
+        fn $name<'a $(, $gen_type : ::postgres::types::ToSql)*>(&'a mut self $($fn_params)*) -> ::std::result::Result<$crate::RowIter<'a>,$err>
+        where $($bound)*;
     };
-    ( % $name:ident $doc:literal ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+    ( ($err:ty, no) % $name:ident $doc:literal ($($bound:tt)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
         #[doc=$doc]
-        fn $name<$($gen_type : ::postgres::types::ToSql ,)* R>(&mut self $($fn_params)*) -> ::std::result::Result<::std::vec::Vec<R>,::postgres::Error>
-        where R: ::std::convert::TryFrom<::postgres::Row>, ::postgres::Error: ::std::convert::From<R::Error>;
+        fn $name<$($gen_type : ::postgres::types::ToSql ,)* R>(&mut self $($fn_params)*) -> ::std::result::Result<::std::vec::Vec<R>,$err>
+        where R: ::std::convert::TryFrom<::postgres::Row>, $err: ::std::convert::From<R::Error>, $($bound)*;
     };
-    ( ! $name:ident $doc:literal ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+    ( ($err:ty, yes) % $name:ident $doc:literal ($($bound:tt)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
         #[doc=$doc]
-        fn $name<$($gen_type : ::postgres::types::ToSql),*>(&mut self $($fn_params)*) -> ::std::result::Result<u64,::postgres::Error>;
+        fn $name<$($gen_type : ::postgres::types::ToSql ,)* R, E>(&mut self $($fn_params)*) -> ::std::result::Result<::std::vec::Vec<R>,E>
+        where R: ::std::convert::TryFrom<::postgres::Row>, E: ::std::convert::From<R::Error> + ::std::convert::From<$err>, $($bound)*;
     };
-    ( -> $name:ident $doc:literal ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+    ( ($err:ty, $app:ident) ! $name:ident $doc:literal ($($bound:tt)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
         #[doc=$doc]
-        fn $name<$($gen_type : ::postgres::types::ToSql),*>(&mut self $($fn_params)*) -> ::std::result::Result<::postgres::Row,::postgres::Error>;
+        fn $name<$($gen_type : ::postgres::types::ToSql),*>(&mut self $($fn_params)*) -> ::std::result::Result<u64,$err>
+        where $($bound)*;
     };
-    ( $kind:tt $name:ident $doc:literal ($($gen_type:ident)*) ($($fn_params:tt)*) $param:ident : _ $($tail:tt)* ) => {
+    ( ($err:ty, $app:ident) -> $name:ident $doc:literal ($($bound:tt)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        #[doc=$doc]
+        fn $name<$($gen_type : ::postgres::types::ToSql),*>(&mut self $($fn_params)*) -> ::std::result::Result<::postgres::Row,$err>
+        where $($bound)*;
+    };
+    ( ($err:ty, $app:ident) $kind:tt $name:ident $doc:literal ($($bound:tt)*) ($($gen_type:ident)*) ($($fn_params:tt)*) $param:ident : _ $($tail:tt)* ) => {
         $crate::decl_method!{
+            ($err, $app)
             $kind
             $name
             $doc
+            ($($bound)*)
             ($($gen_type)*)
             ($($fn_params)* , $param : impl ::postgres::types::ToSql + Sync)
             $($tail)*
         }
     };
-    ( $kind:tt $name:ident $doc:literal ($($gen_type:ident)*) ($($fn_params:tt)*) $param:ident : ($ptype:ty) $($tail:tt)* ) => {
+    ( ($err:ty, $app:ident) $kind:tt $name:ident $doc:literal ($($bound:tt)*) ($($gen_type:ident)*) ($($fn_params:tt)*) $param:ident : ($ptype:ty) $($tail:tt)* ) => {
         $crate::decl_method!{
+            ($err, $app)
             $kind
             $name
             $doc
+            ($($bound)*)
             ($($gen_type)*)
-            ($($fn_params)* , $param : $ptype)
+            ($($fn_params)* , $param : $crate::arg_type!($doc $param $ptype))
             $($tail)*
         }
     };
-    ( $kind:tt $name:ident $doc:literal ($($gen_type:ident)*) ($($fn_params:tt)*) $param:ident # [$gtype:ident] $($tail:tt)* ) => {
+    ( ($err:ty, $app:ident) $kind:tt $name:ident $doc:literal ($($bound:tt)*) ($($gen_type:ident)*) ($($fn_params:tt)*) $param:ident # [$gtype:ident] $($tail:tt)* ) => {
         $crate::decl_method!{
+            ($err, $app)
             $kind
             $name
             $doc
+            ($($bound)*)
             ($($gen_type)* $gtype)
             ($($fn_params)* , $param : & [ $gtype ] )
             $($tail)*
         }
     };
-    ( $kind:tt $name:ident $doc:literal ($($gen_type:ident)*) ($($fn_params:tt)*) $param:ident # ($ptype:ty) $($tail:tt)* ) => {
+    ( ($err:ty, $app:ident) $kind:tt $name:ident $doc:literal ($($bound:tt)*) ($($gen_type:ident)*) ($($fn_params:tt)*) $param:ident # ($ptype:ty) $($tail:tt)* ) => {
         $crate::decl_method!{
+            ($err, $app)
+            $kind
+            $name
+            $doc
+            ($($bound)*)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : & [ $ptype ] )
+            $($tail)*
+        }
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! forward_method {
+    ( ($err:ty, no) $via:ident $sql_name:ident ? $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        fn $name<$($gen_type : ::postgres::types::ToSql ,)* F>(&mut self $($fn_params)* , row_cb: F) -> ::std::result::Result<(),$err>
+        where F: FnMut(::postgres::Row) -> ::std::result::Result<(),$err>
+        {
+            $crate::forward_call!(($err) $via self $sql_name true $doc $name ($($arg)* row_cb))
+        }
+    };
+    ( ($err:ty, yes) $via:ident $sql_name:ident ? $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        fn $name<$($gen_type : ::postgres::types::ToSql ,)* F, E>(&mut self $($fn_params)* , row_cb: F) -> ::std::result::Result<(),E>
+        where F: FnMut(::postgres::Row) -> ::std::result::Result<(),E>, E: ::std::convert::From<$err>
+        {
+            $crate::forward_call!(($err) $via self $sql_name true $doc $name ($($arg)* row_cb))
+        }
+    };
+    ( ($err:ty, $app:ident) $via:ident $sql_name:ident ^ $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        fn $name<'a $(, $gen_type : ::postgres::types::ToSql)*>(&'a mut self $($fn_params)*) -> ::std::result::Result<$crate::RowIter<'a>,$err> {
+            $crate::forward_call!(($err) $via self $sql_name true $doc $name ($($arg)*))
+        }
+    };
+    ( ($err:ty, no) $via:ident $sql_name:ident % $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        fn $name<$($gen_type : ::postgres::types::ToSql ,)* R>(&mut self $($fn_params)*) -> ::std::result::Result<::std::vec::Vec<R>,$err>
+        where R: ::std::convert::TryFrom<::postgres::Row>, $err: ::std::convert::From<R::Error>
+        {
+            $crate::forward_call!(($err) $via self $sql_name true $doc $name ($($arg)*))
+        }
+    };
+    ( ($err:ty, yes) $via:ident $sql_name:ident % $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        fn $name<$($gen_type : ::postgres::types::ToSql ,)* R, E>(&mut self $($fn_params)*) -> ::std::result::Result<::std::vec::Vec<R>,E>
+        where R: ::std::convert::TryFrom<::postgres::Row>, E: ::std::convert::From<R::Error> + ::std::convert::From<$err>
+        {
+            $crate::forward_call!(($err) $via self $sql_name true $doc $name ($($arg)*))
+        }
+    };
+    ( ($err:ty, $app:ident) $via:ident $sql_name:ident ! $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        fn $name<$($gen_type : ::postgres::types::ToSql),*>(&mut self $($fn_params)*) -> ::std::result::Result<u64,$err> {
+            $crate::forward_call!(($err) $via self $sql_name false $doc $name ($($arg)*))
+        }
+    };
+    ( ($err:ty, $app:ident) $via:ident $sql_name:ident -> $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        fn $name<$($gen_type : ::postgres::types::ToSql),*>(&mut self $($fn_params)*) -> ::std::result::Result<::postgres::Row,$err> {
+            $crate::forward_call!(($err) $via self $sql_name false $doc $name ($($arg)*))
+        }
+    };
+    ( ($err:ty, $app:ident) $via:ident $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) $param:ident : _ $($tail:tt)* ) => {
+        $crate::forward_method!{
+            ($err, $app)
+            $via
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)*)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : impl ::postgres::types::ToSql + Sync)
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $via:ident $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) $param:ident : ($ptype:ty) $($tail:tt)* ) => {
+        $crate::forward_method!{
+            ($err, $app)
+            $via
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)*)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : $crate::arg_type!($doc $param $ptype))
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $via:ident $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) $param:ident # [$gtype:ident] $($tail:tt)* ) => {
+        $crate::forward_method!{
+            ($err, $app)
+            $via
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)*)
+            ($($gen_type)* $gtype)
+            ($($fn_params)* , $param : & [ $gtype ] )
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $via:ident $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) $param:ident # ($ptype:ty) $($tail:tt)* ) => {
+        $crate::forward_method!{
+            ($err, $app)
+            $via
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)*)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : & [ $ptype ] )
+            $($tail)*
+        }
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! mock_method {
+    ( ($err:ty, no) $sql_name:ident ? $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        fn $name<$($gen_type : ::postgres::types::ToSql ,)* F>(&mut self $($fn_params)* , row_cb: F) -> ::std::result::Result<(),$err>
+        where F: FnMut(::postgres::Row) -> ::std::result::Result<(),$err>
+        {
+            $crate::canned::process(self.mock.call(stringify!($name), $crate::annotations!($name $doc), &[$((stringify!($arg), &$arg as $crate::Param)),*]).fetch::<$err>(), row_cb)
+        }
+    };
+    ( ($err:ty, yes) $sql_name:ident ? $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        fn $name<$($gen_type : ::postgres::types::ToSql ,)* F, E>(&mut self $($fn_params)* , row_cb: F) -> ::std::result::Result<(),E>
+        where F: FnMut(::postgres::Row) -> ::std::result::Result<(),E>, E: ::std::convert::From<$err>
+        {
+            $crate::canned::process(self.mock.call(stringify!($name), $crate::annotations!($name $doc), &[$((stringify!($arg), &$arg as $crate::Param)),*]).fetch::<$err>(), row_cb)
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident ^ $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        fn $name<'a $(, $gen_type : ::postgres::types::ToSql)*>(&'a mut self $($fn_params)*) -> ::std::result::Result<$crate::RowIter<'a>,$err> {
+            self.mock.call(stringify!($name), $crate::annotations!($name $doc), &[$((stringify!($arg), &$arg as $crate::Param)),*]).rows()
+        }
+    };
+    ( ($err:ty, no) $sql_name:ident % $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        fn $name<$($gen_type : ::postgres::types::ToSql ,)* R>(&mut self $($fn_params)*) -> ::std::result::Result<::std::vec::Vec<R>,$err>
+        where R: ::std::convert::TryFrom<::postgres::Row>, $err: ::std::convert::From<R::Error>
+        {
+            $crate::canned::collect(self.mock.call(stringify!($name), $crate::annotations!($name $doc), &[$((stringify!($arg), &$arg as $crate::Param)),*]).fetch::<$err>())
+        }
+    };
+    ( ($err:ty, yes) $sql_name:ident % $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        fn $name<$($gen_type : ::postgres::types::ToSql ,)* R, E>(&mut self $($fn_params)*) -> ::std::result::Result<::std::vec::Vec<R>,E>
+        where R: ::std::convert::TryFrom<::postgres::Row>, E: ::std::convert::From<R::Error> + ::std::convert::From<$err>
+        {
+            $crate::canned::collect(self.mock.call(stringify!($name), $crate::annotations!($name $doc), &[$((stringify!($arg), &$arg as $crate::Param)),*]).fetch::<$err>())
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident ! $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        fn $name<$($gen_type : ::postgres::types::ToSql),*>(&mut self $($fn_params)*) -> ::std::result::Result<u64,$err> {
+            self.mock.call(stringify!($name), $crate::annotations!($name $doc), &[$((stringify!($arg), &$arg as $crate::Param)),*]).rows_affected()
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident -> $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        fn $name<$($gen_type : ::postgres::types::ToSql),*>(&mut self $($fn_params)*) -> ::std::result::Result<::postgres::Row,$err> {
+            self.mock.call(stringify!($name), $crate::annotations!($name $doc), &[$((stringify!($arg), &$arg as $crate::Param)),*]).row()
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) $param:ident : _ $($tail:tt)* ) => {
+        $crate::mock_method!{
+            ($err, $app)
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)*)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : impl ::postgres::types::ToSql + Sync)
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) $param:ident : ($ptype:ty) $($tail:tt)* ) => {
+        $crate::mock_method!{
+            ($err, $app)
+            $sql_name
             $kind
             $name
             $doc
+            ($($arg)*)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : $crate::arg_type!($doc $param $ptype))
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) $param:ident # [$gtype:ident] $($tail:tt)* ) => {
+        $crate::mock_method!{
+            ($err, $app)
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)*)
+            ($($gen_type)* $gtype)
+            ($($fn_params)* , $param : & [ $gtype ] )
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) $param:ident # ($ptype:ty) $($tail:tt)* ) => {
+        $crate::mock_method!{
+            ($err, $app)
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)*)
             ($($gen_type)*)
             ($($fn_params)* , $param : & [ $ptype ] )
             $($tail)*
@@ -175,174 +588,628 @@ macro_rules! decl_method {
     };
 }
 
+/// Generates `Replay<SqlTraitName>` that implements the generated trait by recording results of the calls
+/// that the database executes, or by answering the calls with the recorded results.
+#[cfg(feature = "replay")]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! replay_sql {
+    ( struct $sql_name:ident ) => {
+        $crate::util::paste::paste! {
+            #[allow(dead_code)]
+            struct [<Replay $sql_name>] {
+                replay: $crate::replay::Replay<::postgres::Client>,
+            }
+            #[allow(dead_code)]
+            impl [<Replay $sql_name>] {
+                fn start<F, E>(path: impl ::std::convert::AsRef<::std::path::Path>, connect: F) -> ::std::result::Result<Self, ::std::boxed::Box<dyn ::std::error::Error>>
+                where F: FnOnce() -> ::std::result::Result<::postgres::Client, E>, E: ::std::convert::Into<::std::boxed::Box<dyn ::std::error::Error>>
+                {
+                    $crate::replay::Replay::start(stringify!($sql_name), path, connect).map(|replay| Self { replay })
+                }
+                fn record(path: impl ::std::convert::AsRef<::std::path::Path>, client: ::postgres::Client) -> Self {
+                    Self { replay: $crate::replay::Replay::record(stringify!($sql_name), path, client) }
+                }
+                fn play(path: impl ::std::convert::AsRef<::std::path::Path>) -> ::std::io::Result<Self> {
+                    $crate::replay::Replay::play(stringify!($sql_name), path).map(|replay| Self { replay })
+                }
+                fn is_recording(&self) -> bool {
+                    self.replay.is_recording()
+                }
+            }
+            // The recorded calls might have been executed in a transaction
+            impl $crate::util::Within<true> for [<Replay $sql_name>] {}
+        }
+    };
+    ( impl ($err:ty, $app:ident) [$($option:ident)*] $sql_name:ident = $( { $kind:tt $name:ident ($($variant:tt $param:ident $ptype:tt)*) $doc:literal } ),+ ) => {
+        $crate::util::paste::paste! {
+            impl $sql_name for [<Replay $sql_name>] {
+                $( $crate::replay_method!{ ($err, $app) $sql_name $kind $name $doc ($($param)*) () () $($param $variant $ptype)* } )+
+                $crate::with_option!{ fixtures [$($option)*] {
+                    $crate::fixtures_method!{ ($err) mock $sql_name $( $kind $name $doc ($($variant $param)*) )+ }
+                } }
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "replay"))]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! replay_sql {
+    ( $($tt:tt)* ) => {};
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! replay_method {
+    ( ($err:ty, no) $sql_name:ident ? $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        fn $name<$($gen_type : ::postgres::types::ToSql ,)* F>(&mut self $($fn_params)* , mut row_cb: F) -> ::std::result::Result<(),$err>
+        where F: FnMut(::postgres::Row) -> ::std::result::Result<(),$err>
+        {
+            if let Some((client, recorder)) = self.replay.recorder_mut() {
+                let mut recording = $crate::replay::Recording::new(stringify!($name), &[$((stringify!($arg), &$arg as $crate::Param)),*]);
+                let res = <::postgres::Client as Statements>::$name(client $(, $arg)*, |row| {
+                    recording.row(&row);
+                    row_cb(row).map_err($crate::replay::CallbackError::Callback)
+                });
+                return recorder.record_processed(recording, res);
+            }
+            $crate::canned::process(self.replay.answer(stringify!($name), $crate::annotations!($name $doc), &[$((stringify!($arg), &$arg as $crate::Param)),*]).fetch::<$err>(), row_cb)
+        }
+    };
+    ( ($err:ty, yes) $sql_name:ident ? $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        fn $name<$($gen_type : ::postgres::types::ToSql ,)* F, E>(&mut self $($fn_params)* , mut row_cb: F) -> ::std::result::Result<(),E>
+        where F: FnMut(::postgres::Row) -> ::std::result::Result<(),E>, E: ::std::convert::From<$err>
+        {
+            if let Some((client, recorder)) = self.replay.recorder_mut() {
+                let mut recording = $crate::replay::Recording::new(stringify!($name), &[$((stringify!($arg), &$arg as $crate::Param)),*]);
+                let res = <::postgres::Client as Statements>::$name(client $(, $arg)*, |row| {
+                    recording.row(&row);
+                    row_cb(row).map_err($crate::replay::CallbackError::Callback)
+                });
+                return recorder.record_processed::<_, $err>(recording, res);
+            }
+            $crate::canned::process(self.replay.answer(stringify!($name), $crate::annotations!($name $doc), &[$((stringify!($arg), &$arg as $crate::Param)),*]).fetch::<$err>(), row_cb)
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident ^ $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        fn $name<'a $(, $gen_type : ::postgres::types::ToSql)*>(&'a mut self $($fn_params)*) -> ::std::result::Result<$crate::RowIter<'a>,$err> {
+            if let Some((client, recorder)) = self.replay.recorder_mut() {
+                let recording = $crate::replay::Recording::new(stringify!($name), &[$((stringify!($arg), &$arg as $crate::Param)),*]);
+                let res = <::postgres::Client as Statements>::$name(client $(, $arg)*);
+                return recorder.record_rows(recording, $crate::annotations!($name $doc), res);
+            }
+            self.replay.answer(stringify!($name), $crate::annotations!($name $doc), &[$((stringify!($arg), &$arg as $crate::Param)),*]).rows()
+        }
+    };
+    ( ($err:ty, no) $sql_name:ident % $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        fn $name<$($gen_type : ::postgres::types::ToSql ,)* R>(&mut self $($fn_params)*) -> ::std::result::Result<::std::vec::Vec<R>,$err>
+        where R: ::std::convert::TryFrom<::postgres::Row>, $err: ::std::convert::From<R::Error>
+        {
+            if let Some((client, recorder)) = self.replay.recorder_mut() {
+                let recording = $crate::replay::Recording::new(stringify!($name), &[$((stringify!($arg), &$arg as $crate::Param)),*]);
+                let res : ::std::result::Result<::std::vec::Vec<$crate::replay::RawRow>, $err> = <::postgres::Client as Statements>::$name(client $(, $arg)*);
+                return $crate::canned::collect(recorder.record_collected(recording, res));
+            }
+            $crate::canned::collect(self.replay.answer(stringify!($name), $crate::annotations!($name $doc), &[$((stringify!($arg), &$arg as $crate::Param)),*]).fetch::<$err>())
+        }
+    };
+    ( ($err:ty, yes) $sql_name:ident % $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        fn $name<$($gen_type : ::postgres::types::ToSql ,)* R, E>(&mut self $($fn_params)*) -> ::std::result::Result<::std::vec::Vec<R>,E>
+        where R: ::std::convert::TryFrom<::postgres::Row>, E: ::std::convert::From<R::Error> + ::std::convert::From<$err>
+        {
+            if let Some((client, recorder)) = self.replay.recorder_mut() {
+                let recording = $crate::replay::Recording::new(stringify!($name), &[$((stringify!($arg), &$arg as $crate::Param)),*]);
+                let res : ::std::result::Result<::std::vec::Vec<$crate::replay::RawRow>, $err> = <::postgres::Client as Statements>::$name(client $(, $arg)*);
+                return $crate::canned::collect(recorder.record_collected(recording, res));
+            }
+            $crate::canned::collect(self.replay.answer(stringify!($name), $crate::annotations!($name $doc), &[$((stringify!($arg), &$arg as $crate::Param)),*]).fetch::<$err>())
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident ! $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        fn $name<$($gen_type : ::postgres::types::ToSql),*>(&mut self $($fn_params)*) -> ::std::result::Result<u64,$err> {
+            if let Some((client, recorder)) = self.replay.recorder_mut() {
+                let recording = $crate::replay::Recording::new(stringify!($name), &[$((stringify!($arg), &$arg as $crate::Param)),*]);
+                let res = <::postgres::Client as Statements>::$name(client $(, $arg)*);
+                return recorder.record_affected(recording, res);
+            }
+            self.replay.answer(stringify!($name), $crate::annotations!($name $doc), &[$((stringify!($arg), &$arg as $crate::Param)),*]).rows_affected()
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident -> $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        fn $name<$($gen_type : ::postgres::types::ToSql),*>(&mut self $($fn_params)*) -> ::std::result::Result<::postgres::Row,$err> {
+            if let Some((client, recorder)) = self.replay.recorder_mut() {
+                let recording = $crate::replay::Recording::new(stringify!($name), &[$((stringify!($arg), &$arg as $crate::Param)),*]);
+                let res = <::postgres::Client as Statements>::$name(client $(, $arg)*);
+                return recorder.record_row(recording, res);
+            }
+            self.replay.answer(stringify!($name), $crate::annotations!($name $doc), &[$((stringify!($arg), &$arg as $crate::Param)),*]).row()
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) $param:ident : _ $($tail:tt)* ) => {
+        $crate::replay_method!{
+            ($err, $app)
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)*)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : impl ::postgres::types::ToSql + Sync)
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) $param:ident : ($ptype:ty) $($tail:tt)* ) => {
+        $crate::replay_method!{
+            ($err, $app)
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)*)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : $crate::arg_type!($doc $param $ptype))
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) $param:ident # [$gtype:ident] $($tail:tt)* ) => {
+        $crate::replay_method!{
+            ($err, $app)
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)*)
+            ($($gen_type)* $gtype)
+            ($($fn_params)* , $param : & [ $gtype ] )
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:ident)*) ($($gen_type:ident)*) ($($fn_params:tt)*) $param:ident # ($ptype:ty) $($tail:tt)* ) => {
+        $crate::replay_method!{
+            ($err, $app)
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)*)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : & [ $ptype ] )
+            $($tail)*
+        }
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! reconnect_method {
+    ( ($err:ty, no) $sql_name:ident ? $name:ident $doc:literal ($($arg:tt)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        fn $name<$($gen_type : ::postgres::types::ToSql ,)* F>(&mut self $($fn_params)* , mut row_cb: F) -> ::std::result::Result<(),$err>
+        where F: FnMut(::postgres::Row) -> ::std::result::Result<(),$err>
+        {
+            let delivered = ::std::cell::Cell::new(false);
+            let mut row_cb = |row| { delivered.set(true); row_cb(row) };
+            $crate::reconnect_call!(($err) self client $sql_name $name (!delivered.get() && $crate::annotations!($name $doc).idempotent(true)) =>
+                <::postgres::Client as Statements>::$name(client $($arg)* , &mut row_cb)
+            )
+        }
+    };
+    ( ($err:ty, yes) $sql_name:ident ? $name:ident $doc:literal ($($arg:tt)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        fn $name<$($gen_type : ::postgres::types::ToSql ,)* F, E>(&mut self $($fn_params)* , mut row_cb: F) -> ::std::result::Result<(),E>
+        where F: FnMut(::postgres::Row) -> ::std::result::Result<(),E>, E: ::std::convert::From<$err>
+        {
+            let delivered = ::std::cell::Cell::new(false);
+            let mut row_cb = |row| { delivered.set(true); row_cb(row) };
+            // The call returns the error type chosen by the caller, thus a lost session is detected only by the closed connection.
+            $crate::reconnect_call!(($err) self client $sql_name $name (!delivered.get() && $crate::annotations!($name $doc).idempotent(true), |_: &E| false) =>
+                <::postgres::Client as Statements>::$name(client $($arg)* , &mut row_cb)
+            )
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident ^ $name:ident $doc:literal ($($arg:tt)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        fn $name<'a $(, $gen_type : ::postgres::types::ToSql)*>(&'a mut self $($fn_params)*) -> ::std::result::Result<$crate::RowIter<'a>,$err> {
+            <::postgres::Client as Statements>::$name(self.connected().map_err($crate::util::connect_error::<$err>(::std::stringify!($sql_name), ::std::stringify!($name)))? $($arg)*)
+        }
+    };
+    ( ($err:ty, no) $sql_name:ident % $name:ident $doc:literal ($($arg:tt)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        fn $name<$($gen_type : ::postgres::types::ToSql ,)* R>(&mut self $($fn_params)*) -> ::std::result::Result<::std::vec::Vec<R>,$err>
+        where R: ::std::convert::TryFrom<::postgres::Row>, $err: ::std::convert::From<R::Error>
+        {
+            $crate::reconnect_call!(($err) self client $sql_name $name ($crate::annotations!($name $doc).idempotent(true)) =>
+                <::postgres::Client as Statements>::$name(client $($arg)*)
+            )
+        }
+    };
+    ( ($err:ty, yes) $sql_name:ident % $name:ident $doc:literal ($($arg:tt)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        fn $name<$($gen_type : ::postgres::types::ToSql ,)* R, E>(&mut self $($fn_params)*) -> ::std::result::Result<::std::vec::Vec<R>,E>
+        where R: ::std::convert::TryFrom<::postgres::Row>, E: ::std::convert::From<R::Error> + ::std::convert::From<$err>
+        {
+            $crate::reconnect_call!(($err) self client $sql_name $name ($crate::annotations!($name $doc).idempotent(true), |_: &E| false) =>
+                <::postgres::Client as Statements>::$name(client $($arg)*)
+            )
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident ! $name:ident $doc:literal ($($arg:tt)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        fn $name<$($gen_type : ::postgres::types::ToSql),*>(&mut self $($fn_params)*) -> ::std::result::Result<u64,$err> {
+            $crate::reconnect_call!(($err) self client $sql_name $name ($crate::annotations!($name $doc).idempotent(false)) =>
+                <::postgres::Client as Statements>::$name(client $($arg)*)
+            )
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident -> $name:ident $doc:literal ($($arg:tt)*) ($($gen_type:ident)*) ($($fn_params:tt)*) ) => {
+        fn $name<$($gen_type : ::postgres::types::ToSql),*>(&mut self $($fn_params)*) -> ::std::result::Result<::postgres::Row,$err> {
+            $crate::reconnect_call!(($err) self client $sql_name $name ($crate::annotations!($name $doc).idempotent(false)) =>
+                <::postgres::Client as Statements>::$name(client $($arg)*)
+            )
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:tt)*) ($($gen_type:ident)*) ($($fn_params:tt)*) $param:ident : _ $($tail:tt)* ) => {
+        $crate::reconnect_method!{
+            ($err, $app)
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)* , & $param)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : impl ::postgres::types::ToSql + Sync)
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:tt)*) ($($gen_type:ident)*) ($($fn_params:tt)*) $param:ident : ($ptype:ty) $($tail:tt)* ) => {
+        $crate::reconnect_method!{
+            ($err, $app)
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)* , ::std::clone::Clone::clone(& $param))
+            ($($gen_type)*)
+            ($($fn_params)* , $param : $crate::arg_type!($doc $param $ptype))
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:tt)*) ($($gen_type:ident)*) ($($fn_params:tt)*) $param:ident # [$gtype:ident] $($tail:tt)* ) => {
+        $crate::reconnect_method!{
+            ($err, $app)
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)* , $param)
+            ($($gen_type)* $gtype)
+            ($($fn_params)* , $param : & [ $gtype ] )
+            $($tail)*
+        }
+    };
+    ( ($err:ty, $app:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($arg:tt)*) ($($gen_type:ident)*) ($($fn_params:tt)*) $param:ident # ($ptype:ty) $($tail:tt)* ) => {
+        $crate::reconnect_method!{
+            ($err, $app)
+            $sql_name
+            $kind
+            $name
+            $doc
+            ($($arg)* , $param)
+            ($($gen_type)*)
+            ($($fn_params)* , $param : & [ $ptype ] )
+            $($tail)*
+        }
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! reconnect_call {
+    ( ($err:ty) $this:ident $client:ident $sql_name:ident $name:ident ($retry:expr) => $call:expr) => {
+        $crate::reconnect_call!(($err) $this $client $sql_name $name ($retry, |err: &$err| $crate::util::MethodError::driver_error(err).is_some_and($crate::util::session_terminated)) => $call)
+    };
+    ( ($err:ty) $this:ident $client:ident $sql_name:ident $name:ident ($retry:expr, $terminated:expr) => $call:expr) => {{
+        let $client = $this.connected().map_err($crate::util::connect_error::<$err>(::std::stringify!($sql_name), ::std::stringify!($name)))?;
+        match $call {
+            Err(err) if $client.is_closed() || $terminated(&err) => {
+                if $retry {
+                    let $client = $this.reconnect().map_err($crate::util::connect_error::<$err>(::std::stringify!($sql_name), ::std::stringify!($name)))?;
+                    $call
+                } else {
+                    $this.session_terminated();
+                    Err(err)
+                }
+            }
+            res => res,
+        }
+    }};
+}
+
 #[macro_export]
 #[doc(hidden)]
 macro_rules! impl_method {
-    ( ? $name:ident () () () => () $text:literal ) => {
-        fn $name<F>(&mut self, mut row_cb: F) -> ::std::result::Result<(),::postgres::Error>
-        where F: FnMut(::postgres::Row) -> ::std::result::Result<(),::postgres::Error>
+    ( ($err:ty, $hooks:ident) $sql_name:ident ? $name:ident $doc:literal () () () => () $text:literal ) => {
+        fn $name<F, E>(&mut self, mut row_cb: F) -> ::std::result::Result<(),E>
+        where F: FnMut(::postgres::Row) -> ::std::result::Result<(),E>, E: ::std::convert::From<$err>
         {
             use ::postgres::fallible_iterator::FallibleIterator;
 
-            let mut rows = self.query_raw( $text, [] as [&dyn ::postgres::types::ToSql; 0] )?;
-            while let Some(row) = rows.next()? {
-                row_cb(row)?;
-            }
-            Ok(())
+            let sql = $crate::util::with_trace_context($crate::tagged_sql!($sql_name $name $text));
+            let annotations = $crate::annotations!($name $doc);
+            $crate::call_stmt!(($err, $hooks) self $sql_name $name annotations (&*sql, None, $crate::call_params!(), ::std::vec::Vec::new()) -> $crate::util::CallFailure<E> {
+                let mut num_rows = 0u64;
+                let mut rows = self.query_raw( &*sql, [] as [&dyn ::postgres::types::ToSql; 0] )?;
+                while let Some(row) = rows.next()? {
+                    row_cb(row).map_err($crate::util::CallFailure::Aborted)?;
+                    num_rows += 1;
+                }
+                Ok(num_rows)
+            }).map(|_| ())
         }
     };
-    ( ? $name:ident () ($($fn_params:tt)+) () => (: $head:ident $(: $tail:ident)*) $($text:tt)+) => {
-        fn $name<F>(&mut self $($fn_params)+ , mut row_cb: F) -> ::std::result::Result<(),::postgres::Error>
-        where F: FnMut(::postgres::Row) -> ::std::result::Result<(),::postgres::Error>
+    ( ($err:ty, $hooks:ident) $sql_name:ident ? $name:ident $doc:literal () ($($fn_params:tt)+) () => (: $head:ident $(: $tail:ident)*) $($text:tt)+) => {
+        fn $name<F, E>(&mut self $($fn_params)+ , mut row_cb: F) -> ::std::result::Result<(),E>
+        where F: FnMut(::postgres::Row) -> ::std::result::Result<(),E>, E: ::std::convert::From<$err>
         {
+            $crate::bind_defaults!($doc : $head $(: $tail)*);
             use ::postgres::fallible_iterator::FallibleIterator;
 
-            let mut rows = self.query_raw(
-                $crate::sql_literal!( $head $($tail)* => $($text)+ ) ,
-                [& $head as &(dyn ::postgres::types::ToSql + Sync) $(, & $tail)* ]
-            )?;
-            while let Some(row) = rows.next()? {
-                row_cb(row)?;
-            }
-            Ok(())
+            let sql = $crate::util::with_trace_context($crate::tagged_sql!($sql_name $name $crate::sql_literal!( $head $($tail)* => $($text)+ )));
+            let annotations = $crate::annotations!($name $doc);
+            $crate::call_stmt!(($err, $hooks) self $sql_name $name annotations (&*sql, None, $crate::call_params!(: $head $(: $tail)*), ::std::vec![& $head as &dyn ::postgres::types::ToSql $(, & $tail as &dyn ::postgres::types::ToSql)*]) -> $crate::util::CallFailure<E> {
+                let mut num_rows = 0u64;
+                let mut rows = self.query_raw(
+                    &*sql ,
+                    [& $head as &(dyn ::postgres::types::ToSql + Sync) $(, & $tail)* ]
+                )?;
+                while let Some(row) = rows.next()? {
+                    row_cb(row).map_err($crate::util::CallFailure::Aborted)?;
+                    num_rows += 1;
+                }
+                Ok(num_rows)
+            }).map(|_| ())
         }
     };
-    ( ? $name:ident ($($gen_type:ident)*) ($($fn_params:tt)+) () => ($($pv:tt $param:ident)+) $($text:tt)+) => {
-        fn $name<$($gen_type : ::postgres::types::ToSql ,)* F>(&mut self $($fn_params)+, mut row_cb: F) -> ::std::result::Result<(),::postgres::Error>
-        where F: FnMut(::postgres::Row) -> ::std::result::Result<(),::postgres::Error>
+    ( ($err:ty, $hooks:ident) $sql_name:ident ? $name:ident $doc:literal ($($gen_type:ident)*) ($($fn_params:tt)+) () => ($($pv:tt $param:ident)+) $($text:tt)+) => {
+        fn $name<$($gen_type : ::postgres::types::ToSql ,)* F, E>(&mut self $($fn_params)+, mut row_cb: F) -> ::std::result::Result<(),E>
+        where F: FnMut(::postgres::Row) -> ::std::result::Result<(),E>, E: ::std::convert::From<$err>
         {
+            $crate::bind_defaults!($doc $($pv $param)+);
             use ::postgres::fallible_iterator::FallibleIterator;
 
             let mut stmt = ::std::string::String::with_capacity($crate::sql_len!($($text)+));
             let mut args = ::std::vec::Vec::<&dyn ::postgres::types::ToSql>::with_capacity($crate::num_args!($($pv $param)+));
             let mut i = 0;
             $crate::dynamic_sql!(stmt args i $($text)+);
-            let mut rows = self.query_raw(&stmt, args)?;
-            while let Some(row) = rows.next()? {
-                row_cb(row)?;
-            }
-            Ok(())
+            $crate::util::tag_sql(&mut stmt, $crate::sql_comment!($sql_name $name));
+            let annotations = $crate::annotations!($name $doc);
+            $crate::call_stmt!(($err, $hooks) self $sql_name $name annotations (&stmt, Some($crate::in_list_len!($($pv $param)+)), $crate::call_params!($($pv $param)+), args.iter().map(|arg| *arg as &dyn ::postgres::types::ToSql).collect()) -> $crate::util::CallFailure<E> {
+                let mut num_rows = 0u64;
+                let mut rows = self.query_raw(&stmt, args.iter().copied())?;
+                while let Some(row) = rows.next()? {
+                    row_cb(row).map_err($crate::util::CallFailure::Aborted)?;
+                    num_rows += 1;
+                }
+                Ok(num_rows)
+            }).map(|_| ())
         }
     };
-    ( ^ $name:ident () () () => () $text:literal ) => {
-        fn $name<'a>(&'a mut self) -> ::std::result::Result<::postgres::RowIter<'a>,::postgres::Error> {
-            self.query_raw( $text, [] as [&dyn ::postgres::types::ToSql; 0] )
+    ( ($err:ty, $hooks:ident) $sql_name:ident ^ $name:ident $doc:literal () () () => () $text:literal ) => {
+        fn $name<'a>(&'a mut self) -> ::std::result::Result<$crate::RowIter<'a>,$err> {
+            let sql = $crate::util::with_trace_context($crate::tagged_sql!($sql_name $name $text));
+            let annotations = $crate::annotations!($name $doc);
+            let started = ::std::time::Instant::now();
+            let metrics = $crate::util::CallMetrics::start(::std::stringify!($sql_name), ::std::stringify!($name));
+            let span = $crate::stmt_span!($name);
+            let _entered = span.enter();
+            $crate::util::trace_statement(&span, &*sql, None);
+            let params = $crate::call_params!();
+            let call = $crate::Call { sql_trait: ::std::stringify!($sql_name), name: ::std::stringify!($name), statement: &*sql, parameters: &params };
+            let interceptor = $crate::hooks!($hooks);
+            let res = match $crate::sync::intercept::<$crate::util::CallFailure<$err>>(interceptor.as_deref(), &call).and_then(|_| $crate::sync::before_call(self, annotations)) {
+                Ok(_) => self.query_raw( &*sql, [] as [&dyn ::postgres::types::ToSql; 0] ).map_err($crate::util::CallFailure::from),
+                Err(err) => Err(err),
+            };
+            if let Some(log) = $crate::SlowQueryLog::exceeded(started) {
+                log.report(&log.slow_query(::std::stringify!($sql_name), ::std::stringify!($name), &*sql, started, &params));
+            }
+            $crate::sync::rows::<$err>(res, &call, annotations, interceptor, span.clone(), metrics, started)
         }
     };
-    ( ^ $name:ident () ($($fn_params:tt)+) () => (: $head:ident $(: $tail:ident)*) $($text:tt)+) => {
-        fn $name<'a>(&'a mut self $($fn_params)+) -> ::std::result::Result<::postgres::RowIter<'a>,::postgres::Error> {
-            self.query_raw(
-                $crate::sql_literal!( $head $($tail)* => $($text)+ ) ,
-                [& $head as &(dyn ::postgres::types::ToSql + Sync) $(, & $tail)* ]
-            )
+    ( ($err:ty, $hooks:ident) $sql_name:ident ^ $name:ident $doc:literal () ($($fn_params:tt)+) () => (: $head:ident $(: $tail:ident)*) $($text:tt)+) => {
+        fn $name<'a>(&'a mut self $($fn_params)+) -> ::std::result::Result<$crate::RowIter<'a>,$err> {
+            $crate::bind_defaults!($doc : $head $(: $tail)*);
+            let sql = $crate::util::with_trace_context($crate::tagged_sql!($sql_name $name $crate::sql_literal!( $head $($tail)* => $($text)+ )));
+            let annotations = $crate::annotations!($name $doc);
+            let started = ::std::time::Instant::now();
+            let metrics = $crate::util::CallMetrics::start(::std::stringify!($sql_name), ::std::stringify!($name));
+            let span = $crate::stmt_span!($name);
+            let _entered = span.enter();
+            $crate::util::trace_statement(&span, &*sql, None);
+            let params = $crate::call_params!(: $head $(: $tail)*);
+            let call = $crate::Call { sql_trait: ::std::stringify!($sql_name), name: ::std::stringify!($name), statement: &*sql, parameters: &params };
+            let interceptor = $crate::hooks!($hooks);
+            let res = match $crate::sync::intercept::<$crate::util::CallFailure<$err>>(interceptor.as_deref(), &call).and_then(|_| $crate::sync::before_call(self, annotations)) {
+                Ok(_) => self.query_raw(
+                    &*sql ,
+                    [& $head as &(dyn ::postgres::types::ToSql + Sync) $(, & $tail)* ]
+                ).map_err($crate::util::CallFailure::from),
+                Err(err) => Err(err),
+            };
+            if let Some(log) = $crate::SlowQueryLog::exceeded(started) {
+                log.report(&log.slow_query(::std::stringify!($sql_name), ::std::stringify!($name), &*sql, started, &params));
+            }
+            $crate::sync::rows::<$err>(res, &call, annotations, interceptor, span.clone(), metrics, started)
         }
     };
-    ( ^ $name:ident ($($gen_type:ident)*) ($($fn_params:tt)+) () => ($($pv:tt $param:ident)+) $($text:tt)+) => {
-        fn $name<'a $(, $gen_type : ::postgres::types::ToSql)*>(&'a mut self $($fn_params)+) -> ::std::result::Result<::postgres::RowIter<'a>,::postgres::Error> {
+    ( ($err:ty, $hooks:ident) $sql_name:ident ^ $name:ident $doc:literal ($($gen_type:ident)*) ($($fn_params:tt)+) () => ($($pv:tt $param:ident)+) $($text:tt)+) => {
+        fn $name<'a $(, $gen_type : ::postgres::types::ToSql)*>(&'a mut self $($fn_params)+) -> ::std::result::Result<$crate::RowIter<'a>,$err> {
+            $crate::bind_defaults!($doc $($pv $param)+);
             let mut stmt = ::std::string::String::with_capacity($crate::sql_len!($($text)+));
             let mut args = ::std::vec::Vec::<&dyn ::postgres::types::ToSql>::with_capacity($crate::num_args!($($pv $param)+));
             let mut i = 0;
             $crate::dynamic_sql!(stmt args i $($text)+);
-            self.query_raw(&stmt, args)
+            $crate::util::tag_sql(&mut stmt, $crate::sql_comment!($sql_name $name));
+            let annotations = $crate::annotations!($name $doc);
+            let started = ::std::time::Instant::now();
+            let metrics = $crate::util::CallMetrics::start(::std::stringify!($sql_name), ::std::stringify!($name));
+            let span = $crate::stmt_span!($name);
+            let _entered = span.enter();
+            $crate::util::trace_statement(&span, &stmt, Some($crate::in_list_len!($($pv $param)+)));
+            let params = $crate::call_params!($($pv $param)+);
+            let call = $crate::Call { sql_trait: ::std::stringify!($sql_name), name: ::std::stringify!($name), statement: &stmt, parameters: &params };
+            let interceptor = $crate::hooks!($hooks);
+            let res = match $crate::sync::intercept::<$crate::util::CallFailure<$err>>(interceptor.as_deref(), &call).and_then(|_| $crate::sync::before_call(self, annotations)) {
+                Ok(_) => self.query_raw(&stmt, args).map_err($crate::util::CallFailure::from),
+                Err(err) => Err(err),
+            };
+            if let Some(log) = $crate::SlowQueryLog::exceeded(started) {
+                log.report(&log.slow_query(::std::stringify!($sql_name), ::std::stringify!($name), &stmt, started, &params));
+            }
+            $crate::sync::rows::<$err>(res, &call, annotations, interceptor, span.clone(), metrics, started)
         }
     };
-    ( % $name:ident () () () => () $text:literal ) => {
-        fn $name<R>(&mut self) -> ::std::result::Result<::std::vec::Vec<R>,::postgres::Error>
-        where R: ::std::convert::TryFrom<::postgres::Row>, ::postgres::Error: ::std::convert::From<R::Error>
+    ( ($err:ty, $hooks:ident) $sql_name:ident % $name:ident $doc:literal () () () => () $text:literal ) => {
+        fn $name<R, E>(&mut self) -> ::std::result::Result<::std::vec::Vec<R>,E>
+        where R: ::std::convert::TryFrom<::postgres::Row>, E: ::std::convert::From<R::Error> + ::std::convert::From<$err>
         {
             use ::postgres::fallible_iterator::FallibleIterator;
-            let mut data = ::std::vec::Vec::new();
-            let mut rows = self.query_raw( $text, [] as [&dyn ::postgres::types::ToSql; 0] )?;
-            while let Some(row) = rows.next()? {
-                let item = R::try_from(row)?;
-                data.push(item);
-            }
-            Ok(data)
+            let sql = $crate::util::with_trace_context($crate::tagged_sql!($sql_name $name $text));
+            let annotations = $crate::annotations!($name $doc);
+            $crate::call_stmt!(($err, $hooks) self $sql_name $name annotations (&*sql, None, $crate::call_params!(), ::std::vec::Vec::new()) -> $crate::util::CallFailure<E> {
+                let mut data = ::std::vec::Vec::new();
+                let mut rows = self.query_raw( &*sql, [] as [&dyn ::postgres::types::ToSql; 0] )?;
+                while let Some(row) = rows.next()? {
+                    let item = R::try_from(row).map_err(|err| $crate::util::CallFailure::Aborted(E::from(err)))?;
+                    data.push(item);
+                }
+                Ok(data)
+            })
         }
     };
-    ( % $name:ident () ($($fn_params:tt)+) () => (: $head:ident $(: $tail:ident)*) $($text:tt)+) => {
-        fn $name<R>(&mut self $($fn_params)+) -> ::std::result::Result<::std::vec::Vec<R>,::postgres::Error>
-        where R: ::std::convert::TryFrom<::postgres::Row>, ::postgres::Error: ::std::convert::From<R::Error>
+    ( ($err:ty, $hooks:ident) $sql_name:ident % $name:ident $doc:literal () ($($fn_params:tt)+) () => (: $head:ident $(: $tail:ident)*) $($text:tt)+) => {
+        fn $name<R, E>(&mut self $($fn_params)+) -> ::std::result::Result<::std::vec::Vec<R>,E>
+        where R: ::std::convert::TryFrom<::postgres::Row>, E: ::std::convert::From<R::Error> + ::std::convert::From<$err>
         {
+            $crate::bind_defaults!($doc : $head $(: $tail)*);
             use ::postgres::fallible_iterator::FallibleIterator;
-            let mut data = ::std::vec::Vec::new();
-            let mut rows = self.query_raw(
-                $crate::sql_literal!( $head $($tail)* => $($text)+ ) ,
-                [& $head as &(dyn ::postgres::types::ToSql + Sync) $(, & $tail)* ]
-            )?;
-            while let Some(row) = rows.next()? {
-                let item = R::try_from(row)?;
-                data.push(item);
-            }
-            Ok(data)
+            let sql = $crate::util::with_trace_context($crate::tagged_sql!($sql_name $name $crate::sql_literal!( $head $($tail)* => $($text)+ )));
+            let annotations = $crate::annotations!($name $doc);
+            $crate::call_stmt!(($err, $hooks) self $sql_name $name annotations (&*sql, None, $crate::call_params!(: $head $(: $tail)*), ::std::vec![& $head as &dyn ::postgres::types::ToSql $(, & $tail as &dyn ::postgres::types::ToSql)*]) -> $crate::util::CallFailure<E> {
+                let mut data = ::std::vec::Vec::new();
+                let mut rows = self.query_raw(
+                    &*sql ,
+                    [& $head as &(dyn ::postgres::types::ToSql + Sync) $(, & $tail)* ]
+                )?;
+                while let Some(row) = rows.next()? {
+                    let item = R::try_from(row).map_err(|err| $crate::util::CallFailure::Aborted(E::from(err)))?;
+                    data.push(item);
+                }
+                Ok(data)
+            })
         }
     };
-    ( % $name:ident ($($gen_type:ident)*) ($($fn_params:tt)+) () => ($($pv:tt $param:ident)+) $($text:tt)+) => {
-        fn $name<$($gen_type : ::postgres::types::ToSql ,)* R>(&mut self $($fn_params)+) -> ::std::result::Result<::std::vec::Vec<R>,::postgres::Error>
-        where R: ::std::convert::TryFrom<::postgres::Row>, ::postgres::Error: ::std::convert::From<R::Error>
+    ( ($err:ty, $hooks:ident) $sql_name:ident % $name:ident $doc:literal ($($gen_type:ident)*) ($($fn_params:tt)+) () => ($($pv:tt $param:ident)+) $($text:tt)+) => {
+        fn $name<$($gen_type : ::postgres::types::ToSql ,)* R, E>(&mut self $($fn_params)+) -> ::std::result::Result<::std::vec::Vec<R>,E>
+        where R: ::std::convert::TryFrom<::postgres::Row>, E: ::std::convert::From<R::Error> + ::std::convert::From<$err>
         {
+            $crate::bind_defaults!($doc $($pv $param)+);
             use ::postgres::fallible_iterator::FallibleIterator;
-            let mut data = ::std::vec::Vec::new();
             let mut stmt = ::std::string::String::with_capacity($crate::sql_len!($($text)+));
             let mut args = ::std::vec::Vec::<&dyn ::postgres::types::ToSql>::with_capacity($crate::num_args!($($pv $param)+));
             let mut i = 0;
             $crate::dynamic_sql!(stmt args i $($text)+);
-            let mut rows = self.query_raw(&stmt, args)?;
-            while let Some(row) = rows.next()? {
-                let item = R::try_from(row)?;
-                data.push(item);
-            }
-            Ok(data)
+            $crate::util::tag_sql(&mut stmt, $crate::sql_comment!($sql_name $name));
+            let annotations = $crate::annotations!($name $doc);
+            $crate::call_stmt!(($err, $hooks) self $sql_name $name annotations (&stmt, Some($crate::in_list_len!($($pv $param)+)), $crate::call_params!($($pv $param)+), args.iter().map(|arg| *arg as &dyn ::postgres::types::ToSql).collect()) -> $crate::util::CallFailure<E> {
+                let mut data = ::std::vec::Vec::new();
+                let mut rows = self.query_raw(&stmt, args.iter().copied())?;
+                while let Some(row) = rows.next()? {
+                    let item = R::try_from(row).map_err(|err| $crate::util::CallFailure::Aborted(E::from(err)))?;
+                    data.push(item);
+                }
+                Ok(data)
+            })
         }
     };
-    ( ! $name:ident () () () => () $text:literal ) => {
-        fn $name(&mut self) -> ::std::result::Result<u64,::postgres::Error> {
-            self.execute( $text, &[] )
+    ( ($err:ty, $hooks:ident) $sql_name:ident ! $name:ident $doc:literal () () () => () $text:literal ) => {
+        fn $name(&mut self) -> ::std::result::Result<u64,$err> {
+            let sql = $crate::util::with_trace_context($crate::tagged_sql!($sql_name $name $text));
+            let annotations = $crate::annotations!($name $doc);
+            $crate::call_stmt!(($err, $hooks) self $sql_name $name annotations (&*sql, None, $crate::call_params!(), ::std::vec::Vec::new()) -> $crate::util::CallFailure<$err> {
+                let num_rows = self.execute( &*sql, &[] )?;
+                Ok(annotations.expect_rows(num_rows)?)
+            })
         }
     };
-    ( ! $name:ident () ($($fn_params:tt)+) () => (: $head:ident $(: $tail:ident)*) $($text:tt)+) => {
-        fn $name(&mut self $($fn_params)+ ) -> ::std::result::Result<u64,::postgres::Error> {
-            self.execute(
-                $crate::sql_literal!( $head $($tail)* => $($text)+ ) ,
-                &[& $head as &(dyn ::postgres::types::ToSql + Sync) $(, & $tail)* ]
-            )
+    ( ($err:ty, $hooks:ident) $sql_name:ident ! $name:ident $doc:literal () ($($fn_params:tt)+) () => (: $head:ident $(: $tail:ident)*) $($text:tt)+) => {
+        fn $name(&mut self $($fn_params)+ ) -> ::std::result::Result<u64,$err> {
+            $crate::bind_defaults!($doc : $head $(: $tail)*);
+            let sql = $crate::util::with_trace_context($crate::tagged_sql!($sql_name $name $crate::sql_literal!( $head $($tail)* => $($text)+ )));
+            let annotations = $crate::annotations!($name $doc);
+            $crate::call_stmt!(($err, $hooks) self $sql_name $name annotations (&*sql, None, $crate::call_params!(: $head $(: $tail)*), ::std::vec![& $head as &dyn ::postgres::types::ToSql $(, & $tail as &dyn ::postgres::types::ToSql)*]) -> $crate::util::CallFailure<$err> {
+                let num_rows = self.execute(
+                    &*sql ,
+                    &[& $head as &(dyn ::postgres::types::ToSql + Sync) $(, & $tail)* ]
+                )?;
+                Ok(annotations.expect_rows(num_rows)?)
+            })
         }
     };
-    ( ! $name:ident ($($gen_type:ident)*) ($($fn_params:tt)+) () => ($($pv:tt $param:ident)+) $($text:tt)+) => {
-        fn $name<$($gen_type : ::postgres::types::ToSql),*>(&mut self $($fn_params)+ ) -> ::std::result::Result<u64,::postgres::Error> {
+    ( ($err:ty, $hooks:ident) $sql_name:ident ! $name:ident $doc:literal ($($gen_type:ident)*) ($($fn_params:tt)+) () => ($($pv:tt $param:ident)+) $($text:tt)+) => {
+        fn $name<$($gen_type : ::postgres::types::ToSql),*>(&mut self $($fn_params)+ ) -> ::std::result::Result<u64,$err> {
+            $crate::bind_defaults!($doc $($pv $param)+);
             let mut stmt = ::std::string::String::with_capacity($crate::sql_len!($($text)+));
             let mut args = ::std::vec::Vec::<&(dyn ::postgres::types::ToSql + Sync)>::with_capacity($crate::num_args!($($pv $param)+));
             let mut i = 0;
             $crate::dynamic_sql!(stmt args i $($text)+);
-            self.execute(&stmt, args.as_slice())
+            $crate::util::tag_sql(&mut stmt, $crate::sql_comment!($sql_name $name));
+            let annotations = $crate::annotations!($name $doc);
+            $crate::call_stmt!(($err, $hooks) self $sql_name $name annotations (&stmt, Some($crate::in_list_len!($($pv $param)+)), $crate::call_params!($($pv $param)+), args.iter().map(|arg| *arg as &dyn ::postgres::types::ToSql).collect()) -> $crate::util::CallFailure<$err> {
+                let num_rows = self.execute(&stmt, args.as_slice())?;
+                Ok(annotations.expect_rows(num_rows)?)
+            })
         }
     };
-    ( -> $name:ident () () () => () $text:literal ) => {
-        fn $name(&mut self) -> ::std::result::Result<::postgres::Row,::postgres::Error> {
-            self.query_one( $text, &[] )
+    ( ($err:ty, $hooks:ident) $sql_name:ident -> $name:ident $doc:literal () () () => () $text:literal ) => {
+        fn $name(&mut self) -> ::std::result::Result<::postgres::Row,$err> {
+            let sql = $crate::util::with_trace_context($crate::tagged_sql!($sql_name $name $text));
+            let annotations = $crate::annotations!($name $doc);
+            $crate::call_stmt!(($err, $hooks) self $sql_name $name annotations (&*sql, None, $crate::call_params!(), ::std::vec::Vec::new()) {
+                self.query_one( &*sql, &[] )
+            })
         }
     };
-    ( -> $name:ident () ($($fn_params:tt)+) () => (: $head:ident $(: $tail:ident)*) $($text:tt)+) => {
-        fn $name(&mut self $($fn_params)+ ) -> ::std::result::Result<::postgres::Row,::postgres::Error> {
-            self.query_one(
-                $crate::sql_literal!( $head $($tail)* => $($text)+ ) ,
-                &[& $head as &(dyn ::postgres::types::ToSql + Sync) $(, & $tail)* ]
-            )
+    ( ($err:ty, $hooks:ident) $sql_name:ident -> $name:ident $doc:literal () ($($fn_params:tt)+) () => (: $head:ident $(: $tail:ident)*) $($text:tt)+) => {
+        fn $name(&mut self $($fn_params)+ ) -> ::std::result::Result<::postgres::Row,$err> {
+            $crate::bind_defaults!($doc : $head $(: $tail)*);
+            let sql = $crate::util::with_trace_context($crate::tagged_sql!($sql_name $name $crate::sql_literal!( $head $($tail)* => $($text)+ )));
+            let annotations = $crate::annotations!($name $doc);
+            $crate::call_stmt!(($err, $hooks) self $sql_name $name annotations (&*sql, None, $crate::call_params!(: $head $(: $tail)*), ::std::vec![& $head as &dyn ::postgres::types::ToSql $(, & $tail as &dyn ::postgres::types::ToSql)*]) {
+                self.query_one(
+                    &*sql ,
+                    &[& $head as &(dyn ::postgres::types::ToSql + Sync) $(, & $tail)* ]
+                )
+            })
         }
     };
-    ( -> $name:ident ($($gen_type:ident)*) ($($fn_params:tt)+) () => ($($pv:tt $param:ident)+) $($text:tt)+) => {
-        fn $name<$($gen_type : ::postgres::types::ToSql),*>(&mut self $($fn_params)+ ) -> ::std::result::Result<::postgres::Row,::postgres::Error> {
+    ( ($err:ty, $hooks:ident) $sql_name:ident -> $name:ident $doc:literal ($($gen_type:ident)*) ($($fn_params:tt)+) () => ($($pv:tt $param:ident)+) $($text:tt)+) => {
+        fn $name<$($gen_type : ::postgres::types::ToSql),*>(&mut self $($fn_params)+ ) -> ::std::result::Result<::postgres::Row,$err> {
+            $crate::bind_defaults!($doc $($pv $param)+);
             let mut stmt = ::std::string::String::with_capacity($crate::sql_len!($($text)+));
-            let mut args = ::std::vec::Vec::<&dyn ::postgres::types::ToSql>::with_capacity($crate::num_args!($($pv $param)+));
+            let mut args = ::std::vec::Vec::<&(dyn ::postgres::types::ToSql + Sync)>::with_capacity($crate::num_args!($($pv $param)+));
             let mut i = 0;
             $crate::dynamic_sql!(stmt args i $($text)+);
-            self.query_one(&stmt, &args)
+            $crate::util::tag_sql(&mut stmt, $crate::sql_comment!($sql_name $name));
+            let annotations = $crate::annotations!($name $doc);
+            $crate::call_stmt!(($err, $hooks) self $sql_name $name annotations (&stmt, Some($crate::in_list_len!($($pv $param)+)), $crate::call_params!($($pv $param)+), args.iter().map(|arg| *arg as &dyn ::postgres::types::ToSql).collect()) {
+                self.query_one(&stmt, &args)
+            })
         }
     };
-    ( $kind:tt $name:ident ($($gen_type:ident)*) ($($fn_params:tt)*) ($param:ident : _ $($tail:tt)*) => ($($pv:tt $param_name:ident)+) $($text:tt)+)  => {
+    ( ($err:ty, $hooks:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($gen_type:ident)*) ($($fn_params:tt)*) ($param:ident : _ $($tail:tt)*) => ($($pv:tt $param_name:ident)+) $($text:tt)+)  => {
         $crate::impl_method!{
+            ($err, $hooks)
+            $sql_name
             $kind
             $name
+            $doc
             ($($gen_type)*)
             ($($fn_params)* , $param : impl ::postgres::types::ToSql + Sync)
             ($($tail)*)
@@ -351,22 +1218,28 @@ macro_rules! impl_method {
             $($text)+
         }
     };
-    ( $kind:tt $name:ident ($($gen_type:ident)*) ($($fn_params:tt)*) ($param:ident : ($ptype:ty) $($tail:tt)*) => ($($pv:tt $param_name:ident)+) $($text:tt)+)  => {
+    ( ($err:ty, $hooks:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($gen_type:ident)*) ($($fn_params:tt)*) ($param:ident : ($ptype:ty) $($tail:tt)*) => ($($pv:tt $param_name:ident)+) $($text:tt)+)  => {
         $crate::impl_method!{
+            ($err, $hooks)
+            $sql_name
             $kind
             $name
+            $doc
             ($($gen_type)*)
-            ($($fn_params)* , $param : $ptype)
+            ($($fn_params)* , $param : $crate::arg_type!($doc $param $ptype))
             ($($tail)*)
             =>
             ($($pv $param_name)+)
             $($text)+
         }
     };
-    ( $kind:tt $name:ident ($($gen_type:ident)*) ($($fn_params:tt)*) ($param:ident # [$gtype:ident] $($tail:tt)*) => ($($pv:tt $param_name:ident)+) $($text:tt)+)  => {
+    ( ($err:ty, $hooks:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($gen_type:ident)*) ($($fn_params:tt)*) ($param:ident # [$gtype:ident] $($tail:tt)*) => ($($pv:tt $param_name:ident)+) $($text:tt)+)  => {
         $crate::impl_method!{
+            ($err, $hooks)
+            $sql_name
             $kind
             $name
+            $doc
             ($($gen_type)* $gtype)
             ($($fn_params)* , $param : & [ $gtype ])
             ($($tail)*)
@@ -375,10 +1248,13 @@ macro_rules! impl_method {
             $($text)+
         }
     };
-    ( $kind:tt $name:ident ($($gen_type:ident)*) ($($fn_params:tt)*) ($param:ident # ($ptype:ty) $($tail:tt)*) => ($($pv:tt $param_name:ident)+) $($text:tt)+)  => {
+    ( ($err:ty, $hooks:ident) $sql_name:ident $kind:tt $name:ident $doc:literal ($($gen_type:ident)*) ($($fn_params:tt)*) ($param:ident # ($ptype:ty) $($tail:tt)*) => ($($pv:tt $param_name:ident)+) $($text:tt)+)  => {
         $crate::impl_method!{
+            ($err, $hooks)
+            $sql_name
             $kind
             $name
+            $doc
             ($($gen_type)*)
             ($($fn_params)* , $param : & [ $ptype ])
             ($($tail)*)
@@ -388,3 +1264,508 @@ macro_rules! impl_method {
         }
     };
 }
+
+/// Tells generated methods whether they are executed within a transaction.
+pub trait Scope {
+    const IN_TRANSACTION: bool;
+}
+
+impl Scope for ::postgres::Client {
+    const IN_TRANSACTION: bool = false;
+}
+
+impl Scope for ::postgres::Transaction<'_> {
+    const IN_TRANSACTION: bool = true;
+}
+
+impl crate::util::Within<true> for ::postgres::Transaction<'_> {}
+
+/**
+Prepares the session for the execution of the named statement.
+
+See `util::Annotations::parse` for what is done. Returns what has to be done after the call.
+*/
+pub fn before_call<C, X>(client: &mut C, annotations: &crate::util::Annotations) -> Result<crate::util::AfterCall, X>
+where C: ::postgres::GenericClient + Scope, X: crate::util::Failure
+{
+    let (cmds, after) = annotations.before_call(C::IN_TRANSACTION);
+    let res = prepare_session(client, annotations, cmds);
+    if res.is_err() && after == crate::util::AfterCall::EndTransaction {
+        let _ = client.batch_execute("ROLLBACK");
+    }
+    res.map(|_| after)
+}
+
+/// Executes the commands that prepare the session and tries to acquire the `try_advisory_lock:` locks.
+fn prepare_session<C, X>(client: &mut C, annotations: &crate::util::Annotations, cmds: &str) -> Result<(), X>
+where C: ::postgres::GenericClient, X: crate::util::Failure
+{
+    if !cmds.is_empty() {
+        client.batch_execute(cmds)?;
+    }
+    if let Some(query) = annotations.try_lock_query() {
+        let row = client.query_one(query, &[])?;
+        if let Some(failure) = annotations.busy_lock(&row)? {
+            return Err(X::from(failure));
+        }
+    }
+    Ok(())
+}
+
+/// Ends the transaction that was started by `before_call`.
+pub fn after_call<C, T, X>(client: &mut C, annotations: &crate::util::Annotations, after: crate::util::AfterCall, res: Result<T, X>) -> Result<T, X>
+where C: ::postgres::GenericClient, X: crate::util::Failure
+{
+    match annotations.after_call(after, res.is_ok()) {
+        Some(cmds) => {
+            let after_res = client.batch_execute(cmds);
+            res.and_then(|data| after_res.map(|_| data).map_err(X::from))
+        }
+        None => res,
+    }
+}
+
+/// Calls the `before` hook of the interceptor and fails the call when the interceptor rejects it.
+pub fn intercept<X: crate::util::Failure>(interceptor: Option<&dyn crate::Interceptor>, call: &crate::Call) -> Result<(), X> {
+    match interceptor.map(|interceptor| interceptor.before(call)) {
+        Some(Err(rejection)) => Err(X::from(crate::CheckFailure::Rejected(rejection))),
+        _ => Ok(()),
+    }
+}
+
+/// Returns the execution plan of the slow statement or `None` if it cannot be obtained.
+pub fn explain<C>(client: &mut C, stmt: &str, args: Vec<&dyn ::postgres::types::ToSql>) -> Option<String>
+where C: ::postgres::GenericClient + Scope
+{
+    use ::postgres::fallible_iterator::FallibleIterator;
+
+    let explain = crate::slow_query::explain_command(stmt)?;
+    if C::IN_TRANSACTION {
+        client.batch_execute(crate::slow_query::EXPLAIN_SAVEPOINT).ok()?;
+    }
+    let plan = client.query_raw(explain.as_str(), args).and_then(|mut rows| rows.next());
+    if C::IN_TRANSACTION {
+        client.batch_execute(crate::slow_query::EXPLAIN_ROLLBACK).ok()?;
+    }
+    plan.ok()??.try_get::<_, crate::slow_query::Plan>(0).ok().map(|plan| plan.0)
+}
+
+/**
+Rows returned by a `^` method.
+
+The method call is completed - its tracing span is closed, its metrics are recorded, and the `after` hook of
+the interceptor is called - when the iterator is exhausted, fails to fetch the next row, or is dropped.
+
+```rust , ignore
+use postgres::fallible_iterator::FallibleIterator;
+
+let mut rows = db.get_loaned_books("mjones")?;
+while let Some(row) = rows.next()? {
+    let book_title : &str = row.try_get("book_title")?;
+    println!("{}", book_title);
+}
+```
+*/
+pub struct RowIter<'a> {
+    rows: Rows<'a>,
+    call: Option<crate::util::RowsCall>,
+}
+
+enum Rows<'a> {
+    Driver(::postgres::RowIter<'a>),
+    #[cfg(any(feature = "mock", feature = "replay"))]
+    Canned(::std::vec::IntoIter<::postgres::Row>, u64),
+}
+
+impl RowIter<'_> {
+    /// Returns the number of rows affected by the statement. Available only after the iterator is exhausted.
+    pub fn rows_affected(&self) -> Option<u64> {
+        match &self.rows {
+            Rows::Driver(rows) => rows.rows_affected(),
+            #[cfg(any(feature = "mock", feature = "replay"))]
+            Rows::Canned(rows, num_rows) => if rows.as_slice().is_empty() { Some(*num_rows) } else { None },
+        }
+    }
+
+    /// Iterates over canned rows.
+    #[cfg(any(feature = "mock", feature = "replay"))]
+    pub(crate) fn canned(rows: Vec<::postgres::Row>) -> Self {
+        let num_rows = rows.len() as u64;
+        Self { rows: Rows::Canned(rows.into_iter(), num_rows), call: None }
+    }
+}
+
+impl ::postgres::fallible_iterator::FallibleIterator for RowIter<'_> {
+    type Item = ::postgres::Row;
+    type Error = ::postgres::Error;
+
+    fn next(&mut self) -> Result<Option<Self::Item>, Self::Error> {
+        let res = match &mut self.rows {
+            Rows::Driver(rows) => rows.next(),
+            #[cfg(any(feature = "mock", feature = "replay"))]
+            Rows::Canned(rows, _) => Ok(rows.next()),
+        };
+        match &res {
+            Ok(Some(_)) => {
+                if let Some(call) = &mut self.call {
+                    call.row();
+                }
+            }
+            Ok(None) => {
+                if let Some(call) = self.call.take() {
+                    call.finish(None);
+                }
+            }
+            Err(err) => {
+                if let Some(call) = self.call.take() {
+                    call.finish(Some(err));
+                }
+            }
+        }
+        res
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.rows {
+            Rows::Driver(rows) => rows.size_hint(),
+            #[cfg(any(feature = "mock", feature = "replay"))]
+            Rows::Canned(rows, _) => rows.size_hint(),
+        }
+    }
+}
+
+impl Drop for RowIter<'_> {
+    fn drop(&mut self) {
+        if let Some(call) = self.call.take() {
+            call.finish(None);
+        }
+    }
+}
+
+/// Returns the rows that the `^` method call retrieved or completes the call that failed.
+pub fn rows<'a, M>(
+    res: Result<::postgres::RowIter<'a>, crate::util::CallFailure<M>>, call: &crate::Call, annotations: &crate::util::Annotations,
+    interceptor: Option<::std::sync::Arc<dyn crate::Interceptor>>, span: crate::util::Span, metrics: crate::util::CallMetrics, started: ::std::time::Instant,
+) -> Result<RowIter<'a>, M>
+where M: crate::util::MethodError
+{
+    let res = match res {
+        Ok(rows) => return Ok(RowIter { rows: Rows::Driver(rows), call: Some(crate::util::RowsCall::new(call, interceptor, span, metrics, started)) }),
+        Err(err) => Err::<RowIter, _>(err),
+    };
+    if let Some(interceptor) = &interceptor {
+        interceptor.after(call, crate::Outcome::of(&res), started.elapsed());
+    }
+    crate::util::trace_result(&span, &res);
+    metrics.record(&res);
+    crate::util::call_error::<M, _, _>(res, call.sql_trait, call.name, annotations, call.statement, call.parameters)
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! call_stmt {
+    ( ($err:ty, $hooks:ident) $client:ident $sql_name:ident $name:ident $annotations:ident ($stmt:expr, $in_list_len:expr, $params:expr, $args:expr) $body:block) => {
+        $crate::call_stmt!(($err, $hooks) $client $sql_name $name $annotations ($stmt, $in_list_len, $params, $args) -> $crate::util::CallFailure<$err> {
+            let res : ::std::result::Result<_,::postgres::Error> = $body;
+            res.map_err($crate::util::CallFailure::from)
+        })
+    };
+    ( ($err:ty, $hooks:ident) $client:ident $sql_name:ident $name:ident $annotations:ident ($stmt:expr, $in_list_len:expr, $params:expr, $args:expr) -> $failure:ty $body:block) => {{
+        let started = ::std::time::Instant::now();
+        let metrics = $crate::util::CallMetrics::start(::std::stringify!($sql_name), ::std::stringify!($name));
+        let span = $crate::stmt_span!($name);
+        let _entered = span.enter();
+        $crate::util::trace_statement(&span, $stmt, $in_list_len);
+        let params = $params;
+        let call = $crate::Call { sql_trait: ::std::stringify!($sql_name), name: ::std::stringify!($name), statement: $stmt, parameters: &params };
+        let interceptor = $crate::hooks!($hooks);
+        let res = (|| {
+            $crate::sync::intercept::<$failure>(interceptor.as_deref(), &call)?;
+            let after = $crate::sync::before_call::<_, $failure>($client, $annotations)?;
+            let res : ::std::result::Result<_,$failure> = (|| $body)();
+            $crate::sync::after_call($client, $annotations, after, res)
+        })();
+        if let Some(interceptor) = &interceptor {
+            interceptor.after(&call, $crate::Outcome::of(&res), started.elapsed());
+        }
+        $crate::util::trace_result(&span, &res);
+        metrics.record(&res);
+        if let Some(log) = $crate::SlowQueryLog::exceeded(started) {
+            let mut query = log.slow_query(::std::stringify!($sql_name), ::std::stringify!($name), $stmt, started, &params);
+            if log.explains() && res.is_ok() {
+                query.plan = $crate::sync::explain($client, $stmt, $args);
+            }
+            log.report(&query);
+        }
+        $crate::util::call_error::<$err, _, _>(res, ::std::stringify!($sql_name), ::std::stringify!($name), $annotations, $stmt, &params)
+    }};
+}
+
+impl crate::Router<::postgres::Client> {
+    /// Starts a transaction on the primary client.
+    pub fn transaction(&mut self) -> Result<::postgres::Transaction<'_>, ::postgres::Error> {
+        self.primary_mut().transaction()
+    }
+}
+
+type Connect = dyn Fn() -> Result<::postgres::Client, ::postgres::Error> + Send + Sync;
+
+/**
+A client that re-establishes the connection when it is lost.
+
+`impl_sql` implements the generated trait for `ReconnectingClient` when it is given the `reconnect` option.
+When the connection is found closed before a call, the client reconnects using the stored configuration
+before executing the statement.
+When the connection is lost during a call the statement is executed again on a new connection only if
+it is safe to do so, i.e. if the statement only reads data - `?`, `^`, and `%` statements and statements
+annotated with `read only` - or if it is annotated with `idempotent`. `?` statements are not retried if some of the rows were
+already passed to the callback, and `^` statements are never retried as the returned row iterator
+borrows the client.
+
+```rust , ignore
+let mut db = ReconnectingClient::connect(config, NoTls)?;
+let rows = db.get_top_sales("London", 2)?;
+```
+*/
+pub struct ReconnectingClient {
+    connect: Box<Connect>,
+    client: ::postgres::Client,
+    terminated: bool,
+}
+
+impl ReconnectingClient {
+    /// Connects to the database and returns the client that will reconnect using the same configuration.
+    pub fn connect<T>(config: ::postgres::Config, tls: T) -> Result<Self, ::postgres::Error>
+    where
+        T: ::postgres::tls::MakeTlsConnect<::postgres::Socket> + Clone + Send + Sync + 'static,
+        T::TlsConnect: Send,
+        T::Stream: Send,
+        <T::TlsConnect as ::postgres::tls::TlsConnect<::postgres::Socket>>::Future: Send,
+    {
+        let connect = move || config.connect(tls.clone());
+        let client = connect()?;
+        Ok(Self { connect: Box::new(connect), client, terminated: false })
+    }
+
+    /// Returns the connected client, reconnecting if the connection was closed.
+    pub fn connected(&mut self) -> Result<&mut ::postgres::Client, ::postgres::Error> {
+        if self.terminated || self.client.is_closed() {
+            self.reconnect()
+        } else {
+            Ok(&mut self.client)
+        }
+    }
+
+    /// Replaces the client with a newly connected one.
+    pub fn reconnect(&mut self) -> Result<&mut ::postgres::Client, ::postgres::Error> {
+        self.client = (self.connect)()?;
+        self.terminated = false;
+        Ok(&mut self.client)
+    }
+
+    /// Records that the server terminated the session, which the client might not have noticed yet.
+    #[doc(hidden)]
+    pub fn session_terminated(&mut self) {
+        self.terminated = true;
+    }
+
+    /// Starts a transaction, reconnecting first if the connection was closed.
+    ///
+    /// Statements executed within the transaction are not retried.
+    pub fn transaction(&mut self) -> Result<::postgres::Transaction<'_>, ::postgres::Error> {
+        self.connected()?.transaction()
+    }
+}
+
+/**
+A session-level advisory lock that is released when the guard is dropped.
+
+The guard dereferences to the client that holds the lock, thus generated methods can be called
+through it while the lock is held.
+
+```rust , ignore
+let mut lock = AdvisoryLock::lock(&mut db, JOB_SCHEDULER_LOCK)?;
+let jobs = lock.get_due_jobs()?;
+lock.unlock()?;
+```
+*/
+pub struct AdvisoryLock<'a> {
+    client: &'a mut ::postgres::Client,
+    key: i64,
+    locked: bool,
+}
+
+impl<'a> AdvisoryLock<'a> {
+    /// Acquires the session-level advisory lock, waiting if necessary.
+    pub fn lock(client: &'a mut ::postgres::Client, key: i64) -> Result<Self, ::postgres::Error> {
+        client.execute("SELECT pg_advisory_lock($1)", &[&key])?;
+        Ok(Self { client, key, locked: true })
+    }
+
+    /// Acquires the session-level advisory lock if it is available. Returns `None` if the lock is held by another session.
+    pub fn try_lock(client: &'a mut ::postgres::Client, key: i64) -> Result<Option<Self>, ::postgres::Error> {
+        let locked : bool = client.query_one("SELECT pg_try_advisory_lock($1)", &[&key])?.try_get(0)?;
+        Ok(if locked { Some(Self { client, key, locked }) } else { None })
+    }
+
+    /// Returns the lock key.
+    pub fn key(&self) -> i64 {
+        self.key
+    }
+
+    /// Releases the lock.
+    pub fn unlock(mut self) -> Result<(), ::postgres::Error> {
+        self.locked = false;
+        self.client.execute("SELECT pg_advisory_unlock($1)", &[&self.key]).map(|_| ())
+    }
+}
+
+impl ::std::ops::Deref for AdvisoryLock<'_> {
+    type Target = ::postgres::Client;
+
+    fn deref(&self) -> &Self::Target {
+        self.client
+    }
+}
+
+impl ::std::ops::DerefMut for AdvisoryLock<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.client
+    }
+}
+
+impl Drop for AdvisoryLock<'_> {
+    fn drop(&mut self) {
+        if self.locked {
+            let _ = self.client.execute("SELECT pg_advisory_unlock($1)", &[&self.key]);
+        }
+    }
+}
+
+/**
+Executes the closure within a transaction and commits the transaction if the closure succeeds.
+
+The transaction is started with the isolation level of the `retry` policy. If the closure or the commit
+fail with a serialization failure, a deadlock, or an optimistic lock conflict, the transaction is rolled
+back and the closure is retried as the policy allows.
+
+```rust , ignore
+let retry = Retry::new(IsolationLevel::Serializable);
+let book_id = with_transaction(&mut db, &retry, |tx| {
+    let book = tx.add_new_book(isbn, title)?;
+    tx.loan_books(&[title], user_id)?;
+    book.try_get("book_id")
+})?;
+```
+*/
+pub fn with_transaction<T, E, F>(client: &mut ::postgres::Client, retry: &crate::Retry, mut body: F) -> Result<T, E>
+where
+    F: FnMut(&mut ::postgres::Transaction<'_>) -> Result<T, E>,
+    E: From<::postgres::Error> + 'static,
+{
+    let mut attempt = 1;
+    loop {
+        let mut tx = client.build_transaction().isolation_level(retry.isolation).start()?;
+        let res = match body(&mut tx) {
+            Ok(data) => tx.commit().map(|_| data).map_err(E::from),
+            Err(err) => {
+                let _ = tx.rollback();
+                Err(err)
+            }
+        };
+        match res {
+            Err(err) => match retry.next_delay(&err, attempt) {
+                Some(delay) => ::std::thread::sleep(delay),
+                None => return Err(err),
+            },
+            ok => return ok,
+        }
+        attempt += 1;
+    }
+}
+
+/**
+Executes the closure within a transaction of a new session with the test database and rolls the transaction
+back regardless of the outcome.
+
+The session is opened using the specified configuration and TLS connector. Each call connects separately,
+thus tests that use it stay isolated from each other and can run in parallel.
+
+```rust , ignore
+#[test]
+fn loan_books() -> Result<(), Box<dyn std::error::Error>> {
+    let config : postgres::Config = std::env::var("DATABASE_URL")?.parse()?;
+    test_transaction(&config, NoTls, |tx| {
+        tx.loan_books(&["War and Peace"], "Sheldon Cooper")?;
+        let mut loaned = Vec::new();
+        tx.get_loaned_books("Sheldon Cooper", |row| { loaned.push(row.try_get("book_title")?); Ok(()) })?;
+        assert_eq!(loaned, ["War and Peace"]);
+        Ok(())
+    })
+}
+```
+*/
+pub fn test_transaction<T, E, F, M>(config: &::postgres::Config, tls: M, body: F) -> Result<T, E>
+where
+    F: FnOnce(&mut ::postgres::Transaction<'_>) -> Result<T, E>,
+    E: From<::postgres::Error>,
+    M: ::postgres::tls::MakeTlsConnect<::postgres::Socket> + Send + 'static,
+    M::TlsConnect: Send,
+    M::Stream: Send,
+    <M::TlsConnect as ::postgres::tls::TlsConnect<::postgres::Socket>>::Future: Send,
+{
+    let mut client = config.connect(tls)?;
+    let mut tx = client.transaction()?;
+    let res = body(&mut tx);
+    let rolled_back = tx.rollback();
+    let data = res?;
+    rolled_back?;
+    Ok(data)
+}
+
+/**
+Executes the closure within a transaction and prepares the transaction for two-phase commit under the
+specified global transaction identifier.
+
+The transaction is rolled back if the closure fails. Once prepared, the transaction is no longer associated
+with the session and has to be finished by `commit_prepared` or `rollback_prepared`, which can be called
+from any session.
+
+```rust , ignore
+prepare_transaction(&mut orders_db, "order-42", |tx| tx.add_order(42, user_id))?;
+prepare_transaction(&mut billing_db, "order-42", |tx| tx.add_invoice(42, amount))?;
+commit_prepared(&mut orders_db, "order-42")?;
+commit_prepared(&mut billing_db, "order-42")?;
+```
+*/
+pub fn prepare_transaction<T, E, F>(client: &mut ::postgres::Client, gid: &str, body: F) -> Result<T, E>
+where
+    F: FnOnce(&mut ::postgres::Transaction<'_>) -> Result<T, E>,
+    E: From<::postgres::Error>,
+{
+    let mut tx = client.transaction()?;
+    let data = body(&mut tx)?;
+    tx.batch_execute(&crate::two_phase::command("PREPARE TRANSACTION", gid))?;
+    // `PREPARE TRANSACTION` has ended the transaction in this session, `COMMIT` only marks `tx` as done
+    tx.commit()?;
+    Ok(data)
+}
+
+/// Commits the transaction that was prepared for two-phase commit.
+pub fn commit_prepared(client: &mut ::postgres::Client, gid: &str) -> Result<(), ::postgres::Error> {
+    client.batch_execute(&crate::two_phase::command("COMMIT PREPARED", gid))
+}
+
+/// Rolls back the transaction that was prepared for two-phase commit.
+pub fn rollback_prepared(client: &mut ::postgres::Client, gid: &str) -> Result<(), ::postgres::Error> {
+    client.batch_execute(&crate::two_phase::command("ROLLBACK PREPARED", gid))
+}
+
+/// Returns transactions of the current database that are prepared for two-phase commit, but that are not
+/// committed or rolled back yet. Used to finish transactions that were left in-doubt after a failure.
+pub fn prepared_transactions(client: &mut ::postgres::Client) -> Result<Vec<crate::PreparedTransaction>, ::postgres::Error> {
+    client.query(crate::two_phase::SELECT_PREPARED, &[])?
+        .into_iter()
+        .map(<crate::PreparedTransaction as ::std::convert::TryFrom<_>>::try_from)
+        .collect()
+}
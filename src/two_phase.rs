@@ -0,0 +1,34 @@
+use std::{convert::TryFrom, time::SystemTime};
+
+/// A transaction prepared for two-phase commit as listed by `pg_prepared_xacts`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreparedTransaction {
+    /// Global transaction identifier that was assigned to the transaction.
+    pub gid: String,
+    /// Time at which the transaction was prepared for commit.
+    pub prepared: SystemTime,
+    /// Name of the user that executed the transaction.
+    pub owner: String,
+    /// Name of the database in which the transaction was executed.
+    pub database: String,
+}
+
+impl TryFrom<::tokio_postgres::Row> for PreparedTransaction {
+    type Error = ::tokio_postgres::Error;
+
+    fn try_from(row: ::tokio_postgres::Row) -> Result<Self, Self::Error> {
+        Ok(Self {
+            gid: row.try_get("gid")?,
+            prepared: row.try_get("prepared")?,
+            owner: row.try_get("owner")?,
+            database: row.try_get("database")?,
+        })
+    }
+}
+
+pub(crate) const SELECT_PREPARED: &str = "SELECT gid, prepared, owner, database FROM pg_prepared_xacts WHERE database = current_database() ORDER BY prepared";
+
+/// Returns the two-phase commit command - `PREPARE TRANSACTION`, `COMMIT PREPARED`, or `ROLLBACK PREPARED` - for the transaction.
+pub(crate) fn command(cmd: &str, gid: &str) -> String {
+    format!("{} '{}'", cmd, gid.replace('\'', "''"))
+}
@@ -1,5 +1,1487 @@
 pub use ::include_sql::index_of;
 
+#[cfg(feature = "tracing")]
+pub use ::tracing;
+
+#[cfg(any(feature = "mock", feature = "replay"))]
+pub use ::paste;
+
+/**
+Returns values of the `key:` annotations of a statement.
+
+include-sql passes all statement comment lines, except `name:` and `param:`, to `impl_sql` as the statement doc comment.
+Annotations are the lines of that doc comment that start with a `key:` prefix, for example:
+
+```sql
+-- name: get_sales_report?
+-- set: work_mem = '256MB'
+-- set: statement_timeout = '5min'
+SELECT ...
+```
+*/
+pub fn annotations<'a>(doc: &'a str, key: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+    doc.lines().filter_map(move |line| {
+        line.trim_start().strip_prefix(key)?.strip_prefix(':').map(str::trim)
+    })
+}
+
+/**
+Returns the position in the statement doc where the default value of the parameter starts, if its `param:` declaration
+sets one, like `-- param: page_size: i64 = 50`.
+
+include-sql does not recognize defaults. It adds everything that follows the parameter type to the statement doc comment
+as the parameter description, i.e. the declaration above becomes `` * `page_size` = 50``.
+*/
+const fn default_start(doc: &[u8], name: &[u8]) -> Option<usize> {
+    let mut i = 0;
+    while i < doc.len() {
+        // at the start of a line
+        if i + 3 < doc.len() && doc[i] == b' ' && doc[i + 1] == b'*' && doc[i + 2] == b' ' && doc[i + 3] == b'`' {
+            i += 4;
+            let mut j = 0;
+            while j < name.len() && i + j < doc.len() && doc[i + j] == name[j] {
+                j += 1;
+            }
+            if j == name.len() && i + j < doc.len() && doc[i + j] == b'`' {
+                i += j + 1;
+                while i < doc.len() && doc[i] == b' ' {
+                    i += 1;
+                }
+                if i < doc.len() && doc[i] == b'=' {
+                    return Some(i + 1);
+                }
+            }
+        }
+        while i < doc.len() && doc[i] != b'\n' {
+            i += 1;
+        }
+        i += 1;
+    }
+    None
+}
+
+/**
+Tells whether the `param:` declaration of the parameter sets a default value.
+
+Only scalar parameters of declared owned types can have defaults:
+
+```compile_fail
+use include_postgres_sql::{impl_sql, include_sql};
+
+include_sql!("tests/sql/query_default_ref.sql");
+```
+*/
+pub const fn has_default(doc: &str, name: &str) -> bool {
+    default_start(doc.as_bytes(), name.as_bytes()).is_some()
+}
+
+/**
+Returns the default value of the parameter as it is written in the `param:` declaration.
+
+The value ends at the end of the line or at the ` - ` that starts the parameter description. Quotes around the value are
+removed, thus both `-- param: status: String = 'open'` and `-- param: status: String = open` default to `open`.
+*/
+pub fn param_default(doc: &'static str, name: &str) -> &'static str {
+    let start = default_start(doc.as_bytes(), name.as_bytes()).unwrap_or(doc.len());
+    let value = &doc[start..];
+    let value = value.split('\n').next().unwrap_or_default();
+    let value = value.split(" - ").next().unwrap_or_default().trim();
+    if value.len() >= 2 && (value.starts_with('\'') && value.ends_with('\'') || value.starts_with('"') && value.ends_with('"')) {
+        &value[1..value.len() - 1]
+    } else {
+        value
+    }
+}
+
+/**
+Selects the type of a method parameter depending on whether its `param:` declaration sets a default value.
+
+A parameter with a default value is passed as `Option` of the declared type and `None` binds the default.
+*/
+pub struct Arg<const DEFAULT: bool>;
+
+/// Type of the method parameter that is declared as `T`.
+pub trait ArgType<T> {
+    type Type;
+}
+
+impl<T> ArgType<T> for Arg<false> {
+    type Type = T;
+}
+
+impl<T> ArgType<T> for Arg<true> {
+    type Type = Option<T>;
+}
+
+impl Arg<false> {
+    /// Returns the argument as is as the parameter does not have a default value.
+    pub fn bind<T>(arg: T, _doc: &'static str, _name: &str) -> T {
+        arg
+    }
+}
+
+impl Arg<true> {
+    /// Returns the argument that binds the default value of the parameter when it is `None`.
+    pub fn bind<T>(arg: Option<T>, doc: &'static str, name: &str) -> WithDefault<T> {
+        WithDefault { arg, default: param_default(doc, name) }
+    }
+}
+
+/**
+Argument of a parameter that has a default value.
+
+The default is parsed into the declared type of the parameter when the argument is bound. A default that cannot be parsed
+fails the call with the error that the driver reports for parameters that cannot be serialized.
+*/
+pub struct WithDefault<T> {
+    arg: Option<T>,
+    default: &'static str,
+}
+
+impl<T: ::std::fmt::Debug> ::std::fmt::Debug for WithDefault<T> {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        match &self.arg {
+            Some(arg) => arg.fmt(f),
+            None => f.write_str(self.default),
+        }
+    }
+}
+
+impl<T> ::tokio_postgres::types::ToSql for WithDefault<T>
+where
+    T: ::tokio_postgres::types::ToSql + ::std::str::FromStr,
+    T::Err: ::std::error::Error + Sync + Send + 'static,
+{
+    fn to_sql(
+        &self, ty: &::tokio_postgres::types::Type, out: &mut ::bytes::BytesMut,
+    ) -> Result<::tokio_postgres::types::IsNull, Box<dyn ::std::error::Error + Sync + Send>> {
+        match &self.arg {
+            Some(arg) => arg.to_sql(ty, out),
+            None => self.default.parse::<T>()?.to_sql(ty, out),
+        }
+    }
+
+    fn accepts(ty: &::tokio_postgres::types::Type) -> bool {
+        T::accepts(ty)
+    }
+
+    ::tokio_postgres::types::to_sql_checked!();
+}
+
+/// Fails the compilation when `impl_sql` is given an option that it does not recognize.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! check_options {
+    () => {};
+    ( router $($rest:ident)* ) => {
+        $crate::check_options!{ $($rest)* }
+    };
+    ( reconnect $($rest:ident)* ) => {
+        $crate::check_options!{ $($rest)* }
+    };
+    ( statement_error $($rest:ident)* ) => {
+        $crate::check_options!{ $($rest)* }
+    };
+    ( app_errors $($rest:ident)* ) => {
+        $crate::check_options!{ $($rest)* }
+    };
+    ( interceptor $($rest:ident)* ) => {
+        $crate::check_options!{ $($rest)* }
+    };
+    ( fixtures $($rest:ident)* ) => {
+        $crate::check_options!{ $($rest)* }
+    };
+    ( $option:ident $($rest:ident)* ) => {
+        ::std::compile_error!(::std::concat!("unknown `impl_sql` option `", ::std::stringify!($option), "`"));
+        $crate::check_options!{ $($rest)* }
+    };
+}
+
+/// Expands the first block if the option is in the list of the options that were given to `impl_sql`
+/// and the `else` block - if there is one - otherwise.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! with_option {
+    ( router [router $($rest:ident)*] { $($yes:tt)* } $(else $no:tt)? ) => {
+        $($yes)*
+    };
+    ( reconnect [reconnect $($rest:ident)*] { $($yes:tt)* } $(else $no:tt)? ) => {
+        $($yes)*
+    };
+    ( statement_error [statement_error $($rest:ident)*] { $($yes:tt)* } $(else $no:tt)? ) => {
+        $($yes)*
+    };
+    ( app_errors [app_errors $($rest:ident)*] { $($yes:tt)* } $(else $no:tt)? ) => {
+        $($yes)*
+    };
+    ( interceptor [interceptor $($rest:ident)*] { $($yes:tt)* } $(else $no:tt)? ) => {
+        $($yes)*
+    };
+    ( fixtures [fixtures $($rest:ident)*] { $($yes:tt)* } $(else $no:tt)? ) => {
+        $($yes)*
+    };
+    ( $option:ident [$other:ident $($rest:ident)*] $yes:tt $(else $no:tt)? ) => {
+        $crate::with_option!{ $option [$($rest)*] $yes $(else $no)? }
+    };
+    ( $option:ident [] $yes:tt $(else { $($no:tt)* })? ) => {
+        $($($no)*)?
+    };
+}
+
+/// Fails the compilation when the statement uses features that `impl_sql` does not support.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! check_statement {
+    ( @checked (yes) $name:ident $doc:literal ) => {};
+    ( @checked (no) $name:ident $doc:literal ) => {
+        const _: () = if $crate::util::has_key($doc, "try_advisory_lock") {
+            ::std::panic!(::std::concat!(
+                "`", ::std::stringify!($name), "` declares `try_advisory_lock:`, which fails the call with `CheckFailure::Busy` - ",
+                "give `impl_sql` the `statement_error` option"
+            ))
+        };
+        const _: () = if $crate::util::checks_row_count($doc) {
+            ::std::panic!(::std::concat!(
+                "`", ::std::stringify!($name), "` declares `expect_rows:` or `optimistic lock`, which fail the call with `CheckFailure::RowCount` ",
+                "or `CheckFailure::Conflict` - give `impl_sql` the `statement_error` option"
+            ))
+        };
+        const _: () = if $crate::util::has_key($doc, "on_constraint") {
+            ::std::panic!(::std::concat!(
+                "`", ::std::stringify!($name), "` declares `on_constraint:`, which sets the `violation` of `StatementError` - ",
+                "give `impl_sql` the `statement_error` option"
+            ))
+        };
+    };
+    ( @fixture ! $name:ident () $doc:literal ) => {};
+    ( @fixture $kind:tt $name:ident ($($param:ident)*) $doc:literal ) => {
+        const _: () = if $crate::util::has_key($doc, "fixture") {
+            ::std::panic!(::std::concat!(
+                "`", ::std::stringify!($name), "` declares `fixture:`, which only `!` statements without parameters can"
+            ))
+        };
+    };
+    ( @no_fixtures $name:ident $doc:literal ) => {
+        const _: () = if $crate::util::has_key($doc, "fixture") {
+            ::std::panic!(::std::concat!(
+                "`", ::std::stringify!($name), "` declares `fixture:`, which is applied by `apply_fixtures` - ",
+                "give `impl_sql` the `fixtures` option"
+            ))
+        };
+    };
+    ( @defaults $name:ident $doc:literal () ) => {};
+    ( @defaults $name:ident $doc:literal (: $param:ident ($plt:lifetime & $ptype:ty) $($tail:tt)*) ) => {
+        $crate::check_statement!{ @no_default $name $param $doc }
+        $crate::check_statement!{ @defaults $name $doc ($($tail)*) }
+    };
+    ( @defaults $name:ident $doc:literal (: $param:ident (& $ptype:ty) $($tail:tt)*) ) => {
+        $crate::check_statement!{ @no_default $name $param $doc }
+        $crate::check_statement!{ @defaults $name $doc ($($tail)*) }
+    };
+    ( @defaults $name:ident $doc:literal (: $param:ident ($ptype:ty) $($tail:tt)*) ) => {
+        $crate::check_statement!{ @defaults $name $doc ($($tail)*) }
+    };
+    ( @defaults $name:ident $doc:literal ($variant:tt $param:ident $ptype:tt $($tail:tt)*) ) => {
+        $crate::check_statement!{ @no_default $name $param $doc }
+        $crate::check_statement!{ @defaults $name $doc ($($tail)*) }
+    };
+    ( @no_default $name:ident $param:ident $doc:literal ) => {
+        const _: () = if $crate::util::has_default($doc, ::std::stringify!($param)) {
+            ::std::panic!(::std::concat!(
+                "`", ::std::stringify!($name), "` declares a default value for `", ::std::stringify!($param), "`, ",
+                "which only scalar parameters of declared owned types can have"
+            ))
+        };
+    };
+    ( @row_count ! $name:ident $doc:literal ) => {};
+    ( @row_count $kind:tt $name:ident $doc:literal ) => {
+        const _: () = if $crate::util::checks_row_count($doc) {
+            ::std::panic!(::std::concat!(
+                "`", ::std::stringify!($name), "` declares `expect_rows:` or `optimistic lock`, which only `!` statements can"
+            ))
+        };
+    };
+    ( ($statement_error:ident) $kind:tt $name:ident $doc:literal ) => {
+        const _: () = if $crate::util::references_parameter($doc, "advisory_lock") || $crate::util::references_parameter($doc, "try_advisory_lock") {
+            ::std::panic!(::std::concat!(
+                "`", ::std::stringify!($name), "` advisory lock key references a statement parameter, which lock keys cannot do - ",
+                "the key is evaluated before the statement is executed"
+            ))
+        };
+        const _: () = if !$crate::util::valid_expected_rows($doc) {
+            ::std::panic!(::std::concat!(
+                "`", ::std::stringify!($name), "` declares a malformed `expect_rows:` - expected a single `N`, `N..M`, `N..=M`, `N..`, `..M`, or `..=M` ",
+                "that allows at least one number of rows"
+            ))
+        };
+        const _: () = if !$crate::util::valid_constraint_mappings($doc) {
+            ::std::panic!(::std::concat!(
+                "`", ::std::stringify!($name), "` declares a malformed `on_constraint:` - expected `constraint_name => ViolationName`"
+            ))
+        };
+        $crate::check_statement!{ @row_count $kind $name $doc }
+        $crate::check_statement!{ @checked ($statement_error) $name $doc }
+    };
+}
+
+/// Returns the range of the value of the first `key:` annotation that is found at or after `from`
+/// and where the next line starts.
+///
+/// This is a `const` counterpart of `annotations` for the checks that are done at compile time.
+const fn find_annotation(doc: &[u8], key: &[u8], from: usize) -> Option<(usize, usize, usize)> {
+    const fn is_space(b: u8) -> bool {
+        b == b' ' || b == b'\t' || b == b'\r'
+    }
+    let mut i = from;
+    while i < doc.len() {
+        let mut end = i;
+        while end < doc.len() && doc[end] != b'\n' {
+            end += 1;
+        }
+        while i < end && is_space(doc[i]) {
+            i += 1;
+        }
+        if i + key.len() < end && is_prefix(doc, i, key) && doc[i + key.len()] == b':' {
+            let mut start = i + key.len() + 1;
+            while start < end && is_space(doc[start]) {
+                start += 1;
+            }
+            let mut stop = end;
+            while stop > start && is_space(doc[stop - 1]) {
+                stop -= 1;
+            }
+            return Some((start, stop, end + 1));
+        }
+        i = end + 1;
+    }
+    None
+}
+
+/// Tells whether a value of the `key:` annotations references a statement parameter, like `:job_id`.
+/// Type casts, like `::bigint`, are not references.
+pub const fn references_parameter(doc: &str, key: &str) -> bool {
+    let doc = doc.as_bytes();
+    let mut from = 0;
+    while let Some((start, stop, next)) = find_annotation(doc, key.as_bytes(), from) {
+        let mut i = start;
+        while i + 1 < stop {
+            let b = doc[i + 1];
+            if doc[i] == b':' && (i == start || doc[i - 1] != b':') && (b.is_ascii_alphabetic() || b == b'_') {
+                return true;
+            }
+            i += 1;
+        }
+        from = next;
+    }
+    false
+}
+
+/// Tells whether every `on_constraint:` annotation of the statement maps a constraint name to a violation
+/// name that is an identifier, like `library_isbn_key => DuplicateIsbn`.
+pub const fn valid_constraint_mappings(doc: &str) -> bool {
+    const fn is_space(b: u8) -> bool {
+        b == b' ' || b == b'\t'
+    }
+    let doc = doc.as_bytes();
+    let mut from = 0;
+    while let Some((start, stop, next)) = find_annotation(doc, b"on_constraint", from) {
+        let mut arrow = start;
+        while arrow + 1 < stop && !(doc[arrow] == b'=' && doc[arrow + 1] == b'>') {
+            arrow += 1;
+        }
+        if arrow + 1 >= stop {
+            return false;
+        }
+        let mut i = start;
+        while i < arrow && !is_space(doc[i]) {
+            i += 1;
+        }
+        if i == start {
+            return false;
+        }
+        while i < arrow && is_space(doc[i]) {
+            i += 1;
+        }
+        if i < arrow {
+            return false;
+        }
+        let mut i = arrow + 2;
+        while i < stop && is_space(doc[i]) {
+            i += 1;
+        }
+        if i == stop || !(doc[i].is_ascii_alphabetic() || doc[i] == b'_') {
+            return false;
+        }
+        while i < stop {
+            if !(doc[i].is_ascii_alphanumeric() || doc[i] == b'_') {
+                return false;
+            }
+            i += 1;
+        }
+        from = next;
+    }
+    true
+}
+
+/// Tells whether `text` is found in `doc` at `at`.
+const fn is_prefix(doc: &[u8], at: usize, text: &[u8]) -> bool {
+    if at + text.len() > doc.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < text.len() {
+        if doc[at + i] != text[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Tells whether the statement has the `key: value` annotation.
+pub const fn has_annotation(doc: &str, key: &str, value: &str) -> bool {
+    let (doc, key, value) = (doc.as_bytes(), key.as_bytes(), value.as_bytes());
+    let mut from = 0;
+    while let Some((start, stop, next)) = find_annotation(doc, key, from) {
+        if stop - start == value.len() && is_prefix(doc, start, value) {
+            return true;
+        }
+        from = next;
+    }
+    false
+}
+
+/// Tells whether the statement has any `key:` annotations.
+pub const fn has_key(doc: &str, key: &str) -> bool {
+    find_annotation(doc.as_bytes(), key.as_bytes(), 0).is_some()
+}
+
+/// Tells whether the statement is annotated with `requires: transaction`.
+pub const fn requires_transaction(doc: &str) -> bool {
+    has_annotation(doc, "requires", "transaction")
+}
+
+/// Tells whether the statement has a line - other than an annotation - that matches `text` ignoring the case.
+const fn has_line(doc: &str, text: &str) -> bool {
+    const fn is_space(b: u8) -> bool {
+        b == b' ' || b == b'\t' || b == b'\r'
+    }
+    let (doc, text) = (doc.as_bytes(), text.as_bytes());
+    let mut i = 0;
+    while i < doc.len() {
+        let mut end = i;
+        while end < doc.len() && doc[end] != b'\n' {
+            end += 1;
+        }
+        let mut start = i;
+        while start < end && is_space(doc[start]) {
+            start += 1;
+        }
+        let mut stop = end;
+        while stop > start && is_space(doc[stop - 1]) {
+            stop -= 1;
+        }
+        if stop - start == text.len() {
+            let mut j = 0;
+            while j < text.len() && doc[start + j].eq_ignore_ascii_case(&text[j]) {
+                j += 1;
+            }
+            if j == text.len() {
+                return true;
+            }
+        }
+        i = end + 1;
+    }
+    false
+}
+
+/// Tells whether the statement declares session settings, transaction characteristics, or advisory locks,
+/// which have to be applied before it is executed.
+pub const fn prepares_session(doc: &str) -> bool {
+    has_key(doc, "set") || has_key(doc, "isolation") || has_line(doc, "read only")
+        || has_key(doc, "advisory_lock") || has_key(doc, "try_advisory_lock")
+}
+
+/// Tells whether the statement is executed in its own transaction when it is called outside of one.
+pub const fn opens_transaction(doc: &str) -> bool {
+    prepares_session(doc) || checks_row_count(doc)
+}
+
+/// Tells whether the method of the statement can only be called within a transaction.
+///
+/// Streaming (`^`) statements are executed lazily, after the method returns, thus they cannot start
+/// their own transaction.
+///
+/// Async clients are shared by tasks, which pipeline their queries over the same session, thus statements
+/// that would be executed in their own transaction would include queries of other tasks in it. The async
+/// methods of these statements can only be called within a transaction too.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! within_transaction {
+    ( async $kind:tt $doc:literal ) => {
+        $crate::util::requires_transaction($doc) || $crate::util::opens_transaction($doc)
+    };
+    ( ^ $doc:literal ) => {
+        $crate::util::requires_transaction($doc) || $crate::util::prepares_session($doc)
+    };
+    ( $kind:tt $doc:literal ) => {
+        $crate::util::requires_transaction($doc)
+    };
+}
+
+/// Tells whether any of the fixture statements can only be called within a transaction.
+///
+/// `shared` selects the rules of the async methods - see `within_transaction!`.
+pub const fn fixtures_require_transaction(docs: &[&str], shared: bool) -> bool {
+    let mut i = 0;
+    while i < docs.len() {
+        if has_key(docs[i], "fixture") && (requires_transaction(docs[i]) || shared && opens_transaction(docs[i])) {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
+/**
+Restricts the sessions on which a generated method can be called.
+
+Methods of statements annotated with `requires: transaction`, as well as methods of `^` statements that
+declare session settings, transaction characteristics, or advisory locks, are bound by `Self: Within<true>`,
+which only transactions implement, thus calling them on a client fails to compile. Async methods of all
+statements that would be executed in their own transaction - statements with session settings, transaction
+characteristics, advisory locks, or checked row counts - are bound by it as well. All other methods are
+bound by `Self: Within<false>`, which every type implements.
+
+```compile_fail
+use include_postgres_sql::{impl_sql, include_sql};
+
+include_sql!("tests/sql/query_for_update.sql");
+
+fn lock_genre(db: &mut postgres::Client) -> Result<postgres::Row, postgres::Error> {
+    db.lock_genre(1)
+}
+```
+
+```compile_fail
+use include_postgres_sql::{impl_sql, include_sql};
+
+include_sql!("tests/sql/query_settings.sql");
+
+fn stream_work_mem(db: &mut postgres::Client) -> Result<include_postgres_sql::RowIter<'_>, postgres::Error> {
+    db.stream_work_mem()
+}
+```
+*/
+pub trait Within<const TRANSACTION: bool> {}
+
+impl<T: ?Sized> Within<false> for T {}
+
+/**
+Returns the names of the statements that apply the fixtures and the fixtures they depend on, in the order
+in which they have to be executed.
+
+Statements are marked as parts of a fixture by the `fixture:` annotation. The `depends_on:` annotations of
+those statements list the fixtures that have to be applied before it. Each fixture is applied once, after
+the fixtures it depends on. Statements of a fixture are executed in the order they appear in the SQL file.
+
+Panics if a fixture is unknown or if fixtures depend on each other.
+
+`apply_fixtures` is generated only when `impl_sql` is given the `fixtures` option, thus fixture statements
+do not compile without it:
+
+```compile_fail
+use include_postgres_sql::{impl_sql, include_sql};
+
+include_sql!("tests/sql/dml_fixtures.sql");
+```
+
+Only `!` statements without parameters can be parts of fixtures:
+
+```compile_fail
+use include_postgres_sql::include_sql;
+
+macro_rules! impl_sql {
+    ( $($tt:tt)+ ) => {
+        include_postgres_sql::impl_sql!{ #[options(fixtures)] $($tt)+ }
+    };
+}
+
+include_sql!("tests/sql/query_fixture.sql");
+```
+*/
+pub fn fixture_order(sql_trait: &str, statements: &[(&'static str, &'static str)], fixtures: &[&str]) -> Vec<&'static str> {
+    fn visit(
+        sql_trait: &str, statements: &[(&'static str, &'static str)], fixture: &str,
+        visiting: &mut Vec<String>, applied: &mut Vec<String>, order: &mut Vec<&'static str>,
+    ) {
+        if applied.iter().any(|name| name == fixture) {
+            return;
+        }
+        if visiting.iter().any(|name| name == fixture) {
+            panic!("{} fixtures depend on each other: {} -> {}", sql_trait, visiting.join(" -> "), fixture);
+        }
+        let parts: Vec<_> = statements.iter().filter(|(_, doc)| annotations(doc, "fixture").any(|name| name == fixture)).collect();
+        if parts.is_empty() {
+            panic!("{} has no fixture {}", sql_trait, fixture);
+        }
+        visiting.push(fixture.to_string());
+        for (_, doc) in parts.iter() {
+            for dependencies in annotations(doc, "depends_on") {
+                for dependency in dependencies.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+                    visit(sql_trait, statements, dependency, visiting, applied, order);
+                }
+            }
+        }
+        visiting.pop();
+        order.extend(parts.iter().map(|(name, _)| *name));
+        applied.push(fixture.to_string());
+    }
+
+    let mut applied = Vec::new();
+    let mut order = Vec::new();
+    for fixture in fixtures {
+        visit(sql_trait, statements, fixture, &mut Vec::new(), &mut applied, &mut order);
+    }
+    order
+}
+
+/// Returns the `SET LOCAL` commands for the `set:` annotations of a statement or `None` if there are none.
+pub fn set_commands(doc: &str) -> Option<String> {
+    let mut cmds = String::new();
+    for setting in annotations(doc, "set") {
+        cmds.push_str("SET LOCAL ");
+        cmds.push_str(setting);
+        cmds.push(';');
+    }
+    if cmds.is_empty() { None } else { Some(cmds) }
+}
+
+/// Returns the isolation level declared by the `isolation:` annotation of a statement.
+pub fn isolation(doc: &str) -> Option<&str> {
+    annotations(doc, "isolation").last()
+}
+
+/// Tells whether the statement is annotated with `read only`.
+pub fn read_only(doc: &str) -> bool {
+    doc.lines().any(|line| line.trim().eq_ignore_ascii_case("read only"))
+}
+
+/// Returns the command that starts a transaction with the characteristics declared by the
+/// `isolation:` and `read only` annotations of a statement or `None` if there are none.
+pub fn begin_command(doc: &str) -> Option<String> {
+    match (isolation(doc), read_only(doc)) {
+        (None, false) => None,
+        (None, true) => Some("BEGIN READ ONLY;".to_string()),
+        (Some(level), false) => Some(format!("BEGIN ISOLATION LEVEL {};", level)),
+        (Some(level), true) => Some(format!("BEGIN ISOLATION LEVEL {}, READ ONLY;", level)),
+    }
+}
+
+/// Returns the command that fails with `invalid_transaction_state` error if the current transaction
+/// is weaker than what the `isolation:` and `read only` annotations of the named statement require,
+/// or `None` if the statement has no such annotations.
+pub fn check_transaction_command(name: &str, doc: &str) -> Option<String> {
+    const LEVELS: &str = "ARRAY['read uncommitted','read committed','repeatable read','serializable']";
+    let mut conds = Vec::new();
+    if let Some(level) = isolation(doc) {
+        conds.push(format!(
+            "array_position({levels}, current_setting('transaction_isolation')) < array_position({levels}, lower('{level}'))",
+            levels = LEVELS, level = level.replace('\'', "''")
+        ));
+    }
+    if read_only(doc) {
+        conds.push("current_setting('transaction_read_only') = 'off'".to_string());
+    }
+    if conds.is_empty() {
+        return None;
+    }
+    let mode = begin_command(doc)?.replace("BEGIN ", "").replace('\'', "''");
+    Some(format!(
+        "DO $$BEGIN IF {} THEN RAISE EXCEPTION '{} requires {}' USING ERRCODE = 'invalid_transaction_state'; END IF; END$$;",
+        conds.join(" OR "), name, mode.trim_end_matches(';')
+    ))
+}
+
+/// Returns the commands that acquire transaction-level advisory locks declared by the `advisory_lock:`
+/// annotations of a statement or `None` if there are none.
+///
+/// The annotation value is an SQL expression - or two expressions separated by a comma - that evaluates
+/// to the lock key. The key is a part of the command, thus it cannot reference statement parameters.
+pub fn advisory_lock_commands(doc: &str) -> Option<String> {
+    let mut cmds = String::new();
+    for key in annotations(doc, "advisory_lock") {
+        cmds.push_str(&format!("SELECT pg_advisory_xact_lock({});", key));
+    }
+    if cmds.is_empty() { None } else { Some(cmds) }
+}
+
+/// Returns the query that tries to acquire transaction-level advisory locks declared by the `try_advisory_lock:`
+/// annotations of a statement or `None` if there are none. The query returns whether each lock was acquired.
+///
+/// Like `advisory_lock:` keys, the keys are SQL expressions that cannot reference statement parameters.
+pub fn try_advisory_lock_query(doc: &str) -> Option<String> {
+    let locks: Vec<_> = annotations(doc, "try_advisory_lock").map(|key| format!("pg_try_advisory_xact_lock({})", key)).collect();
+    if locks.is_empty() { None } else { Some(format!("SELECT {}", locks.join(", "))) }
+}
+
+/// Parses the number of rows in `value[start..stop]`. Returns `Some(None)` if there is no number and `None`
+/// if it is not a valid number.
+const fn parse_num_rows(value: &[u8], mut start: usize, mut stop: usize) -> Option<Option<u64>> {
+    while start < stop && value[start] == b' ' {
+        start += 1;
+    }
+    while stop > start && value[stop - 1] == b' ' {
+        stop -= 1;
+    }
+    if start == stop {
+        return Some(None);
+    }
+    let mut num: u64 = 0;
+    while start < stop {
+        let digit = value[start];
+        if !digit.is_ascii_digit() {
+            return None;
+        }
+        num = match num.checked_mul(10) {
+            Some(num) => match num.checked_add((digit - b'0') as u64) {
+                Some(num) => num,
+                None => return None,
+            },
+            None => return None,
+        };
+        start += 1;
+    }
+    Some(Some(num))
+}
+
+/// Parses the value of the `expect_rows:` annotation - `N`, `N..M`, `N..=M`, `N..`, `..M`, or `..=M` - into
+/// the inclusive range of the number of rows. Returns `None` if the value is malformed or the range is empty.
+const fn parse_expected_rows(value: &[u8], start: usize, stop: usize) -> Option<(u64, u64)> {
+    let mut dots = start;
+    while dots + 1 < stop && !(value[dots] == b'.' && value[dots + 1] == b'.') {
+        dots += 1;
+    }
+    let (min, max) = if dots + 1 >= stop {
+        match parse_num_rows(value, start, stop) {
+            Some(Some(num)) => (num, num),
+            _ => return None,
+        }
+    } else {
+        let min = match parse_num_rows(value, start, dots) {
+            Some(min) => min,
+            None => return None,
+        };
+        let max = if dots + 2 < stop && value[dots + 2] == b'=' {
+            match parse_num_rows(value, dots + 3, stop) {
+                Some(Some(max)) => max,
+                _ => return None,
+            }
+        } else {
+            match parse_num_rows(value, dots + 2, stop) {
+                Some(Some(end)) if end > 0 => end - 1,
+                Some(None) if min.is_some() => u64::MAX,
+                _ => return None,
+            }
+        };
+        match min {
+            Some(min) => (min, max),
+            None => (0, max),
+        }
+    };
+    if min <= max { Some((min, max)) } else { None }
+}
+
+/// Tells whether the statement declares at most one `expect_rows:` annotation and its value is a valid,
+/// non-empty range.
+pub const fn valid_expected_rows(doc: &str) -> bool {
+    let doc = doc.as_bytes();
+    match find_annotation(doc, b"expect_rows", 0) {
+        Some((start, stop, next)) => parse_expected_rows(doc, start, stop).is_some() && find_annotation(doc, b"expect_rows", next).is_none(),
+        None => true,
+    }
+}
+
+/// Returns the range of the number of rows that the statement is expected to affect as declared by the
+/// `expect_rows:` annotation or `None` if there is none.
+///
+/// Malformed annotations do not compile (see `valid_expected_rows`), thus they are never parsed here.
+pub fn expected_rows(doc: &str) -> Option<(u64, u64)> {
+    let range = annotations(doc, "expect_rows").last()?;
+    parse_expected_rows(range.as_bytes(), 0, range.len())
+}
+
+/// Tells whether the statement is annotated with `optimistic lock`.
+pub const fn optimistic_lock(doc: &str) -> bool {
+    has_line(doc, "optimistic lock")
+}
+
+/// Returns the check failure when the number of rows affected by a statement does not match what its
+/// `expect_rows:` and `optimistic lock` annotations require, or `None` if it does.
+///
+/// When an `optimistic lock` statement does not affect any rows the call fails with `CheckFailure::Conflict`.
+/// Otherwise a mismatch fails with `CheckFailure::RowCount`.
+fn row_count_failure(expected_rows: Option<(u64, u64)>, optimistic_lock: bool, num_rows: u64) -> Option<crate::CheckFailure> {
+    if optimistic_lock && num_rows == 0 {
+        return Some(crate::CheckFailure::Conflict);
+    }
+    let (min, max) = expected_rows.or(if optimistic_lock { Some((1, 1)) } else { None })?;
+    if min <= num_rows && num_rows <= max {
+        return None;
+    }
+    Some(crate::CheckFailure::RowCount { expected: min..=max, actual: num_rows })
+}
+
+/// Tells whether the number of rows affected by the statement is checked after it is executed.
+pub const fn checks_row_count(doc: &str) -> bool {
+    optimistic_lock(doc) || has_key(doc, "expect_rows")
+}
+
+/// Tells whether the call failed because the server terminated the session.
+///
+/// The client might not have noticed yet that the connection is closed when the server reported the error.
+pub fn session_terminated(err: &::tokio_postgres::Error) -> bool {
+    err.is_closed() || err.as_db_error().is_some_and(|err| matches!(err.severity(), "FATAL" | "PANIC"))
+}
+
+/// What a generated method has to do after the statement is executed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AfterCall {
+    Nothing,
+    /// Commit or roll back the transaction that was started before the call.
+    EndTransaction,
+}
+
+/**
+Annotations of a statement that its generated methods use when they are called.
+
+Generated methods parse them once, when they are called for the first time, and keep them in a `static`.
+See `annotations!`.
+*/
+pub struct Annotations {
+    doc: &'static str,
+    /// Commands that prepare the session when the statement is called outside of a transaction.
+    begin: String,
+    /// What has to be done after the statement is executed outside of a transaction.
+    after: AfterCall,
+    /// Commands that prepare the transaction when the statement is called within one.
+    within: String,
+    /// Query that tries to acquire the `try_advisory_lock:` locks after the session is prepared.
+    try_lock: Option<String>,
+    try_lock_keys: Vec<&'static str>,
+    expected_rows: Option<(u64, u64)>,
+    optimistic_lock: bool,
+    replica: Option<bool>,
+    idempotent: bool,
+}
+
+impl Annotations {
+    /**
+    Parses annotations of the named statement.
+
+    Commands that prepare the session:
+    - start a transaction, when the statement declares session settings, transaction characteristics, advisory
+      locks, or expected number of affected rows and it is called outside of a transaction,
+    - check that the current transaction has the characteristics declared by the statement,
+    - acquire transaction-level advisory locks declared by the `advisory_lock:` annotations,
+    - apply session settings declared by the `set:` annotations via `SET LOCAL`, thus they end with the
+      transaction and the settings of the session are not changed.
+
+    After these commands are executed the `try_advisory_lock:` locks are tried by a separate query, so
+    the generated method can fail the call with `CheckFailure::Busy` when one of them is not available.
+
+    Streaming (`^`) statements are executed lazily, after the method returns, thus they cannot start their
+    own transaction. Their methods do not compile outside of a
+    transaction if the statement needs any of these commands.
+    */
+    pub fn parse(name: &'static str, doc: &'static str) -> Self {
+        let locks = advisory_lock_commands(doc);
+        let try_lock = try_advisory_lock_query(doc);
+        let mut within = check_transaction_command(name, doc).unwrap_or_default();
+        within.extend(locks.as_deref());
+        within.extend(set_commands(doc));
+        let begin_cmd = begin_command(doc).or_else(|| {
+            if locks.is_some() || try_lock.is_some() || checks_row_count(doc) || has_key(doc, "set") { Some("BEGIN;".to_string()) } else { None }
+        });
+        let (begin, after) = match begin_cmd {
+            Some(mut cmds) => {
+                cmds.extend(locks);
+                cmds.extend(set_commands(doc));
+                (cmds, AfterCall::EndTransaction)
+            }
+            None => (String::new(), AfterCall::Nothing),
+        };
+        let replica = match annotations(doc, "replica").last() {
+            Some("ok") => Some(true),
+            Some("no") => Some(false),
+            _ => None,
+        };
+        Self {
+            doc, begin, after, within, try_lock,
+            try_lock_keys: annotations(doc, "try_advisory_lock").collect(),
+            expected_rows: expected_rows(doc),
+            optimistic_lock: optimistic_lock(doc),
+            replica,
+            idempotent: read_only(doc) || doc.lines().any(|line| line.trim() == "idempotent"),
+        }
+    }
+
+    /// Returns commands that prepare the session for the execution of the statement and what has to be done
+    /// after the statement is executed.
+    pub fn before_call(&self, in_transaction: bool) -> (&str, AfterCall) {
+        if in_transaction { (&self.within, AfterCall::Nothing) } else { (&self.begin, self.after) }
+    }
+
+    /// Returns the query that tries to acquire the `try_advisory_lock:` locks of the statement after the session
+    /// is prepared for the call, or `None` if the statement has no such locks.
+    pub fn try_lock_query(&self) -> Option<&str> {
+        self.try_lock.as_deref()
+    }
+
+    /// Returns the `Busy` check failure for the first lock that the `try_lock_query` did not acquire
+    /// or `None` if it acquired all of them.
+    pub fn busy_lock(&self, row: &::tokio_postgres::Row) -> Result<Option<crate::CheckFailure>, ::tokio_postgres::Error> {
+        for (i, &key) in self.try_lock_keys.iter().enumerate() {
+            if !row.try_get::<_, bool>(i)? {
+                return Ok(Some(crate::CheckFailure::Busy { key }));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the command that has to be executed after the statement succeeded or failed.
+    pub fn after_call(&self, after: AfterCall, succeeded: bool) -> Option<&str> {
+        match after {
+            AfterCall::Nothing => None,
+            AfterCall::EndTransaction => Some(if succeeded { "COMMIT" } else { "ROLLBACK" }),
+        }
+    }
+
+    /// Fails when the number of rows affected by the `!` statement does not match what the `expect_rows:`
+    /// and `optimistic lock` annotations of the statement require.
+    pub fn expect_rows(&self, num_rows: u64) -> Result<u64, crate::CheckFailure> {
+        match row_count_failure(self.expected_rows, self.optimistic_lock, num_rows) {
+            Some(failure) => Err(failure),
+            None => Ok(num_rows),
+        }
+    }
+
+    /// Tells whether the statement is executed on a replica by the `Router`.
+    ///
+    /// Statements that only read data are executed on a replica unless they are annotated with `replica: no`.
+    /// Other statements are executed on the primary unless they are annotated with `replica: ok`.
+    pub fn use_replica(&self, reads: bool) -> bool {
+        self.replica.unwrap_or(reads)
+    }
+
+    /// Tells whether the statement can be safely executed again after the connection was lost during the call.
+    ///
+    /// Statements that only read data, statements annotated with `read only`, and statements annotated
+    /// with `idempotent` can be retried.
+    pub fn idempotent(&self, reads: bool) -> bool {
+        reads || self.idempotent
+    }
+
+    /// Returns the name that the `on_constraint:` annotation of the statement maps the constraint violated
+    /// by the error to, or `None` if the error is not a violation of a mapped constraint.
+    ///
+    /// Mappings are declared as `constraint_name => ViolationName`, for example:
+    ///
+    /// ```sql
+    /// -- name: add_book!
+    /// -- on_constraint: library_isbn_key => DuplicateIsbn
+    /// INSERT INTO library (isbn, book_title) VALUES (:isbn, :book_title)
+    /// ```
+    pub fn constraint_violation(&self, err: &::tokio_postgres::Error) -> Option<&'static str> {
+        let constraint = err.as_db_error()?.constraint()?;
+        annotations(self.doc, "on_constraint").find_map(|mapping| {
+            let (name, violation) = mapping.split_once("=>")?;
+            if name.trim() == constraint { Some(violation.trim()) } else { None }
+        })
+    }
+}
+
+/// Returns the annotations of the statement, which are parsed when the method is called for the first time.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! annotations {
+    ( $name:ident $doc:literal ) => {{
+        static ANNOTATIONS: ::std::sync::OnceLock<$crate::util::Annotations> = ::std::sync::OnceLock::new();
+        ANNOTATIONS.get_or_init(|| $crate::util::Annotations::parse(::std::stringify!($name), $doc))
+    }};
+}
+
+#[cfg(feature = "tracing")]
+pub use ::tracing::{Instrument, Span};
+
+/// Stands in for `tracing::Span` when the `tracing` feature is not enabled.
+#[cfg(not(feature = "tracing"))]
+#[derive(Clone)]
+pub struct Span;
+
+#[cfg(not(feature = "tracing"))]
+impl Span {
+    pub fn enter(&self) -> &Self {
+        self
+    }
+}
+
+/// Stands in for `tracing::Instrument` when the `tracing` feature is not enabled.
+#[cfg(not(feature = "tracing"))]
+pub trait Instrument: Sized {
+    fn instrument(self, _span: Span) -> Self {
+        self
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+impl<T> Instrument for T {}
+
+/// Number of rows that a generated method returned or affected.
+pub trait RowCount {
+    fn row_count(&self) -> Option<u64>;
+}
+
+impl RowCount for u64 {
+    fn row_count(&self) -> Option<u64> {
+        Some(*self)
+    }
+}
+
+impl<R> RowCount for Vec<R> {
+    fn row_count(&self) -> Option<u64> {
+        Some(self.len() as u64)
+    }
+}
+
+impl RowCount for ::tokio_postgres::Row {
+    fn row_count(&self) -> Option<u64> {
+        Some(1)
+    }
+}
+
+#[cfg(all(feature = "sync", not(feature = "tokio")))]
+impl RowCount for crate::RowIter<'_> {
+    fn row_count(&self) -> Option<u64> {
+        None
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl RowCount for crate::RowStream {
+    fn row_count(&self) -> Option<u64> {
+        None
+    }
+}
+
+/// Records the statement that the generated method executes in the method span.
+#[cfg(feature = "tracing")]
+pub fn trace_statement(span: &Span, stmt: &str, in_list_len: Option<usize>) {
+    span.record("db.statement", stmt);
+    if let Some(operation) = stmt.split_whitespace().next() {
+        span.record("db.operation", operation.to_uppercase().as_str());
+    }
+    if let Some(len) = in_list_len {
+        span.record("db.in_list_size", len);
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+pub fn trace_statement(_span: &Span, _stmt: &str, _in_list_len: Option<usize>) {}
+
+/// Records the outcome of the generated method call in the method span.
+pub fn trace_result<T: RowCount, X: Failure>(span: &Span, res: &Result<T, X>) {
+    trace_outcome(span, crate::Outcome::of(res));
+}
+
+/// Records the outcome of the generated method call in the method span.
+#[cfg(feature = "tracing")]
+pub fn trace_outcome(span: &Span, outcome: crate::Outcome) {
+    match outcome {
+        crate::Outcome::Succeeded(num_rows) => {
+            if let Some(num_rows) = num_rows {
+                span.record("db.rows", num_rows);
+            }
+        }
+        crate::Outcome::Failed(err) => {
+            span.record("otel.status_code", "ERROR");
+            match err.as_db_error() {
+                Some(db_err) => span.record("error", ::tracing::field::display(db_err)),
+                None => span.record("error", ::tracing::field::display(err)),
+            };
+        }
+        crate::Outcome::CheckFailed(failure) => {
+            span.record("otel.status_code", "ERROR");
+            span.record("error", ::tracing::field::display(failure));
+        }
+        crate::Outcome::Aborted => {
+            span.record("otel.status_code", "ERROR");
+            span.record("error", "aborted by the application");
+        }
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+pub fn trace_outcome(_span: &Span, _outcome: crate::Outcome) {}
+
+/// Records metrics of a generated method call.
+#[cfg(feature = "metrics")]
+pub struct CallMetrics {
+    sql_name: &'static str,
+    name: &'static str,
+    started: ::std::time::Instant,
+}
+
+#[cfg(feature = "metrics")]
+impl CallMetrics {
+    pub fn start(sql_name: &'static str, name: &'static str) -> Self {
+        Self { sql_name, name, started: ::std::time::Instant::now() }
+    }
+
+    pub fn record<T: RowCount, X: Failure>(self, res: &Result<T, X>) {
+        self.record_outcome(crate::Outcome::of(res));
+    }
+
+    pub fn record_outcome(self, outcome: crate::Outcome) {
+        let Self { sql_name, name, started } = self;
+        let labels = [("sql_trait", sql_name), ("statement", name)];
+        ::metrics::counter!("sql_statement_calls_total", &labels).increment(1);
+        ::metrics::histogram!("sql_statement_duration_seconds", &labels).record(started.elapsed().as_secs_f64());
+        let sqlstate = match outcome {
+            crate::Outcome::Succeeded(Some(num_rows)) => {
+                ::metrics::histogram!("sql_statement_rows", &labels).record(num_rows as f64);
+                return;
+            }
+            crate::Outcome::Succeeded(None) => return,
+            crate::Outcome::Failed(err) => err.code().map_or("unknown", |code| code.code()).to_string(),
+            crate::Outcome::CheckFailed(failure) => failure.label().to_string(),
+            crate::Outcome::Aborted => "aborted".to_string(),
+        };
+        ::metrics::counter!("sql_statement_errors_total", "sql_trait" => sql_name, "statement" => name, "sqlstate" => sqlstate).increment(1);
+    }
+}
+
+/// Stands in for the metrics recorder when the `metrics` feature is not enabled.
+#[cfg(not(feature = "metrics"))]
+pub struct CallMetrics;
+
+#[cfg(not(feature = "metrics"))]
+impl CallMetrics {
+    pub fn start(_sql_name: &'static str, _name: &'static str) -> Self {
+        Self
+    }
+
+    pub fn record<T: RowCount, X: Failure>(self, _res: &Result<T, X>) {}
+
+    pub fn record_outcome(self, _outcome: crate::Outcome) {}
+}
+
+/// Parameter of a `^` method call, which was formatted when the call returned the rows.
+struct FormattedParam(String);
+
+impl ::std::fmt::Debug for FormattedParam {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// What the `after` hook of the interceptor is called with when the rows of a `^` method are consumed.
+struct AfterRows {
+    interceptor: ::std::sync::Arc<dyn crate::Interceptor>,
+    statement: String,
+    parameters: Vec<(&'static str, FormattedParam)>,
+}
+
+/**
+Call of a `^` method that is completed - the span is closed, metrics are recorded, and the `after` hook
+of the interceptor is called - when the application stops consuming the returned rows.
+*/
+pub struct RowsCall {
+    sql_trait: &'static str,
+    name: &'static str,
+    after: Option<AfterRows>,
+    span: Span,
+    metrics: CallMetrics,
+    started: ::std::time::Instant,
+    num_rows: u64,
+}
+
+impl RowsCall {
+    pub fn new(call: &crate::Call, interceptor: Option<::std::sync::Arc<dyn crate::Interceptor>>, span: Span, metrics: CallMetrics, started: ::std::time::Instant) -> Self {
+        // parameters borrow the method arguments, thus they are kept only if the interceptor will be shown them
+        let after = interceptor.map(|interceptor| AfterRows {
+            interceptor,
+            statement: call.statement.to_string(),
+            parameters: call.parameters.iter().map(|(name, value)| (*name, FormattedParam(format!("{:?}", value)))).collect(),
+        });
+        Self { sql_trait: call.sql_trait, name: call.name, after, span, metrics, started, num_rows: 0 }
+    }
+
+    /// Counts the row that the application received.
+    pub fn row(&mut self) {
+        self.num_rows += 1;
+    }
+
+    fn outcome<'a>(&self, err: Option<&'a ::tokio_postgres::Error>) -> crate::Outcome<'a> {
+        match err {
+            Some(err) => crate::Outcome::Failed(err),
+            None => crate::Outcome::Succeeded(Some(self.num_rows)),
+        }
+    }
+
+    /// Completes the call when the rows are consumed, fail to be fetched, or are dropped.
+    #[cfg(not(feature = "tokio"))]
+    pub fn finish(self, err: Option<&::tokio_postgres::Error>) {
+        let outcome = self.outcome(err);
+        if let Some(after) = &self.after {
+            let parameters: Vec<_> = after.parameters.iter().map(|(name, value)| (*name, value as crate::Param)).collect();
+            let call = crate::Call { sql_trait: self.sql_trait, name: self.name, statement: &after.statement, parameters: &parameters };
+            after.interceptor.after(&call, outcome, self.started.elapsed());
+        }
+        trace_outcome(&self.span, outcome);
+        self.metrics.record_outcome(outcome);
+    }
+
+    /// Completes the call when the rows are consumed, fail to be fetched, or are dropped.
+    /// The returned future resolves to the error that the rows failed to be fetched with.
+    #[cfg(feature = "tokio")]
+    pub fn finish(self, err: Option<::tokio_postgres::Error>) -> crate::BoxFuture<'static, Option<::tokio_postgres::Error>> {
+        Box::pin(async move {
+            let outcome = self.outcome(err.as_ref());
+            let elapsed = self.started.elapsed();
+            // recorded before the hook is awaited as a dropped stream polls the returned future only once
+            trace_outcome(&self.span, outcome);
+            self.metrics.record_outcome(outcome);
+            if let Some(after) = &self.after {
+                let parameters: Vec<_> = after.parameters.iter().map(|(name, value)| (*name, value as crate::Param)).collect();
+                let call = crate::Call { sql_trait: self.sql_trait, name: self.name, statement: &after.statement, parameters: &parameters };
+                after.interceptor.after(&call, outcome, elapsed).await;
+            }
+            err
+        })
+    }
+}
+
+/**
+Error of a generated method call before it is converted into the error that the method returns.
+
+Unlike other methods `?` and `%` methods can also fail when the row callback or the row conversion
+returns an application error. Other methods use the error type they return as `E`.
+*/
+pub enum CallFailure<E> {
+    /// The driver or the database reported the error.
+    Db(::tokio_postgres::Error),
+    /// The call failed a check of the statement annotations.
+    Check(crate::CheckFailure),
+    /// The row callback or the row conversion failed.
+    Aborted(E),
+}
+
+impl<E> From<::tokio_postgres::Error> for CallFailure<E> {
+    fn from(err: ::tokio_postgres::Error) -> Self {
+        Self::Db(err)
+    }
+}
+
+impl<E> From<crate::CheckFailure> for CallFailure<E> {
+    fn from(failure: crate::CheckFailure) -> Self {
+        Self::Check(failure)
+    }
+}
+
+/// Error of a generated method call before it is converted into the error that the method returns.
+pub trait Failure: From<::tokio_postgres::Error> + From<crate::CheckFailure> {
+    /// Returns the error reported by the driver or `None` if the call failed a check or was aborted by the application.
+    fn db_error(&self) -> Option<&::tokio_postgres::Error>;
+
+    /// Returns the check that the call failed or `None` if it did not fail one.
+    fn check_failure(&self) -> Option<&crate::CheckFailure>;
+}
+
+impl<E> Failure for CallFailure<E> {
+    fn db_error(&self) -> Option<&::tokio_postgres::Error> {
+        match self {
+            Self::Db(err) => Some(err),
+            _ => None,
+        }
+    }
+
+    fn check_failure(&self) -> Option<&crate::CheckFailure> {
+        match self {
+            Self::Check(failure) => Some(failure),
+            _ => None,
+        }
+    }
+}
+
+/**
+Error that generated methods report driver errors with - `postgres::Error`, or `StatementError` when `impl_sql`
+is given the `statement_error` option.
+*/
+pub trait MethodError: Sized {
+    /// Adds what the failed generated method executed to the driver error.
+    fn from_call(err: ::tokio_postgres::Error, sql_trait: &'static str, name: &'static str, annotations: &Annotations, stmt: &str, params: &[(&'static str, crate::Param)]) -> Self;
+
+    /// Adds what the failed generated method executed to the check that it failed.
+    ///
+    /// Annotations that are checked by the generated methods do not compile unless `impl_sql` is given
+    /// the `statement_error` option, thus only `StatementError` is ever created from a check failure.
+    fn from_check(failure: crate::CheckFailure, sql_trait: &'static str, name: &'static str, stmt: &str, params: &[(&'static str, crate::Param)]) -> Self;
+
+    /// Converts the error of a failed connection attempt of the named generated method.
+    fn from_connect(err: ::tokio_postgres::Error, sql_trait: &'static str, name: &'static str) -> Self;
+
+    /// Returns the error reported by the driver.
+    fn driver_error(&self) -> Option<&::tokio_postgres::Error>;
+}
+
+impl MethodError for ::tokio_postgres::Error {
+    fn from_call(err: ::tokio_postgres::Error, _sql_trait: &'static str, _name: &'static str, _annotations: &Annotations, _stmt: &str, _params: &[(&'static str, crate::Param)]) -> Self {
+        err
+    }
+
+    fn from_check(failure: crate::CheckFailure, sql_trait: &'static str, name: &'static str, _stmt: &str, _params: &[(&'static str, crate::Param)]) -> Self {
+        unreachable!("{}::{} failed a check that requires the `statement_error` option: {}", sql_trait, name, failure)
+    }
+
+    fn from_connect(err: ::tokio_postgres::Error, _sql_trait: &'static str, _name: &'static str) -> Self {
+        err
+    }
+
+    fn driver_error(&self) -> Option<&::tokio_postgres::Error> {
+        Some(self)
+    }
+}
+
+impl MethodError for crate::StatementError {
+    fn from_call(err: ::tokio_postgres::Error, sql_trait: &'static str, name: &'static str, annotations: &Annotations, stmt: &str, params: &[(&'static str, crate::Param)]) -> Self {
+        let violation = annotations.constraint_violation(&err);
+        let mut err = crate::StatementError::new(sql_trait, name, stmt, params, err);
+        err.violation = violation;
+        err
+    }
+
+    fn from_check(failure: crate::CheckFailure, sql_trait: &'static str, name: &'static str, stmt: &str, params: &[(&'static str, crate::Param)]) -> Self {
+        crate::StatementError::failed_check(sql_trait, name, stmt, params, failure)
+    }
+
+    fn from_connect(err: ::tokio_postgres::Error, sql_trait: &'static str, name: &'static str) -> Self {
+        crate::StatementError::new(sql_trait, name, "", &[], err)
+    }
+
+    fn driver_error(&self) -> Option<&::tokio_postgres::Error> {
+        crate::StatementError::driver_error(self)
+    }
+}
+
+/// Converts the failure of a generated method call into the error that the method returns when it reports
+/// driver errors as `M`.
+pub trait IntoError<M: MethodError>: Failure {
+    /// Error that the generated method returns.
+    type Error;
+
+    /// Adds what the failed generated method executed to the error and converts it into the error that the method returns.
+    fn into_error(self, sql_trait: &'static str, name: &'static str, annotations: &Annotations, stmt: &str, params: &[(&'static str, crate::Param)]) -> Self::Error;
+}
+
+impl<M: MethodError, E: From<M>> IntoError<M> for CallFailure<E> {
+    type Error = E;
+
+    fn into_error(self, sql_trait: &'static str, name: &'static str, annotations: &Annotations, stmt: &str, params: &[(&'static str, crate::Param)]) -> Self::Error {
+        match self {
+            Self::Db(err) => E::from(M::from_call(err, sql_trait, name, annotations, stmt, params)),
+            Self::Check(failure) => E::from(M::from_check(failure, sql_trait, name, stmt, params)),
+            Self::Aborted(err) => err,
+        }
+    }
+}
+
+/// Adds what the failed generated method executed to the error that the method returns.
+pub fn call_error<M: MethodError, T, X: IntoError<M>>(res: Result<T, X>, sql_trait: &'static str, name: &'static str, annotations: &Annotations, stmt: &str, params: &[(&'static str, crate::Param)]) -> Result<T, X::Error> {
+    res.map_err(|err| err.into_error(sql_trait, name, annotations, stmt, params))
+}
+
+/// Returns the function that converts the error of a failed connection attempt into the error
+/// that the named generated method returns.
+pub fn connect_error<M: MethodError>(sql_trait: &'static str, name: &'static str) -> impl FnOnce(::tokio_postgres::Error) -> M {
+    move |err| M::from_connect(err, sql_trait, name)
+}
+
+/// Adds the current trace context, if there is one, to the comment of the static statement.
+#[cfg(feature = "sqlcommenter")]
+pub fn with_trace_context(sql: &'static str) -> ::std::borrow::Cow<'static, str> {
+    crate::sql_comment::with_trace_context(sql)
+}
+
+#[cfg(not(feature = "sqlcommenter"))]
+pub fn with_trace_context(sql: &'static str) -> ::std::borrow::Cow<'static, str> {
+    ::std::borrow::Cow::Borrowed(sql)
+}
+
+/// Appends the comment, with the current trace context if there is one, to the dynamic statement.
+#[cfg(feature = "sqlcommenter")]
+pub fn tag_sql(sql: &mut String, comment: &str) {
+    sql.push_str(comment);
+    crate::sql_comment::add_trace_context(sql);
+}
+
+#[cfg(not(feature = "sqlcommenter"))]
+pub fn tag_sql(_sql: &mut String, _comment: &str) {}
+
+#[cfg(feature = "sqlcommenter")]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! sql_comment {
+    ($sql_name:ident $name:ident) => {
+        ::std::concat!(" /* name='", ::std::stringify!($name), "',trait='", ::std::stringify!($sql_name), "',app='", ::std::env!("CARGO_PKG_NAME"), "' */")
+    };
+}
+
+#[cfg(not(feature = "sqlcommenter"))]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! sql_comment {
+    ($sql_name:ident $name:ident) => {
+        ""
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! tagged_sql {
+    ($sql_name:ident $name:ident $($sql:tt)+) => {
+        ::std::concat!($($sql)+, $crate::sql_comment!($sql_name $name))
+    };
+}
+
+#[cfg(feature = "tracing")]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! stmt_span {
+    ($name:ident) => {
+        $crate::util::tracing::info_span!(
+            ::std::stringify!($name),
+            otel.kind = "client",
+            otel.status_code = $crate::util::tracing::field::Empty,
+            db.system = "postgresql",
+            db.statement = $crate::util::tracing::field::Empty,
+            db.operation = $crate::util::tracing::field::Empty,
+            db.in_list_size = $crate::util::tracing::field::Empty,
+            db.rows = $crate::util::tracing::field::Empty,
+            error = $crate::util::tracing::field::Empty,
+        )
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! stmt_span {
+    ($name:ident) => {
+        $crate::util::Span
+    };
+}
+
 #[macro_export]
 #[doc(hidden)]
 macro_rules! sql_literal {
@@ -17,6 +1499,27 @@ macro_rules! sql_literal {
     };
 }
 
+#[macro_export]
+#[doc(hidden)]
+macro_rules! arg_type {
+    ($doc:literal $param:ident $ptype:ty) => {
+        <$crate::util::Arg<{ $crate::util::has_default($doc, ::std::stringify!($param)) }> as $crate::util::ArgType<$ptype>>::Type
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! bind_defaults {
+    ($doc:literal) => {};
+    ($doc:literal : $param:ident $($tail:tt)*) => {
+        let $param = $crate::util::Arg::<{ $crate::util::has_default($doc, ::std::stringify!($param)) }>::bind($param, $doc, ::std::stringify!($param));
+        $crate::bind_defaults!($doc $($tail)*);
+    };
+    ($doc:literal # $param:ident $($tail:tt)*) => {
+        $crate::bind_defaults!($doc $($tail)*);
+    };
+}
+
 #[macro_export]
 #[doc(hidden)]
 macro_rules! num_args {
@@ -25,6 +1528,14 @@ macro_rules! num_args {
     (# $head:ident $($tail:tt)*) => { $head.len() + $crate::num_args!($($tail)*) };
 }
 
+#[macro_export]
+#[doc(hidden)]
+macro_rules! in_list_len {
+    () => { 0 };
+    (: $head:ident $($tail:tt)*) => { $crate::in_list_len!($($tail)*) };
+    (# $head:ident $($tail:tt)*) => { $head.len() + $crate::in_list_len!($($tail)*) };
+}
+
 #[macro_export]
 #[doc(hidden)]
 macro_rules! sql_len {
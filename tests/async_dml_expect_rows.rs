@@ -0,0 +1,106 @@
+#[cfg(feature = "tokio")]
+mod test {
+    use include_postgres_sql::{ include_sql, CheckFailure, ErrorClass, RetryReason };
+    use tokio_postgres::{Config, NoTls};
+
+    macro_rules! impl_sql {
+        ( $($tt:tt)+ ) => {
+            include_postgres_sql::impl_sql!{ #[options(statement_error)] $($tt)+ }
+        };
+    }
+
+    include_sql!("tests/sql/dml_expect_rows.sql");
+
+    #[tokio::test]
+    async fn impl_method_with_expected_rows() -> Result<(),Box<dyn std::error::Error>> {
+        let (mut db, conn) = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls).await?;
+
+        tokio::spawn(async move {
+            if let Err(e) = conn.await {
+                eprintln!("connection error: {}", e);
+            }
+        });
+
+        // `db.rename_genre(..)` does not compile as the client might be shared by tasks which queries would
+        // be executed within the transaction that the call would start for the check
+        let tr = db.transaction().await?;
+        let genre_id : i32 = tr.new_genre("Expected").await?.try_get("genre_id")?;
+
+        let num_rows = tr.rename_genre(genre_id, "Expected Rows").await?;
+        assert_eq!(num_rows, 1);
+
+        let err = tr.rename_genre(-1, "Missing").await.expect_err("no rows are updated");
+        assert_eq!(err.check_failure(), Some(&CheckFailure::RowCount { expected: 1..=1, actual: 0 }));
+        assert!(err.to_string().ends_with("::rename_genre failed: affected 0 rows, expected 1"));
+
+        let num_rows = tr.rename_genres(&[genre_id, -1], "Expected Rows").await?;
+        assert_eq!(num_rows, 1);
+        tr.commit().await?;
+
+        let tr = db.transaction().await?;
+        let err = tr.rename_genres(&[1, 2, 3], "Oops").await.expect_err("too many rows are updated");
+        assert_eq!(err.check_failure(), Some(&CheckFailure::RowCount { expected: 1..=2, actual: 3 }));
+        // the check failure does not abort the transaction - the caller decides whether to roll it back
+        let name : String = tr.get_genre(1).await?.try_get("name")?;
+        assert_eq!(name, "Oops");
+        tr.rollback().await?;
+        let name : String = db.get_genre(1).await?.try_get("name")?;
+        assert_eq!(name, "Rock");
+
+        let row = db.get_genre(genre_id).await?;
+        let version : &str = row.try_get("version")?;
+        let tr = db.transaction().await?;
+        let num_rows = tr.update_genre(genre_id, version, "Optimistic").await?;
+        assert_eq!(num_rows, 1);
+        tr.commit().await?;
+
+        let tr = db.transaction().await?;
+        let err = tr.update_genre(genre_id, version, "Lost Update").await.expect_err("genre was updated");
+        assert_eq!(err.check_failure(), Some(&CheckFailure::Conflict));
+        assert_eq!(err.code(), None);
+        assert_eq!(ErrorClass::of(&err), ErrorClass::Conflict);
+        assert_eq!(RetryReason::of(&err), Some(RetryReason::Conflict));
+        tr.rollback().await?;
+        let name : String = db.get_genre(genre_id).await?.try_get("name")?;
+        assert_eq!(name, "Optimistic");
+
+        let num_deleted = db.delete_genre(genre_id).await?;
+        assert_eq!(num_deleted, 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn concurrent_calls_with_expected_rows() -> Result<(),Box<dyn std::error::Error>> {
+        let (mut db, conn) = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls).await?;
+
+        tokio::spawn(async move {
+            if let Err(e) = conn.await {
+                eprintln!("connection error: {}", e);
+            }
+        });
+
+        // Both calls are pipelined over the same session. The failed check of one call must not undo
+        // the changes of the other.
+        let tr = db.transaction().await?;
+        let (renamed, missing) = tokio::join!(
+            tr.rename_genre(1, "Concurrent"),
+            tr.rename_genre(-1, "Missing"),
+        );
+        assert_eq!(renamed?, 1);
+        assert_eq!(missing.expect_err("no rows are updated").check_failure(), Some(&CheckFailure::RowCount { expected: 1..=1, actual: 0 }));
+        let name : String = tr.get_genre(1).await?.try_get("name")?;
+        assert_eq!(name, "Concurrent");
+        tr.rollback().await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+#[cfg(feature = "tokio")]
+mod test {
+    use include_postgres_sql::{include_sql, test_transaction};
+    use tokio_postgres::{Config, Error, NoTls};
+
+    macro_rules! impl_sql {
+        ( $($tt:tt)+ ) => {
+            include_postgres_sql::impl_sql!{ #[options(fixtures)] $($tt)+ }
+        };
+    }
+
+    include_sql!("tests/sql/dml_fixtures.sql");
+
+    fn test_database() -> Config {
+        let mut config = Config::new();
+        config.host("localhost").user("postgres").dbname("chinook");
+        config
+    }
+
+    #[tokio::test]
+    async fn apply_fixtures_with_dependencies() -> Result<(), Error> {
+        test_transaction(&test_database(), NoTls, |tx| Box::pin(async move {
+            // fixture_album is applied once - as a dependency of fixture_tracks
+            tx.apply_fixtures(&["fixture_tracks", "fixture_album"]).await?;
+            let row = tx.count_album_tracks(10001).await?;
+            assert_eq!(row.try_get::<_, i64>("num_tracks")?, 2);
+            let row = tx.count_fixture_rows().await?;
+            assert_eq!(row.try_get::<_, i64>("num_rows")?, 5);
+            Ok::<_, Error>(())
+        })).await?;
+
+        test_transaction(&test_database(), NoTls, |tx| Box::pin(async move {
+            tx.apply_fixtures(&["fixture_genre"]).await?;
+            let row = tx.count_fixture_rows().await?;
+            assert_eq!(row.try_get::<_, i64>("num_rows")?, 1);
+            Ok::<_, Error>(())
+        })).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "DmlFixturesSql has no fixture fixture_playlist")]
+    async fn panic_on_unknown_fixture() {
+        let _ = test_transaction(&test_database(), NoTls, |tx| Box::pin(async move {
+            tx.apply_fixtures(&["fixture_artist", "fixture_playlist"]).await
+        })).await;
+    }
+}
@@ -0,0 +1,64 @@
+#[cfg(feature = "tokio")]
+mod test {
+    use include_postgres_sql::{ include_sql, impl_sql, with_transaction, Retry, RetryReason };
+    use tokio_postgres::{Config, NoTls, IsolationLevel, error::SqlState};
+    use std::{sync::{Arc, Mutex, atomic::{AtomicU32, Ordering}}, time::Duration};
+
+    include_sql!("tests/sql/dml_retry.sql");
+
+    #[tokio::test]
+    async fn retry_transaction() -> Result<(),tokio_postgres::Error> {
+        let (mut db, conn) = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls).await?;
+
+        tokio::spawn(async move {
+            if let Err(e) = conn.await {
+                eprintln!("connection error: {}", e);
+            }
+        });
+
+        let retries = Arc::new(Mutex::new(Vec::new()));
+        let retry = {
+            let retries = retries.clone();
+            Retry::new(IsolationLevel::Serializable)
+                .max_attempts(3)
+                .backoff(Duration::from_millis(1), Duration::from_millis(2))
+                .on_retry(move |reason, attempt, _| retries.lock().unwrap().push((reason, attempt)))
+        };
+
+        let attempts = AtomicU32::new(0);
+        let genre_id : i32 = with_transaction(&mut db, &retry, |tx| {
+            let attempt = attempts.fetch_add(1, Ordering::Relaxed) + 1;
+            Box::pin(async move {
+                let row = tx.new_genre("Retried").await?;
+                if attempt == 1 {
+                    tx.fail_serialization().await?;
+                }
+                row.try_get("genre_id")
+            })
+        }).await?;
+        assert_eq!(attempts.load(Ordering::Relaxed), 2);
+        assert_eq!(*retries.lock().unwrap(), [(RetryReason::SerializationFailure, 1)]);
+
+        let num_deleted = db.delete_genre(genre_id).await?;
+        assert_eq!(num_deleted, 1);
+
+        retries.lock().unwrap().clear();
+        attempts.store(0, Ordering::Relaxed);
+        let res = with_transaction(&mut db, &retry, |tx| {
+            attempts.fetch_add(1, Ordering::Relaxed);
+            Box::pin(async move {
+                tx.new_genre("Never Committed").await?;
+                tx.fail_serialization().await
+            })
+        }).await;
+        assert_eq!(attempts.load(Ordering::Relaxed), 3);
+        assert_eq!(res.unwrap_err().code(), Some(&SqlState::T_R_SERIALIZATION_FAILURE));
+        assert_eq!(retries.lock().unwrap().len(), 2);
+
+        Ok(())
+    }
+}
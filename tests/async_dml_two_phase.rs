@@ -0,0 +1,81 @@
+/*!
+Two-phase commit needs the server to allow prepared transactions, i.e. `max_prepared_transactions` has to be
+set to a positive number in `postgresql.conf`. It is 0 by default, and then this test is skipped.
+*/
+#[cfg(feature = "tokio")]
+mod test {
+    use include_postgres_sql::{ include_sql, impl_sql, prepare_transaction, commit_prepared, rollback_prepared, prepared_transactions };
+    use tokio_postgres::{Client, Config, NoTls};
+
+    include_sql!("tests/sql/dml_two_phase.sql");
+
+    async fn count_genres(db: &Client, name: &str) -> Result<i64,tokio_postgres::Error> {
+        db.count_genres(name).await?.try_get("num_genres")
+    }
+
+    async fn is_prepared(db: &Client, gid: &str) -> Result<bool,tokio_postgres::Error> {
+        Ok(prepared_transactions(db).await?.iter().any(|tx| tx.gid == gid))
+    }
+
+    /// Tells whether the server allows transactions to be prepared for two-phase commit.
+    async fn supports_two_phase(db: &Client) -> Result<bool,tokio_postgres::Error> {
+        let max_prepared : String = db.query_one("SHOW max_prepared_transactions", &[]).await?.try_get(0)?;
+        Ok(max_prepared.parse::<u32>().is_ok_and(|max| max > 0))
+    }
+
+    /// Rolls back transactions and deletes genres that a previously failed run left behind.
+    async fn clean_up(db: &Client, gids: &[&str]) -> Result<(),tokio_postgres::Error> {
+        for tx in prepared_transactions(db).await? {
+            if gids.contains(&tx.gid.as_str()) {
+                rollback_prepared(db, &tx.gid).await?;
+            }
+        }
+        db.execute("DELETE FROM genre WHERE name = 'Two-Phase Async'", &[]).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn two_phase_commit() -> Result<(),tokio_postgres::Error> {
+        let (mut db, conn) = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls).await?;
+
+        tokio::spawn(async move {
+            if let Err(e) = conn.await {
+                eprintln!("connection error: {}", e);
+            }
+        });
+
+        if !supports_two_phase(&db).await? {
+            eprintln!("skipped: max_prepared_transactions is 0");
+            return Ok(());
+        }
+        clean_up(&db, &["async-two-phase-commit", "async-two-phase-rollback"]).await?;
+
+        let gid = "async-two-phase-commit";
+        let genre_id : i32 = prepare_transaction(&mut db, gid, |tx| Box::pin(async move {
+            tx.new_genre("Two-Phase Async").await?.try_get("genre_id")
+        })).await?;
+        assert!(is_prepared(&db, gid).await?);
+        assert_eq!(count_genres(&db, "Two-Phase Async").await?, 0);
+
+        commit_prepared(&db, gid).await?;
+        assert!(!is_prepared(&db, gid).await?);
+        assert_eq!(count_genres(&db, "Two-Phase Async").await?, 1);
+
+        let num_deleted = db.delete_genre(genre_id).await?;
+        assert_eq!(num_deleted, 1);
+
+        let gid = "async-two-phase-rollback";
+        prepare_transaction(&mut db, gid, |tx| Box::pin(tx.new_genre("Two-Phase Async"))).await?;
+        assert!(is_prepared(&db, gid).await?);
+
+        rollback_prepared(&db, gid).await?;
+        assert!(!is_prepared(&db, gid).await?);
+        assert_eq!(count_genres(&db, "Two-Phase Async").await?, 0);
+
+        Ok(())
+    }
+}
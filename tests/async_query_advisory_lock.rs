@@ -0,0 +1,82 @@
+#[cfg(feature = "tokio")]
+mod test {
+    use include_postgres_sql::{ include_sql, AdvisoryLock, CheckFailure };
+    use tokio_postgres::{Client, Config, NoTls};
+
+    macro_rules! impl_sql {
+        ( $($tt:tt)+ ) => {
+            include_postgres_sql::impl_sql!{ #[options(statement_error)] $($tt)+ }
+        };
+    }
+
+    include_sql!("tests/sql/query_advisory_lock.sql");
+
+    async fn connect() -> Result<Client,tokio_postgres::Error> {
+        let (db, conn) = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls).await?;
+
+        tokio::spawn(async move {
+            if let Err(e) = conn.await {
+                eprintln!("connection error: {}", e);
+            }
+        });
+
+        Ok(db)
+    }
+
+    async fn num_locks(db: &Client, key: i64) -> Result<i64,tokio_postgres::Error> {
+        db.query_one(
+            "SELECT Count(*) FROM pg_locks WHERE locktype = 'advisory' AND objid = $1::BIGINT AND pid = pg_backend_pid()",
+            &[&key]
+        ).await?.try_get(0)
+    }
+
+    #[tokio::test]
+    async fn impl_method_with_advisory_lock() -> Result<(),Box<dyn std::error::Error>> {
+        let mut db = connect().await?;
+        let other = connect().await?;
+
+        // `db.run_job()` does not compile as the client might be shared by tasks which queries would
+        // be executed within the transaction that the call would start for the lock
+        let tr = db.transaction().await?;
+        let num : i64 = tr.run_job().await?.try_get("num_locks")?;
+        assert_eq!(num, 1);
+        let num : i64 = tr.query_one("SELECT Count(*) FROM pg_locks WHERE locktype = 'advisory' AND objid = 4201 AND pid = pg_backend_pid()", &[]).await?.try_get(0)?;
+        assert_eq!(num, 1);
+        tr.rollback().await?;
+        assert_eq!(num_locks(&db, 4201).await?, 0);
+
+        let lock = AdvisoryLock::lock(&other, 4202).await?;
+        {
+            let tr = db.transaction().await?;
+            let err = tr.try_run_job().await.expect_err("lock is busy");
+            assert_eq!(err.check_failure(), Some(&CheckFailure::Busy { key: "4202" }));
+            assert_eq!(err.code(), None);
+            assert!(err.to_string().ends_with("::try_run_job failed: advisory lock 4202 is held by another session"));
+            // the busy lock does not abort the transaction
+            let num : i64 = tr.run_job().await?.try_get("num_locks")?;
+            assert_eq!(num, 1);
+            tr.rollback().await?;
+        }
+        assert!(AdvisoryLock::try_lock(&db, 4202).await?.is_none());
+        drop(lock);
+        // the unlock request sent by the dropped guard is executed before the next request
+        assert_eq!(num_locks(&other, 4202).await?, 0);
+
+        let tr = db.transaction().await?;
+        let num : i64 = tr.try_run_job().await?.try_get("num_locks")?;
+        assert_eq!(num, 1);
+        tr.rollback().await?;
+
+        let lock = AdvisoryLock::try_lock(&db, 4202).await?.expect("lock is available");
+        assert_eq!(lock.key(), 4202);
+        assert_eq!(num_locks(&lock, 4202).await?, 1);
+        lock.unlock().await?;
+        assert_eq!(num_locks(&db, 4202).await?, 0);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+#[cfg(feature = "tokio")]
+mod test {
+    use std::convert::TryFrom;
+
+    use include_postgres_sql::include_sql;
+    use tokio_postgres::{Client, Config, NoTls, Error, Row, error::SqlState};
+
+    macro_rules! impl_sql {
+        ( $($tt:tt)+ ) => {
+            include_postgres_sql::impl_sql!{ #[options(app_errors)] $($tt)+ }
+        };
+    }
+
+    include_sql!("tests/sql/query_app_error.sql");
+
+    #[derive(Debug)]
+    enum AppError {
+        Db(tokio_postgres::Error),
+        Unsupported(String),
+    }
+
+    impl From<tokio_postgres::Error> for AppError {
+        fn from(err: tokio_postgres::Error) -> Self {
+            Self::Db(err)
+        }
+    }
+
+    struct Genre {
+        name: String,
+    }
+
+    impl TryFrom<Row> for Genre {
+        type Error = AppError;
+
+        fn try_from(row: Row) -> Result<Self, Self::Error> {
+            let name : String = row.get("name");
+            if name == "Metal" {
+                return Err(AppError::Unsupported(name));
+            }
+            Ok(Self { name })
+        }
+    }
+
+    async fn connect() -> Result<Client,Error> {
+        let (db, conn) = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls).await?;
+
+        tokio::spawn(async move {
+            if let Err(e) = conn.await {
+                eprintln!("connection error: {}", e);
+            }
+        });
+
+        Ok(db)
+    }
+
+    #[tokio::test]
+    async fn fail_with_app_errors() -> Result<(), Error> {
+        let mut db = connect().await?;
+
+        // `get_genres` declares settings, thus it can only be called within a transaction
+        let tx = db.transaction().await?;
+        let mut names = Vec::new();
+        let res = tx.get_genres(3, |row| {
+            let name : String = row.get("name");
+            if name == "Jazz" {
+                return Err(AppError::Unsupported(name));
+            }
+            names.push(name);
+            Ok(())
+        }).await;
+        assert!(matches!(res, Err(AppError::Unsupported(name)) if name == "Jazz"));
+        assert_eq!(names, ["Rock"]);
+        tx.rollback().await?;
+
+        let row = db.get_work_mem().await?;
+        let work_mem : &str = row.try_get("work_mem")?;
+        assert_eq!(work_mem, "4MB");
+
+        let res : Result<Vec<Genre>, AppError> = db.get_genre_list(3).await;
+        assert!(matches!(res, Err(AppError::Unsupported(name)) if name == "Metal"));
+
+        let genres : Vec<Genre> = db.get_genre_list::<Genre, AppError>(2).await.unwrap();
+        assert_eq!(genres.iter().map(|genre| genre.name.as_str()).collect::<Vec<_>>(), ["Rock", "Jazz"]);
+
+        let tx = db.transaction().await?;
+        let res = tx.get_genres(i32::MAX, |_| Ok::<_, AppError>(())).await;
+        assert!(res.is_ok());
+        tx.rollback().await?;
+
+        let tx = db.transaction().await?;
+        tx.batch_execute("SELECT 1/0").await.unwrap_err();
+        let res = tx.get_genres(1, |_| Ok::<_, AppError>(())).await;
+        assert!(matches!(res, Err(AppError::Db(err)) if err.code() == Some(&SqlState::IN_FAILED_SQL_TRANSACTION)));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+#[cfg(feature = "tokio")]
+mod test {
+    use include_postgres_sql::{ include_sql, impl_sql, cancel_on_drop };
+    use tokio_postgres::{Config, NoTls};
+    use std::time::{Duration, Instant};
+
+    include_sql!("tests/sql/query_cancel.sql");
+
+    #[tokio::test]
+    async fn cancel_impl_method_on_drop() -> Result<(),tokio_postgres::Error> {
+        let (mut db, conn) = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls).await?;
+
+        tokio::spawn(async move {
+            if let Err(e) = conn.await {
+                eprintln!("connection error: {}", e);
+            }
+        });
+
+        let start = Instant::now();
+        let res = tokio::time::timeout(
+            Duration::from_millis(200),
+            cancel_on_drop(&mut db, NoTls, |db| db.sleep(10.0, |_| Ok(())))
+        ).await;
+        assert!(res.is_err());
+
+        // the connection would be busy for another 10 seconds if the query was not canceled
+        let row = db.query_one("SELECT 1", &[]).await?;
+        let one : i32 = row.try_get(0)?;
+        assert_eq!(one, 1);
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+#[cfg(feature = "tokio")]
+mod test {
+    use include_postgres_sql::{include_sql, impl_sql, ErrorClass};
+    use tokio_postgres::{Client, Config, NoTls, Error, error::SqlState};
+
+    include_sql!("tests/sql/query_error_class.sql");
+
+    async fn connect() -> Result<Client,Error> {
+        let (db, conn) = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls).await?;
+
+        tokio::spawn(async move {
+            if let Err(e) = conn.await {
+                eprintln!("connection error: {}", e);
+            }
+        });
+
+        Ok(db)
+    }
+
+    #[tokio::test]
+    async fn classify_errors() -> Result<(), Error> {
+        let mut db = connect().await?;
+
+        let err = db.copy_album(1).await.unwrap_err();
+        assert_eq!(ErrorClass::of(&err), ErrorClass::UniqueViolation {
+            constraint: Some("pk_album".to_string()),
+            table: Some("album".to_string()),
+        });
+
+        let err = db.move_album(1, -1).await.unwrap_err();
+        assert_eq!(ErrorClass::of(&err), ErrorClass::ForeignKeyViolation {
+            constraint: Some("fk_album_artist_id".to_string()),
+            table: Some("album".to_string()),
+        });
+
+        let err = db.rename_album(1, None).await.unwrap_err();
+        assert_eq!(ErrorClass::of(&err), ErrorClass::NotNullViolation {
+            table: Some("album".to_string()),
+            column: Some("title".to_string()),
+        });
+
+        let err = db.fail_check().await.unwrap_err();
+        assert_eq!(ErrorClass::of(&err), ErrorClass::CheckViolation {
+            constraint: Some("album_title_check".to_string()),
+            table: Some("album".to_string()),
+        });
+
+        let err = db.fail_serialization().await.unwrap_err();
+        assert_eq!(ErrorClass::of(&err), ErrorClass::SerializationFailure);
+
+        let err = db.fail_deadlock().await.unwrap_err();
+        assert_eq!(ErrorClass::of(&err), ErrorClass::Deadlock);
+
+        let tx = db.transaction().await?;
+        let err = tx.sleep(|_| Ok(())).await.unwrap_err();
+        assert_eq!(ErrorClass::of(&err), ErrorClass::QueryCanceled);
+        tx.rollback().await?;
+
+        let err = db.divide_by_zero().await.unwrap_err();
+        assert_eq!(ErrorClass::of(&err), ErrorClass::Other(Some(SqlState::DIVISION_BY_ZERO)));
+
+        let err = db.terminate_session().await.unwrap_err();
+        assert_eq!(ErrorClass::of(&err), ErrorClass::ConnectionClosed);
+
+        let err = db.divide_by_zero().await.unwrap_err();
+        assert_eq!(ErrorClass::of(&err), ErrorClass::ConnectionClosed);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,31 @@
+#[cfg(feature = "tokio")]
+mod test {
+    use include_postgres_sql::{ include_sql, impl_sql };
+    use tokio_postgres::{Config, NoTls};
+
+    include_sql!("tests/sql/query_for_update.sql");
+
+    #[tokio::test]
+    async fn impl_method_requiring_transaction() -> Result<(),tokio_postgres::Error> {
+        let (mut db, conn) = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls).await?;
+
+        tokio::spawn(async move {
+            if let Err(e) = conn.await {
+                eprintln!("connection error: {}", e);
+            }
+        });
+
+        // `db.lock_genre(1)` does not compile as `lock_genre` requires a transaction
+        let tr = db.transaction().await?;
+        let row = tr.lock_genre(1).await?;
+        let name : &str = row.try_get("name")?;
+        assert_eq!(name, "Rock");
+        tr.rollback().await?;
+
+        Ok(())
+    }
+}
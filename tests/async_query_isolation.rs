@@ -0,0 +1,46 @@
+#[cfg(feature = "tokio")]
+mod test {
+    use include_postgres_sql::{ include_sql, impl_sql };
+    use tokio_postgres::{Config, IsolationLevel, NoTls, error::SqlState};
+
+    include_sql!("tests/sql/query_isolation.sql");
+
+    #[tokio::test]
+    async fn impl_method_with_isolation() -> Result<(),tokio_postgres::Error> {
+        let (mut db, conn) = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls).await?;
+
+        tokio::spawn(async move {
+            if let Err(e) = conn.await {
+                eprintln!("connection error: {}", e);
+            }
+        });
+
+        // `db.get_snapshot_mode()` does not compile as the client might be shared by tasks which queries
+        // would be executed within the transaction that the call would start
+        let tr = db.build_transaction().isolation_level(IsolationLevel::RepeatableRead).read_only(true).start().await?;
+        let row = tr.get_snapshot_mode().await?;
+        let isolation : &str = row.try_get("isolation")?;
+        let read_only : &str = row.try_get("read_only")?;
+        assert_eq!(isolation, "repeatable read");
+        assert_eq!(read_only, "on");
+        tr.sleep_in_snapshot(0.0, |_| Ok(())).await?;
+        tr.rollback().await?;
+
+        let tr = db.transaction().await?;
+        let err = tr.get_snapshot_mode().await.expect_err("transaction mode is checked");
+        assert_eq!(err.code(), Some(&SqlState::INVALID_TRANSACTION_STATE));
+        tr.rollback().await?;
+
+        let tr = db.build_transaction().isolation_level(IsolationLevel::Serializable).read_only(true).start().await?;
+        let row = tr.get_snapshot_mode().await?;
+        let isolation : &str = row.try_get("isolation")?;
+        assert_eq!(isolation, "serializable");
+        tr.rollback().await?;
+
+        Ok(())
+    }
+}
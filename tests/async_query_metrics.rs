@@ -0,0 +1,146 @@
+#[cfg(all(feature = "metrics", feature = "tokio"))]
+mod test {
+    use include_postgres_sql::{include_sql, impl_sql};
+    use tokio_postgres::{Client, Config, NoTls, Error};
+    use futures_util::TryStreamExt;
+    use metrics::{Counter, CounterFn, Gauge, Histogram, HistogramFn, Key, KeyName, Metadata, Recorder, SharedString, Unit};
+    use std::{convert::TryFrom, sync::{Arc, Mutex}};
+
+    include_sql!("tests/sql/query_metrics.sql");
+
+    /// Metric name, labels, and the recorded value.
+    type Measurement = (String, Vec<(String, String)>, f64);
+    type Measurements = Arc<Mutex<Vec<Measurement>>>;
+
+    /// Collects all recorded values with the names and labels of their metrics.
+    #[derive(Default)]
+    struct Measured(Measurements);
+
+    impl Measured {
+        fn take(&self) -> Vec<Measurement> {
+            std::mem::take(&mut *self.0.lock().unwrap())
+        }
+    }
+
+    struct Handle(Key, Measurements);
+
+    impl Handle {
+        fn push(&self, value: f64) {
+            let labels = self.0.labels().map(|label| (label.key().to_string(), label.value().to_string())).collect();
+            self.1.lock().unwrap().push((self.0.name().to_string(), labels, value));
+        }
+    }
+
+    impl CounterFn for Handle {
+        fn increment(&self, value: u64) {
+            self.push(value as f64);
+        }
+
+        fn absolute(&self, value: u64) {
+            self.push(value as f64);
+        }
+    }
+
+    impl HistogramFn for Handle {
+        fn record(&self, value: f64) {
+            self.push(value);
+        }
+    }
+
+    impl Recorder for Measured {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+            Counter::from_arc(Arc::new(Handle(key.clone(), self.0.clone())))
+        }
+
+        fn register_gauge(&self, _: &Key, _: &Metadata<'_>) -> Gauge {
+            Gauge::noop()
+        }
+
+        fn register_histogram(&self, key: &Key, _: &Metadata<'_>) -> Histogram {
+            Histogram::from_arc(Arc::new(Handle(key.clone(), self.0.clone())))
+        }
+    }
+
+    struct GenreId(i32);
+
+    impl TryFrom<tokio_postgres::Row> for GenreId {
+        type Error = Error;
+
+        fn try_from(row: tokio_postgres::Row) -> Result<Self, Self::Error> {
+            Ok(Self(row.try_get("genre_id")?))
+        }
+    }
+
+    fn labels(statement: &str) -> Vec<(String, String)> {
+        vec![("sql_trait".to_string(), "QueryMetricsSql".to_string()), ("statement".to_string(), statement.to_string())]
+    }
+
+    async fn connect() -> Result<Client,Error> {
+        let (db, conn) = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls).await?;
+
+        tokio::spawn(async move {
+            if let Err(e) = conn.await {
+                eprintln!("connection error: {}", e);
+            }
+        });
+
+        Ok(db)
+    }
+
+    #[tokio::test]
+    async fn record_metrics() -> Result<(), Error> {
+        let db = connect().await?;
+
+        let measured = Measured::default();
+        let _local = metrics::set_default_local_recorder(&measured);
+
+        let genres : Vec<GenreId> = db.get_genres(&[1, 3, 5]).await?;
+        assert_eq!(genres.iter().map(|genre| genre.0).collect::<Vec<_>>(), [1, 3, 5]);
+
+        let values = measured.take();
+        assert_eq!(values.len(), 3);
+        assert_eq!(values[0], ("sql_statement_calls_total".to_string(), labels("get_genres"), 1.0));
+        assert_eq!(values[1].0, "sql_statement_duration_seconds");
+        assert_eq!(values[1].1, labels("get_genres"));
+        assert!(values[1].2 > 0.0);
+        assert_eq!(values[2], ("sql_statement_rows".to_string(), labels("get_genres"), 3.0));
+
+        let num_deleted = db.delete_genre(-1).await?;
+        assert_eq!(num_deleted, 0);
+
+        let values = measured.take();
+        assert_eq!(values.len(), 3);
+        assert_eq!(values[2], ("sql_statement_rows".to_string(), labels("delete_genre"), 0.0));
+
+        let rows = db.get_genre_names().await?;
+        assert!(measured.take().is_empty());
+        let names : Vec<String> = rows.map_ok(|row| row.get("name")).try_collect().await?;
+        assert_eq!(names.len(), 25);
+
+        let values = measured.take();
+        assert_eq!(values.len(), 3);
+        assert_eq!(values[0], ("sql_statement_calls_total".to_string(), labels("get_genre_names"), 1.0));
+        assert_eq!(values[2], ("sql_statement_rows".to_string(), labels("get_genre_names"), 25.0));
+
+        let res = db.divide_by_zero().await;
+        assert!(res.is_err());
+
+        let values = measured.take();
+        assert_eq!(values.len(), 3);
+        assert_eq!(values[0], ("sql_statement_calls_total".to_string(), labels("divide_by_zero"), 1.0));
+        assert_eq!(values[1].0, "sql_statement_duration_seconds");
+        let mut error_labels = labels("divide_by_zero");
+        error_labels.push(("sqlstate".to_string(), "22012".to_string()));
+        assert_eq!(values[2], ("sql_statement_errors_total".to_string(), error_labels, 1.0));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+#[cfg(feature = "tokio")]
+mod test {
+    use include_postgres_sql::{ include_sql, ReconnectingClient };
+    use tokio_postgres::{Config, NoTls};
+
+    macro_rules! impl_sql {
+        ( $($tt:tt)+ ) => {
+            include_postgres_sql::impl_sql!{ #[options(reconnect)] $($tt)+ }
+        };
+    }
+
+    include_sql!("tests/sql/query_reconnect.sql");
+
+    fn config() -> Config {
+        let mut config = Config::new();
+        config
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook");
+        config
+    }
+
+    async fn terminate(pid: i32) -> Result<(),tokio_postgres::Error> {
+        let (admin, conn) = config().connect(NoTls).await?;
+        tokio::spawn(async move {
+            if let Err(e) = conn.await {
+                eprintln!("connection error: {}", e);
+            }
+        });
+        admin.execute("SELECT pg_terminate_backend($1, 5000)", &[&pid]).await?;
+        Ok(())
+    }
+
+    async fn backend_pid(db: &ReconnectingClient) -> Result<i32,tokio_postgres::Error> {
+        db.get_backend_pid().await?.try_get("pid")
+    }
+
+    #[tokio::test]
+    async fn impl_reconnecting_client() -> Result<(),tokio_postgres::Error> {
+        let mut db = ReconnectingClient::connect(config(), NoTls).await?;
+        let pid = backend_pid(&db).await?;
+
+        // idempotent statement is retried on a new connection
+        terminate(pid).await?;
+        let new_pid = backend_pid(&db).await?;
+        assert_ne!(new_pid, pid);
+
+        // non-idempotent statement is not retried...
+        let pid = backend_pid(&db).await?;
+        db.terminate_session(pid).await.expect_err("statement is not retried");
+        // ...but the client reconnects before the next call
+        let num_rows = db.terminate_session(pid).await?;
+        assert_eq!(num_rows, 0);
+        let new_pid = backend_pid(&db).await?;
+        assert_ne!(new_pid, pid);
+
+        // idempotent statement is retried
+        let num_rows = db.terminate_session_idempotent(new_pid).await?;
+        assert_eq!(num_rows, 0);
+        assert_ne!(backend_pid(&db).await?, new_pid);
+
+        terminate(backend_pid(&db).await?).await?;
+        let tr = db.transaction().await?;
+        let num_rows = tr.terminate_session(0).await?;
+        assert_eq!(num_rows, 0);
+        tr.rollback().await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+#[cfg(all(feature = "replay", feature = "tokio"))]
+mod test {
+    use std::convert::TryFrom;
+
+    use futures_util::TryStreamExt;
+    use include_postgres_sql::{include_sql, impl_sql};
+    use tokio_postgres::{Config, NoTls, Error, Row, error::SqlState};
+
+    include_sql!("tests/sql/query_replay.sql");
+
+    #[derive(Debug, PartialEq)]
+    struct Genre {
+        id: i32,
+        name: String,
+    }
+
+    impl TryFrom<Row> for Genre {
+        type Error = Error;
+
+        fn try_from(row: Row) -> Result<Self, Self::Error> {
+            Ok(Self { id: row.try_get("genre_id")?, name: row.try_get("name")? })
+        }
+    }
+
+    #[tokio::test]
+    async fn replay_recorded_results() -> Result<(), Box<dyn std::error::Error>> {
+        let db = ReplayQueryReplaySql::start("tests/replay/async_query_replay.txt", || async {
+            let (client, conn) = Config::new().host("localhost").user("postgres").dbname("chinook").connect(NoTls).await?;
+            tokio::spawn(async move {
+                if let Err(e) = conn.await {
+                    eprintln!("connection error: {}", e);
+                }
+            });
+            Ok::<_, Error>(client)
+        }).await?;
+
+        let mut tracks = Vec::new();
+        db.get_top_sales(&["London", "Berlin"], 2, |row| {
+            let artist_name : String = row.try_get("artist_name")?;
+            let track_name  : String = row.try_get("track_name")?;
+            let num_sold    :    i64 = row.try_get("num_sold")?;
+            tracks.push((artist_name, track_name, num_sold));
+            Ok(())
+        }).await?;
+        assert_eq!(tracks, [
+            ("Cidade Negra".to_string(), "Firmamento".to_string(), 2),
+            ("Iron Maiden".to_string(), "The Number Of The Beast".to_string(), 2),
+            ("Van Halen".to_string(), "Eruption".to_string(), 2),
+        ]);
+
+        let genres : Vec<Genre> = db.get_genres(2).await?;
+        assert_eq!(genres, [Genre { id: 1, name: "Rock".into() }, Genre { id: 2, name: "Jazz".into() }]);
+
+        let names : Vec<String> = db.get_genre_names(3).await?.map_ok(|row| row.get("name")).try_collect().await?;
+        assert_eq!(names, ["Rock", "Jazz", "Metal"]);
+
+        let row = db.get_genre_name(4).await?;
+        assert_eq!(row.try_get::<_, &str>("name")?, "Alternative & Punk");
+
+        let err = db.get_genre_name(999).await.unwrap_err();
+        assert!(err.as_db_error().is_none());
+        assert_eq!(err.to_string(), "query returned an unexpected number of rows");
+
+        assert_eq!(db.touch_genre(1).await?, 1);
+
+        let err = db.get_share(0).await.unwrap_err();
+        assert_eq!(err.code(), Some(&SqlState::DIVISION_BY_ZERO));
+
+        if !db.is_recording() {
+            let err = db.get_genres::<Genre>(3).await.unwrap_err();
+            assert_eq!(err.code(), Some(&SqlState::INTERNAL_ERROR));
+            assert_eq!(
+                err.as_db_error().unwrap().message(),
+                "QueryReplaySql::get_genres(max_id = 3) was not recorded in tests/replay/async_query_replay.txt"
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,78 @@
+#[cfg(feature = "tokio")]
+mod test {
+    use include_postgres_sql::{ include_sql, Router };
+    use tokio_postgres::{Client, Config, NoTls, Row};
+    use std::convert::TryFrom;
+
+    macro_rules! impl_sql {
+        ( $($tt:tt)+ ) => {
+            include_postgres_sql::impl_sql!{ #[options(router)] $($tt)+ }
+        };
+    }
+
+    include_sql!("tests/sql/query_router.sql");
+
+    #[derive(Debug, PartialEq)]
+    struct ConnectionName(String);
+
+    impl TryFrom<Row> for ConnectionName {
+        type Error = tokio_postgres::Error;
+
+        fn try_from(row: Row) -> Result<Self, Self::Error> {
+            Ok(Self(row.try_get("name")?))
+        }
+    }
+
+    fn names(rows: &[ConnectionName]) -> Vec<&str> {
+        rows.iter().map(|ConnectionName(name)| name.as_str()).collect()
+    }
+
+    async fn connect(name: &str) -> Result<Client, tokio_postgres::Error> {
+        let (db, conn) = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .application_name(name)
+            .connect(NoTls).await?;
+
+        tokio::spawn(async move {
+            if let Err(e) = conn.await {
+                eprintln!("connection error: {}", e);
+            }
+        });
+
+        Ok(db)
+    }
+
+    #[tokio::test]
+    async fn impl_router() -> Result<(),tokio_postgres::Error> {
+        let mut db = Router::new(connect("primary").await?, vec![connect("replica1").await?, connect("replica2").await?]);
+
+        let row = db.get_connection_name().await?;
+        let name : &str = row.try_get("name")?;
+        assert_eq!(name, "primary");
+
+        let row = db.get_replica_connection_name().await?;
+        let name : &str = row.try_get("name")?;
+        assert_eq!(name, "replica1");
+
+        let rows : Vec<ConnectionName> = db.get_connection_names(&[1, 2]).await?;
+        assert_eq!(names(&rows), ["replica2", "replica2"]);
+
+        let rows : Vec<ConnectionName> = db.get_connection_names(&[3]).await?;
+        assert_eq!(names(&rows), ["replica1"]);
+
+        db.get_primary_connection_names(|row| {
+            let name : &str = row.try_get("name")?;
+            assert_eq!(name, "primary");
+            Ok(())
+        }).await?;
+
+        let tr = db.transaction().await?;
+        let rows : Vec<ConnectionName> = tr.get_connection_names(&[1]).await?;
+        assert_eq!(names(&rows), ["primary"]);
+        tr.rollback().await?;
+
+        Ok(())
+    }
+}
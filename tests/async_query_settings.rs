@@ -0,0 +1,51 @@
+#[cfg(feature = "tokio")]
+mod test {
+    use include_postgres_sql::{ include_sql, impl_sql };
+    use futures_util::{pin_mut, TryStreamExt};
+    use tokio_postgres::{Config, NoTls};
+
+    include_sql!("tests/sql/query_settings.sql");
+
+    #[tokio::test]
+    async fn impl_method_with_settings() -> Result<(),tokio_postgres::Error> {
+        let (mut db, conn) = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls).await?;
+
+        tokio::spawn(async move {
+            if let Err(e) = conn.await {
+                eprintln!("connection error: {}", e);
+            }
+        });
+
+        // The value that the application set for the session outlives the transactions
+        db.batch_execute("SET work_mem = '7MB'").await?;
+
+        // `db.get_work_mem(..)` does not compile as the client might be shared by tasks which queries would
+        // be executed with the settings of the call
+        let tr = db.transaction().await?;
+        tr.get_work_mem(|row| {
+            let work_mem : &str = row.try_get("work_mem")?;
+            assert_eq!(work_mem, "12MB");
+            Ok(())
+        }).await?;
+        let work_mem : String = tr.query_one("SHOW work_mem", &[]).await?.try_get(0)?;
+        assert_eq!(work_mem, "12MB");
+        tr.rollback().await?;
+
+        let tr = db.transaction().await?;
+        let rows = tr.stream_work_mem().await?;
+        pin_mut!(rows);
+        let row = rows.try_next().await?.expect("one row");
+        let work_mem : &str = row.try_get("work_mem")?;
+        assert_eq!(work_mem, "12MB");
+        tr.rollback().await?;
+
+        let work_mem : String = db.query_one("SHOW work_mem", &[]).await?.try_get(0)?;
+        assert_eq!(work_mem, "7MB");
+
+        Ok(())
+    }
+}
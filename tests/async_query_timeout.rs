@@ -0,0 +1,41 @@
+#[cfg(feature = "tokio")]
+mod test {
+    use include_postgres_sql::{ include_sql, impl_sql, timeout, TimeoutError };
+    use tokio_postgres::{Config, NoTls};
+    use std::time::{Duration, Instant};
+
+    include_sql!("tests/sql/query_cancel.sql");
+
+    #[tokio::test]
+    async fn impl_method_with_timeout() -> Result<(),TimeoutError> {
+        let (mut db, conn) = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls).await?;
+
+        tokio::spawn(async move {
+            if let Err(e) = conn.await {
+                eprintln!("connection error: {}", e);
+            }
+        });
+
+        let mut num_rows = 0;
+        timeout(Duration::from_secs(5), &mut db, NoTls, |db| db.sleep(0.01, |_| {
+            num_rows += 1;
+            Ok(())
+        })).await?;
+        assert_eq!(num_rows, 1);
+
+        let start = Instant::now();
+        let res = timeout(Duration::from_millis(200), &mut db, NoTls, |db| db.sleep(10.0, |_| Ok(()))).await;
+        assert!(matches!(res, Err(TimeoutError::Elapsed)));
+
+        let row = db.query_one("SELECT 1", &[]).await?;
+        let one : i32 = row.try_get(0)?;
+        assert_eq!(one, 1);
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        Ok(())
+    }
+}
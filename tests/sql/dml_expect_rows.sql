@@ -0,0 +1,45 @@
+-- name: new_genre->
+-- Creates new genre
+-- # Parameters
+-- param: name: &str - genre name
+INSERT INTO genre (genre_id, name)
+SELECT Coalesce(Max(genre_id),0) + 1, :name
+  FROM genre
+RETURNING genre_id
+
+-- name: get_genre->
+-- Retrieves genre name and version
+-- # Parameters
+-- param: id: i32 - genre ID
+SELECT name, xmin::text AS version FROM genre WHERE genre_id = :id
+
+-- name: rename_genre!
+-- Sets the name of the genre
+-- expect_rows: 1
+-- # Parameters
+-- param: id: i32 - genre ID
+-- param: name: &str - genre name
+UPDATE genre SET name = :name WHERE genre_id = :id
+
+-- name: rename_genres!
+-- Sets the name of the genres
+-- expect_rows: 1..=2
+-- # Parameters
+-- param: ids: i32 - genre IDs
+-- param: name: &str - genre name
+UPDATE genre SET name = :name WHERE genre_id IN (:ids)
+
+-- name: update_genre!
+-- Sets the name of the genre unless it was updated since it was read
+-- optimistic lock
+-- # Parameters
+-- param: id: i32 - genre ID
+-- param: version: &str - genre version
+-- param: name: &str - genre name
+UPDATE genre SET name = :name WHERE genre_id = :id AND xmin::text = :version
+
+-- name: delete_genre!
+-- Deletes genre
+-- # Parameters
+-- param: id: i32 - genre ID
+DELETE FROM genre WHERE genre_id = :id
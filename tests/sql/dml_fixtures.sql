@@ -0,0 +1,40 @@
+-- name: add_fixture_artist!
+-- Adds the artist of the fixture album
+-- fixture: fixture_artist
+INSERT INTO artist (artist_id, name) VALUES (10001, 'Fixture Artist')
+
+-- name: add_fixture_album!
+-- Adds the fixture album
+-- fixture: fixture_album
+-- depends_on: fixture_artist
+INSERT INTO album (album_id, title, artist_id) VALUES (10001, 'Fixture Album', 10001)
+
+-- name: add_fixture_genre!
+-- Adds the genre of the fixture tracks
+-- fixture: fixture_genre
+INSERT INTO genre (genre_id, name) VALUES (10001, 'Fixture Genre')
+
+-- name: add_first_fixture_track!
+-- Adds tracks to the fixture album
+-- fixture: fixture_tracks
+-- depends_on: fixture_album, fixture_genre
+INSERT INTO track (track_id, name, album_id, media_type_id, genre_id, milliseconds, unit_price)
+VALUES (10001, 'First Fixture Track', 10001, 1, 10001, 180000, 0.99)
+
+-- name: add_second_fixture_track!
+-- Adds tracks to the fixture album
+-- fixture: fixture_tracks
+INSERT INTO track (track_id, name, album_id, media_type_id, genre_id, milliseconds, unit_price)
+VALUES (10002, 'Second Fixture Track', 10001, 1, 10001, 240000, 0.99)
+
+-- name: count_album_tracks->
+-- Counts tracks of the album
+-- param: album_id: i32 - album ID
+SELECT Count(*) AS num_tracks FROM track WHERE album_id = :album_id
+
+-- name: count_fixture_rows->
+-- Counts rows that fixtures add
+SELECT (SELECT Count(*) FROM artist WHERE artist_id = 10001)
+     + (SELECT Count(*) FROM album WHERE album_id = 10001)
+     + (SELECT Count(*) FROM genre WHERE genre_id = 10001)
+     + (SELECT Count(*) FROM track WHERE track_id IN (10001, 10002)) AS num_rows
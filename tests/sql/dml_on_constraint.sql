@@ -0,0 +1,14 @@
+-- name: add_album!
+-- Adds a new album
+-- on_constraint: pk_album => DuplicateAlbum
+-- on_constraint: fk_album_artist_id => UnknownArtist
+-- param: album_id: i32 - album ID
+-- param: title: &str - album title
+-- param: artist_id: i32 - artist ID
+INSERT INTO album (album_id, title, artist_id) VALUES (:album_id, :title, :artist_id)
+
+-- name: move_album!
+-- Changes the artist of the album
+-- param: album_id: i32 - album ID
+-- param: artist_id: i32 - artist ID
+UPDATE album SET artist_id = :artist_id WHERE album_id = :album_id
@@ -0,0 +1,21 @@
+-- name: new_genre->
+-- Creates new genre
+-- # Parameters
+-- param: name: &str - genre name
+INSERT INTO genre (genre_id, name)
+SELECT Coalesce(Max(genre_id),0) + 1, :name
+  FROM genre
+RETURNING genre_id
+
+-- name: delete_genre!
+-- Deletes genre
+-- # Parameters
+-- param: id: i32 - genre ID
+DELETE FROM genre WHERE genre_id = :id
+
+-- name: fail_serialization!
+-- Fails the current transaction as if it could not be serialized
+DO $$
+BEGIN
+  RAISE EXCEPTION 'could not serialize access' USING ERRCODE = 'serialization_failure';
+END$$
@@ -0,0 +1,14 @@
+-- name: new_genre->
+-- Creates new genre
+-- # Parameters
+-- param: name: &str - genre name
+INSERT INTO genre (genre_id, name)
+SELECT Coalesce(Max(genre_id),0) + 1, :name
+  FROM genre
+RETURNING genre_id
+
+-- name: count_genres->
+-- Counts genres with the given name
+-- # Parameters
+-- param: name: &str - genre name
+SELECT Count(*) AS num_genres FROM genre WHERE name = :name
@@ -0,0 +1,20 @@
+-- name: new_genre->
+-- Creates new genre
+-- # Parameters
+-- param: name: &str - genre name
+INSERT INTO genre (genre_id, name)
+SELECT Coalesce(Max(genre_id),0) + 1, :name
+  FROM genre
+RETURNING genre_id
+
+-- name: count_genres->
+-- Counts genres with the specified name
+-- # Parameters
+-- param: name: &str - genre name
+SELECT Count(*) AS num_genres FROM genre WHERE name = :name
+
+-- name: delete_genre!
+-- Deletes genre
+-- # Parameters
+-- param: id: i32 - genre ID
+DELETE FROM genre WHERE genre_id = :id
@@ -0,0 +1,9 @@
+-- name: run_job->
+-- Returns the number of the job advisory locks held by the current session
+-- advisory_lock: 4201
+SELECT Count(*) AS num_locks FROM pg_locks WHERE locktype = 'advisory' AND objid = 4201 AND pid = pg_backend_pid()
+
+-- name: try_run_job->
+-- Returns the number of the job advisory locks held by the current session
+-- try_advisory_lock: 4202
+SELECT Count(*) AS num_locks FROM pg_locks WHERE locktype = 'advisory' AND objid = 4202 AND pid = pg_backend_pid()
@@ -0,0 +1,14 @@
+-- name: get_genres?
+-- Retrieves genres with IDs up to the given one
+-- set: work_mem = '12MB'
+-- param: max_id: i32 - maximum genre ID
+SELECT genre_id, name FROM genre WHERE genre_id <= :max_id ORDER BY genre_id
+
+-- name: get_genre_list%
+-- Retrieves genres with IDs up to the given one
+-- param: max_id: i32 - maximum genre ID
+SELECT genre_id, name FROM genre WHERE genre_id <= :max_id ORDER BY genre_id
+
+-- name: get_work_mem->
+-- Retrieves the effective `work_mem`
+SELECT current_setting('work_mem') AS work_mem
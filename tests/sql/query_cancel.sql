@@ -0,0 +1,5 @@
+-- name: sleep?
+-- Keeps the connection busy
+-- # Parameters
+-- param: secs: f64 - number of seconds to sleep
+SELECT pg_sleep(:secs)
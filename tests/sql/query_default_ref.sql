@@ -0,0 +1,7 @@
+-- name: get_genre_id->
+-- Retrieves the ID of the genre with a default for a reference parameter
+-- # Parameters
+-- param: name: &str = 'Rock' - genre name
+SELECT genre_id
+  FROM genre
+ WHERE name = :name
@@ -0,0 +1,50 @@
+-- name: copy_album!
+-- Inserts a copy of the album with the given ID
+-- param: album_id: i32 - ID of the copy
+INSERT INTO album (album_id, title, artist_id) VALUES (:album_id, 'Copy', 1)
+
+-- name: move_album!
+-- Changes the artist of the album
+-- param: album_id: i32 - album ID
+-- param: artist_id: i32 - artist ID
+UPDATE album SET artist_id = :artist_id WHERE album_id = :album_id
+
+-- name: rename_album!
+-- Changes the title of the album
+-- param: album_id: i32 - album ID
+-- param: title: Option<String> - album title
+UPDATE album SET title = :title WHERE album_id = :album_id
+
+-- name: fail_check!
+-- Fails as if a check constraint were violated
+DO $$
+BEGIN
+  RAISE EXCEPTION 'new row violates check constraint' USING ERRCODE = 'check_violation', CONSTRAINT = 'album_title_check', TABLE = 'album';
+END$$
+
+-- name: fail_serialization!
+-- Fails the current transaction as if it could not be serialized
+DO $$
+BEGIN
+  RAISE EXCEPTION 'could not serialize access' USING ERRCODE = 'serialization_failure';
+END$$
+
+-- name: fail_deadlock!
+-- Fails as if a deadlock were detected
+DO $$
+BEGIN
+  RAISE EXCEPTION 'deadlock detected' USING ERRCODE = 'deadlock_detected';
+END$$
+
+-- name: sleep?
+-- Sleeps longer than the statement is allowed to run
+-- set: statement_timeout = '10ms'
+SELECT pg_sleep(1)
+
+-- name: divide_by_zero->
+-- Fails the call
+SELECT 1 / 0 AS result
+
+-- name: terminate_session!
+-- Terminates the current session
+SELECT pg_terminate_backend(pg_backend_pid())
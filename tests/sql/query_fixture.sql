@@ -0,0 +1,4 @@
+-- name: get_fixture_genres?
+-- Selects genres as if they were a part of a fixture
+-- fixture: fixture_genre
+SELECT genre_id, name FROM genre WHERE genre_id >= 10000
@@ -0,0 +1,6 @@
+-- name: lock_genre->
+-- Locks the genre until the end of the transaction
+-- requires: transaction
+-- # Parameters
+-- param: id: i32 - genre ID
+SELECT name FROM genre WHERE genre_id = :id FOR UPDATE
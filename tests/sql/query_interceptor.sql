@@ -0,0 +1,16 @@
+-- name: get_genre_name->
+-- Returns the name of the genre
+-- param: genre_id: i32 - genre ID
+SELECT name FROM genre WHERE genre_id = :genre_id
+
+-- name: get_genres?
+-- Retrieves genres with the given IDs
+-- param: ids: i32 - genre IDs
+SELECT genre_id, name
+  FROM genre
+ WHERE genre_id IN (:ids)
+ ORDER BY genre_id
+
+-- name: get_genre_names^
+-- Retrieves names of all genres
+SELECT name FROM genre ORDER BY genre_id
@@ -0,0 +1,13 @@
+-- name: get_snapshot_mode->
+-- Returns the characteristics of the transaction the statement is executed in
+-- isolation: repeatable read
+-- read only
+SELECT current_setting('transaction_isolation') AS isolation
+     , current_setting('transaction_read_only') AS read_only
+
+-- name: sleep_in_snapshot?
+-- Sleeps within a repeatable read transaction
+-- isolation: repeatable read
+-- # Parameters
+-- param: secs: f64 - sleep duration
+SELECT pg_sleep(:secs)
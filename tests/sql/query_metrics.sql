@@ -0,0 +1,20 @@
+-- name: get_genres%
+-- Retrieves genres with the given IDs
+-- param: ids: i32 - genre IDs
+SELECT genre_id, name
+  FROM genre
+ WHERE genre_id IN (:ids)
+ ORDER BY genre_id
+
+-- name: delete_genre!
+-- Deletes the genre
+-- param: genre_id: i32 - genre ID
+DELETE FROM genre WHERE genre_id = :genre_id
+
+-- name: divide_by_zero->
+-- Fails the call
+SELECT 1 / 0 AS result
+
+-- name: get_genre_names^
+-- Retrieves names of all genres
+SELECT name FROM genre ORDER BY genre_id
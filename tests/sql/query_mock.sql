@@ -0,0 +1,24 @@
+-- name: get_genres?
+-- Retrieves genres with IDs up to the given one
+-- param: max_id: i32 - maximum genre ID
+SELECT genre_id, name FROM genre WHERE genre_id <= :max_id ORDER BY genre_id
+
+-- name: get_genre_list%
+-- Retrieves genres with IDs in the given list
+-- param: genre_ids: i32 - genre IDs
+SELECT genre_id, name FROM genre WHERE genre_id IN (:genre_ids) ORDER BY genre_id
+
+-- name: new_genre!
+-- Creates new genre
+-- param: id: i32 - new genre ID
+-- param: name: &str - genre name
+INSERT INTO genre (genre_id, name) VALUES (:id, :name)
+
+-- name: get_work_mem->
+-- Retrieves the effective `work_mem`
+SELECT current_setting('work_mem') AS work_mem
+
+-- name: get_genre_names^
+-- Retrieves names of genres with IDs up to the given one
+-- param: max_id: i32 - maximum genre ID
+SELECT name FROM genre WHERE genre_id <= :max_id ORDER BY genre_id
@@ -0,0 +1,28 @@
+-- name: get_genres?
+-- Retrieves the first genres ordered by their ID
+-- # Parameters
+-- param: page_size: i64 = 3 - number of genres to return
+SELECT name
+  FROM genre
+ ORDER BY genre_id
+ LIMIT :page_size
+
+-- name: count_tracks->
+-- Counts tracks of the specified genres that are stored in the specified media type
+-- # Parameters
+-- param: genre_ids: i32 - genre IDs
+-- param: media_type: String = 'MPEG audio file' - media type name
+SELECT Count(*) AS num_tracks
+  FROM track t
+  JOIN media_type m ON m.media_type_id = t.media_type_id
+ WHERE t.genre_id IN (:genre_ids)
+   AND m.name = :media_type
+
+-- name: get_genre_names?
+-- Retrieves the first genres with a default that cannot be parsed into the parameter type
+-- # Parameters
+-- param: page_size: i64 = all - number of genres to return
+SELECT name
+  FROM genre
+ ORDER BY genre_id
+ LIMIT :page_size
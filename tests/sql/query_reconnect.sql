@@ -0,0 +1,17 @@
+-- name: get_backend_pid->
+-- Returns the process ID of the server process that serves the session
+-- idempotent
+SELECT pg_backend_pid() AS pid
+
+-- name: terminate_session!
+-- Terminates the session if it is served by the specified server process
+-- # Parameters
+-- param: pid: i32 - server process ID
+SELECT pg_terminate_backend(pg_backend_pid()) WHERE pg_backend_pid() = :pid
+
+-- name: terminate_session_idempotent!
+-- Terminates the session if it is served by the specified server process
+-- idempotent
+-- # Parameters
+-- param: pid: i32 - server process ID
+SELECT pg_terminate_backend(pg_backend_pid()) WHERE pg_backend_pid() = :pid
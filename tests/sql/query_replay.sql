@@ -0,0 +1,41 @@
+-- name: get_top_sales?
+-- Retrieves artists with the most sales in a given city
+-- # Parameters
+-- param: cities: &str - Cities where trackas were sold to
+-- param: min_sold: i64 - mininum number of track sales
+SELECT artist.name AS artist_name, track.name AS track_name, Sum(invoice_line.quantity) AS num_sold
+  FROM invoice_line
+  JOIN invoice  ON invoice.invoice_id = invoice_line.invoice_id
+  JOIN customer ON customer.customer_id = invoice.customer_id
+  JOIN track    ON track.track_id = invoice_line.track_id
+  JOIN album    ON album.album_id = track.album_id
+  JOIN artist   ON artist.artist_id = album.artist_id  
+ WHERE customer.city IN (:cities)
+ GROUP BY artist.name, track.name
+HAVING Sum(invoice_line.quantity) >= :min_sold
+ ORDER BY 1, 3
+
+-- name: get_genres%
+-- Retrieves genres with IDs up to the given one
+-- param: max_id: i32 - maximum genre ID
+SELECT genre_id, name FROM genre WHERE genre_id <= :max_id ORDER BY genre_id
+
+-- name: get_genre_names^
+-- Retrieves names of genres with IDs up to the given one
+-- param: max_id: i32 - maximum genre ID
+SELECT name FROM genre WHERE genre_id <= :max_id ORDER BY genre_id
+
+-- name: get_genre_name->
+-- Retrieves the name of the genre
+-- param: genre_id: i32 - genre ID
+SELECT name FROM genre WHERE genre_id = :genre_id
+
+-- name: touch_genre!
+-- Updates the genre without changing it
+-- param: genre_id: i32 - genre ID
+UPDATE genre SET name = name WHERE genre_id = :genre_id
+
+-- name: get_share->
+-- Divides 100 by the number of parts
+-- param: num_parts: i32 - number of parts
+SELECT 100 / :num_parts AS share
@@ -0,0 +1,19 @@
+-- name: get_connection_name->
+-- Returns the name of the connection that executed the statement
+SELECT current_setting('application_name') AS name
+
+-- name: get_replica_connection_name->
+-- Returns the name of the connection that executed the statement
+-- replica: ok
+SELECT current_setting('application_name') AS name
+
+-- name: get_connection_names%
+-- Returns the name of the connection that executed the statement
+-- # Parameters
+-- param: ids: i32 - genre IDs
+SELECT current_setting('application_name') AS name FROM genre WHERE genre_id IN (:ids)
+
+-- name: get_primary_connection_names?
+-- Returns the name of the connection that executed the statement
+-- replica: no
+SELECT current_setting('application_name') AS name
@@ -0,0 +1,9 @@
+-- name: get_work_mem?
+-- Retrieves the effective `work_mem`
+-- set: work_mem = '12MB'
+SELECT current_setting('work_mem') AS work_mem
+
+-- name: stream_work_mem^
+-- Streams the effective `work_mem`
+-- set: work_mem = '12MB'
+SELECT current_setting('work_mem') AS work_mem
@@ -0,0 +1,11 @@
+-- name: count_genres->
+-- Counts the genres with the given IDs after the delay
+-- param: delay: f64 - delay in seconds
+-- param: ids: i32 - genre IDs
+SELECT Count(*) AS num_genres
+  FROM genre, pg_sleep(:delay)
+ WHERE genre_id IN (:ids)
+
+-- name: sleep_in_block!
+-- Sleeps in an anonymous code block, which cannot be explained
+DO $$ BEGIN PERFORM pg_sleep(0.2); END $$
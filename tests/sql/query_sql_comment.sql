@@ -0,0 +1,12 @@
+-- name: get_own_query->
+-- Returns the text of the current query of this session
+SELECT query FROM pg_stat_activity WHERE pid = pg_backend_pid()
+
+-- name: get_own_query_with_genres->
+-- Returns the text of the current query of this session
+-- param: ids: i32 - genre IDs
+SELECT query, Count(*) AS num_genres
+  FROM pg_stat_activity, genre
+ WHERE pid = pg_backend_pid()
+   AND genre_id IN (:ids)
+ GROUP BY query
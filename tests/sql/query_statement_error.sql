@@ -0,0 +1,12 @@
+-- name: get_missing_column->
+-- Fails as the selected column does not exist
+-- param: genre_id: i32 - genre ID
+SELECT missing_column FROM genre WHERE genre_id = :genre_id
+
+-- name: count_genres->
+-- Counts the genres with the given IDs or fails when the divisor is zero
+-- param: divisor: i64 - divisor
+-- param: ids: i32 - genre IDs
+SELECT Count(*) / :divisor AS num_genres
+  FROM genre
+ WHERE genre_id IN (:ids)
@@ -0,0 +1,20 @@
+-- name: get_genres?
+-- Retrieves genres with the given IDs
+-- param: ids: i32 - genre IDs
+SELECT genre_id, name
+  FROM genre
+ WHERE genre_id IN (:ids)
+ ORDER BY genre_id
+
+-- name: count_genre_tracks->
+-- Returns the number of tracks of the given genre
+-- param: genre_id: i32 - genre ID
+SELECT Count(*) AS num_tracks FROM track WHERE genre_id = :genre_id
+
+-- name: divide_by_zero->
+-- Fails the call
+SELECT 1 / 0 AS result
+
+-- name: get_genre_names^
+-- Retrieves names of all genres
+SELECT name FROM genre ORDER BY genre_id
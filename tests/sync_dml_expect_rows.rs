@@ -0,0 +1,66 @@
+#[cfg(not(feature = "tokio"))]
+mod test {
+    use include_postgres_sql::{include_sql, CheckFailure, ErrorClass, RetryReason};
+    use postgres::{Config, NoTls};
+
+    macro_rules! impl_sql {
+        ( $($tt:tt)+ ) => {
+            include_postgres_sql::impl_sql!{ #[options(statement_error)] $($tt)+ }
+        };
+    }
+
+    include_sql!("tests/sql/dml_expect_rows.sql");
+
+    #[test]
+    fn impl_method_with_expected_rows() -> Result<(), Box<dyn std::error::Error>> {
+        let mut db = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls)?;
+
+        let genre_id : i32 = db.new_genre("Expected")?.try_get("genre_id")?;
+
+        let num_rows = db.rename_genre(genre_id, "Expected Rows")?;
+        assert_eq!(num_rows, 1);
+
+        let err = db.rename_genre(-1, "Missing").expect_err("no rows are updated");
+        assert_eq!(err.check_failure(), Some(&CheckFailure::RowCount { expected: 1..=1, actual: 0 }));
+        assert!(err.to_string().ends_with("::rename_genre failed: affected 0 rows, expected 1"));
+
+        let num_rows = db.rename_genres(&[genre_id, -1], "Expected Rows")?;
+        assert_eq!(num_rows, 1);
+
+        let err = db.rename_genres(&[1, 2, 3], "Oops").expect_err("too many rows are updated");
+        assert_eq!(err.check_failure(), Some(&CheckFailure::RowCount { expected: 1..=2, actual: 3 }));
+        // the update was rolled back
+        let name : String = db.get_genre(1)?.try_get("name")?;
+        assert_eq!(name, "Rock");
+
+        let mut tr = db.transaction()?;
+        let err = tr.rename_genre(-1, "Missing").expect_err("no rows are updated");
+        assert_eq!(err.check_failure(), Some(&CheckFailure::RowCount { expected: 1..=1, actual: 0 }));
+        // the check failure does not abort the transaction
+        let name : String = tr.get_genre(1)?.try_get("name")?;
+        assert_eq!(name, "Rock");
+        tr.rollback()?;
+
+        let row = db.get_genre(genre_id)?;
+        let version : &str = row.try_get("version")?;
+        let num_rows = db.update_genre(genre_id, version, "Optimistic")?;
+        assert_eq!(num_rows, 1);
+
+        let err = db.update_genre(genre_id, version, "Lost Update").expect_err("genre was updated");
+        assert_eq!(err.check_failure(), Some(&CheckFailure::Conflict));
+        assert_eq!(err.code(), None);
+        assert_eq!(ErrorClass::of(&err), ErrorClass::Conflict);
+        assert_eq!(RetryReason::of(&err), Some(RetryReason::Conflict));
+        let name : String = db.get_genre(genre_id)?.try_get("name")?;
+        assert_eq!(name, "Optimistic");
+
+        let num_deleted = db.delete_genre(genre_id)?;
+        assert_eq!(num_deleted, 1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+#[cfg(not(feature = "tokio"))]
+mod test {
+    use include_postgres_sql::{include_sql, test_transaction};
+    use postgres::{Config, Error, NoTls};
+
+    macro_rules! impl_sql {
+        ( $($tt:tt)+ ) => {
+            include_postgres_sql::impl_sql!{ #[options(fixtures)] $($tt)+ }
+        };
+    }
+
+    include_sql!("tests/sql/dml_fixtures.sql");
+
+    fn test_database() -> Config {
+        let mut config = Config::new();
+        config.host("localhost").user("postgres").dbname("chinook");
+        config
+    }
+
+    #[test]
+    fn apply_fixtures_with_dependencies() -> Result<(), Error> {
+        test_transaction(&test_database(), NoTls, |tx| {
+            // fixture_album is applied once - as a dependency of fixture_tracks
+            tx.apply_fixtures(&["fixture_tracks", "fixture_album"])?;
+            let row = tx.count_album_tracks(10001)?;
+            assert_eq!(row.try_get::<_, i64>("num_tracks")?, 2);
+            let row = tx.count_fixture_rows()?;
+            assert_eq!(row.try_get::<_, i64>("num_rows")?, 5);
+            Ok::<_, Error>(())
+        })?;
+
+        test_transaction(&test_database(), NoTls, |tx| {
+            tx.apply_fixtures(&["fixture_genre"])?;
+            let row = tx.count_fixture_rows()?;
+            assert_eq!(row.try_get::<_, i64>("num_rows")?, 1);
+            Ok::<_, Error>(())
+        })?;
+
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "DmlFixturesSql has no fixture fixture_playlist")]
+    fn panic_on_unknown_fixture() {
+        let _ = test_transaction(&test_database(), NoTls, |tx| tx.apply_fixtures(&["fixture_artist", "fixture_playlist"]));
+    }
+}
@@ -0,0 +1,55 @@
+#[cfg(not(feature = "tokio"))]
+mod test {
+    use include_postgres_sql::{include_sql, StatementError};
+    use postgres::{Config, NoTls, Error, error::SqlState};
+
+    macro_rules! impl_sql {
+        ( $($tt:tt)+ ) => {
+            include_postgres_sql::impl_sql!{ #[options(statement_error)] $($tt)+ }
+        };
+    }
+
+    include_sql!("tests/sql/dml_on_constraint.sql");
+
+    #[derive(Debug, PartialEq)]
+    enum AlbumError {
+        DuplicateAlbum,
+        UnknownArtist,
+        Db(Option<SqlState>),
+    }
+
+    impl From<StatementError> for AlbumError {
+        fn from(err: StatementError) -> Self {
+            match err.violation {
+                Some("DuplicateAlbum") => Self::DuplicateAlbum,
+                Some("UnknownArtist") => Self::UnknownArtist,
+                _ => Self::Db(err.code().cloned()),
+            }
+        }
+    }
+
+    #[test]
+    fn map_constraint_violations() -> Result<(), Error> {
+        let mut db = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls)?
+        ;
+
+        let err = db.add_album(1, "Copy", 1).unwrap_err();
+        assert_eq!(err.violation, Some("DuplicateAlbum"));
+        assert!(err.to_string().starts_with("DmlOnConstraintSql::add_album failed (DuplicateAlbum): ERROR: duplicate key value"));
+        assert_eq!(AlbumError::from(err), AlbumError::DuplicateAlbum);
+
+        let err = db.add_album(-1, "Copy", -1).unwrap_err();
+        assert_eq!(err.violation, Some("UnknownArtist"));
+        assert_eq!(AlbumError::from(err), AlbumError::UnknownArtist);
+
+        let err = db.move_album(1, -1).unwrap_err();
+        assert_eq!(err.violation, None);
+        assert_eq!(AlbumError::from(err), AlbumError::Db(Some(SqlState::FOREIGN_KEY_VIOLATION)));
+
+        Ok(())
+    }
+}
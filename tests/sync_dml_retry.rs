@@ -0,0 +1,109 @@
+#[cfg(not(feature = "tokio"))]
+mod test {
+    use include_postgres_sql::{impl_sql, include_sql, with_transaction, Retry, RetryReason};
+    use postgres::{Config, NoTls, IsolationLevel, error::SqlState};
+    use std::{sync::{Arc, Mutex}, time::Duration};
+
+    include_sql!("tests/sql/dml_retry.sql");
+
+    #[test]
+    fn retry_transaction() -> Result<(), postgres::Error> {
+        let mut db = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls)?;
+
+        let retries = Arc::new(Mutex::new(Vec::new()));
+        let retry = {
+            let retries = retries.clone();
+            Retry::new(IsolationLevel::Serializable)
+                .max_attempts(3)
+                .backoff(Duration::from_millis(1), Duration::from_millis(2))
+                .on_retry(move |reason, attempt, _| retries.lock().unwrap().push((reason, attempt)))
+        };
+
+        let mut attempts = 0;
+        let genre_id : i32 = with_transaction(&mut db, &retry, |tx| {
+            attempts += 1;
+            let row = tx.new_genre("Retried")?;
+            if attempts == 1 {
+                tx.fail_serialization()?;
+            }
+            row.try_get("genre_id")
+        })?;
+        assert_eq!(attempts, 2);
+        assert_eq!(*retries.lock().unwrap(), [(RetryReason::SerializationFailure, 1)]);
+
+        let num_deleted = db.delete_genre(genre_id)?;
+        assert_eq!(num_deleted, 1);
+
+        retries.lock().unwrap().clear();
+        attempts = 0;
+        let res = with_transaction(&mut db, &retry, |tx| {
+            attempts += 1;
+            tx.new_genre("Never Committed")?;
+            tx.fail_serialization()
+        });
+        assert_eq!(attempts, 3);
+        assert_eq!(res.unwrap_err().code(), Some(&SqlState::T_R_SERIALIZATION_FAILURE));
+        assert_eq!(retries.lock().unwrap().len(), 2);
+
+        Ok(())
+    }
+
+    /// Application error that does not implement `std::error::Error`.
+    #[derive(Debug)]
+    enum AppError {
+        Db(postgres::Error),
+    }
+
+    impl From<postgres::Error> for AppError {
+        fn from(err: postgres::Error) -> Self {
+            Self::Db(err)
+        }
+    }
+
+    #[test]
+    fn retry_application_errors() -> Result<(), Box<dyn std::error::Error>> {
+        let mut db = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls)?;
+
+        let retry = Retry::new(IsolationLevel::Serializable)
+            .max_attempts(2)
+            .backoff(Duration::from_millis(1), Duration::from_millis(2));
+
+        let mut attempts = 0;
+        let res : Result<(), Box<dyn std::error::Error>> = with_transaction(&mut db, &retry, |tx| {
+            attempts += 1;
+            tx.fail_serialization()?;
+            Ok(())
+        });
+        assert!(res.is_err());
+        assert_eq!(attempts, 2);
+
+        attempts = 0;
+        let res : Result<(), AppError> = with_transaction(&mut db, &retry, |tx| {
+            attempts += 1;
+            tx.fail_serialization()?;
+            Ok(())
+        });
+        assert!(res.is_err());
+        assert_eq!(attempts, 1);
+
+        let retry = retry.inspect(|AppError::Db(err): &AppError| RetryReason::of(err));
+        attempts = 0;
+        let res = with_transaction(&mut db, &retry, |tx| {
+            attempts += 1;
+            tx.fail_serialization()?;
+            Ok(())
+        });
+        assert!(matches!(res, Err(AppError::Db(err)) if err.code() == Some(&SqlState::T_R_SERIALIZATION_FAILURE)));
+        assert_eq!(attempts, 2);
+
+        Ok(())
+    }
+}
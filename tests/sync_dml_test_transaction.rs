@@ -0,0 +1,29 @@
+#[cfg(not(feature = "tokio"))]
+mod test {
+    use include_postgres_sql::{impl_sql, include_sql, test_transaction};
+    use postgres::{Config, Error, NoTls};
+
+    include_sql!("tests/sql/dml_test_transaction.sql");
+
+    fn test_database() -> Config {
+        let mut config = Config::new();
+        config.host("localhost").user("postgres").dbname("chinook");
+        config
+    }
+
+    #[test]
+    fn roll_back_test_changes() -> Result<(), Error> {
+        let new_id : i32 = test_transaction(&test_database(), NoTls, |tx| {
+            let row = tx.new_genre("Test Transaction Genre")?;
+            let row_count = tx.count_genres("Test Transaction Genre")?;
+            assert_eq!(row_count.try_get::<_, i64>("num_genres")?, 1);
+            row.try_get("genre_id")
+        })?;
+        assert!(new_id > 0);
+
+        let num_genres : i64 = test_transaction(&test_database(), NoTls, |tx| tx.count_genres("Test Transaction Genre")?.try_get("num_genres"))?;
+        assert_eq!(num_genres, 0);
+
+        Ok(())
+    }
+}
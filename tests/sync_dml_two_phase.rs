@@ -0,0 +1,75 @@
+/*!
+Two-phase commit needs the server to allow prepared transactions, i.e. `max_prepared_transactions` has to be
+set to a positive number in `postgresql.conf`. It is 0 by default, and then this test is skipped.
+*/
+#[cfg(not(feature = "tokio"))]
+mod test {
+    use include_postgres_sql::{impl_sql, include_sql, prepare_transaction, commit_prepared, rollback_prepared, prepared_transactions};
+    use postgres::{Client, Config, NoTls};
+
+    include_sql!("tests/sql/dml_two_phase.sql");
+
+    fn count_genres(db: &mut Client, name: &str) -> Result<i64, postgres::Error> {
+        db.count_genres(name)?.try_get("num_genres")
+    }
+
+    fn is_prepared(db: &mut Client, gid: &str) -> Result<bool, postgres::Error> {
+        Ok(prepared_transactions(db)?.iter().any(|tx| tx.gid == gid))
+    }
+
+    /// Tells whether the server allows transactions to be prepared for two-phase commit.
+    fn supports_two_phase(db: &mut Client) -> Result<bool, postgres::Error> {
+        let max_prepared : String = db.query_one("SHOW max_prepared_transactions", &[])?.try_get(0)?;
+        Ok(max_prepared.parse::<u32>().is_ok_and(|max| max > 0))
+    }
+
+    /// Rolls back transactions and deletes genres that a previously failed run left behind.
+    fn clean_up(db: &mut Client, gids: &[&str]) -> Result<(), postgres::Error> {
+        for tx in prepared_transactions(db)? {
+            if gids.contains(&tx.gid.as_str()) {
+                rollback_prepared(db, &tx.gid)?;
+            }
+        }
+        db.execute("DELETE FROM genre WHERE name = 'Two-Phase Sync'", &[])?;
+        Ok(())
+    }
+
+    #[test]
+    fn two_phase_commit() -> Result<(), postgres::Error> {
+        let mut db = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls)?;
+
+        if !supports_two_phase(&mut db)? {
+            eprintln!("skipped: max_prepared_transactions is 0");
+            return Ok(());
+        }
+        clean_up(&mut db, &["sync-two-phase-commit", "sync-two-phase-rollback"])?;
+
+        let gid = "sync-two-phase-commit";
+        let genre_id : i32 = prepare_transaction(&mut db, gid, |tx| {
+            tx.new_genre("Two-Phase Sync")?.try_get("genre_id")
+        })?;
+        assert!(is_prepared(&mut db, gid)?);
+        assert_eq!(count_genres(&mut db, "Two-Phase Sync")?, 0);
+
+        commit_prepared(&mut db, gid)?;
+        assert!(!is_prepared(&mut db, gid)?);
+        assert_eq!(count_genres(&mut db, "Two-Phase Sync")?, 1);
+
+        let num_deleted = db.delete_genre(genre_id)?;
+        assert_eq!(num_deleted, 1);
+
+        let gid = "sync-two-phase-rollback";
+        prepare_transaction(&mut db, gid, |tx| tx.new_genre("Two-Phase Sync"))?;
+        assert!(is_prepared(&mut db, gid)?);
+
+        rollback_prepared(&mut db, gid)?;
+        assert!(!is_prepared(&mut db, gid)?);
+        assert_eq!(count_genres(&mut db, "Two-Phase Sync")?, 0);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+#[cfg(not(feature = "tokio"))]
+mod test {
+    use include_postgres_sql::{include_sql, AdvisoryLock, CheckFailure};
+    use postgres::{Client, Config, NoTls};
+
+    macro_rules! impl_sql {
+        ( $($tt:tt)+ ) => {
+            include_postgres_sql::impl_sql!{ #[options(statement_error)] $($tt)+ }
+        };
+    }
+
+    include_sql!("tests/sql/query_advisory_lock.sql");
+
+    fn connect() -> Result<Client, postgres::Error> {
+        Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls)
+    }
+
+    fn num_locks(db: &mut Client, key: i64) -> Result<i64, postgres::Error> {
+        db.query_one(
+            "SELECT Count(*) FROM pg_locks WHERE locktype = 'advisory' AND objid = $1::BIGINT AND pid = pg_backend_pid()",
+            &[&key]
+        )?.try_get(0)
+    }
+
+    #[test]
+    fn impl_method_with_advisory_lock() -> Result<(), Box<dyn std::error::Error>> {
+        let mut db = connect()?;
+        let mut other = connect()?;
+
+        let num : i64 = db.run_job()?.try_get("num_locks")?;
+        assert_eq!(num, 1);
+        assert_eq!(num_locks(&mut db, 4201)?, 0);
+
+        let mut tr = db.transaction()?;
+        let num : i64 = tr.run_job()?.try_get("num_locks")?;
+        assert_eq!(num, 1);
+        let num : i64 = tr.query_one("SELECT Count(*) FROM pg_locks WHERE locktype = 'advisory' AND objid = 4201 AND pid = pg_backend_pid()", &[])?.try_get(0)?;
+        assert_eq!(num, 1);
+        tr.rollback()?;
+
+        let lock = AdvisoryLock::lock(&mut other, 4202)?;
+        let err = db.try_run_job().expect_err("lock is busy");
+        assert_eq!(err.check_failure(), Some(&CheckFailure::Busy { key: "4202" }));
+        assert_eq!(err.code(), None);
+        assert!(err.to_string().ends_with("::try_run_job failed: advisory lock 4202 is held by another session"));
+        {
+            let mut tr = db.transaction()?;
+            tr.try_run_job().expect_err("lock is busy");
+            // the busy lock does not abort the transaction
+            let num : i64 = tr.run_job()?.try_get("num_locks")?;
+            assert_eq!(num, 1);
+            tr.rollback()?;
+        }
+        assert!(AdvisoryLock::try_lock(&mut db, 4202)?.is_none());
+        drop(lock);
+
+        let num : i64 = db.try_run_job()?.try_get("num_locks")?;
+        assert_eq!(num, 1);
+
+        let mut lock = AdvisoryLock::try_lock(&mut db, 4202)?.expect("lock is available");
+        assert_eq!(lock.key(), 4202);
+        assert_eq!(num_locks(&mut lock, 4202)?, 1);
+        lock.unlock()?;
+        assert_eq!(num_locks(&mut db, 4202)?, 0);
+
+        Ok(())
+    }
+}
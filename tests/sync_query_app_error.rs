@@ -0,0 +1,85 @@
+#[cfg(not(feature = "tokio"))]
+mod test {
+    use std::convert::TryFrom;
+
+    use include_postgres_sql::include_sql;
+    use postgres::{Config, NoTls, Error, Row, error::SqlState};
+
+    macro_rules! impl_sql {
+        ( $($tt:tt)+ ) => {
+            include_postgres_sql::impl_sql!{ #[options(app_errors)] $($tt)+ }
+        };
+    }
+
+    include_sql!("tests/sql/query_app_error.sql");
+
+    #[derive(Debug)]
+    enum AppError {
+        Db(postgres::Error),
+        Unsupported(String),
+    }
+
+    impl From<postgres::Error> for AppError {
+        fn from(err: postgres::Error) -> Self {
+            Self::Db(err)
+        }
+    }
+
+    struct Genre {
+        name: String,
+    }
+
+    impl TryFrom<Row> for Genre {
+        type Error = AppError;
+
+        fn try_from(row: Row) -> Result<Self, Self::Error> {
+            let name : String = row.get("name");
+            if name == "Metal" {
+                return Err(AppError::Unsupported(name));
+            }
+            Ok(Self { name })
+        }
+    }
+
+    #[test]
+    fn fail_with_app_errors() -> Result<(), Error> {
+        let mut db = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls)?
+        ;
+
+        let mut names = Vec::new();
+        let res = db.get_genres(3, |row| {
+            let name : String = row.get("name");
+            if name == "Jazz" {
+                return Err(AppError::Unsupported(name));
+            }
+            names.push(name);
+            Ok(())
+        });
+        assert!(matches!(res, Err(AppError::Unsupported(name)) if name == "Jazz"));
+        assert_eq!(names, ["Rock"]);
+
+        let row = db.get_work_mem()?;
+        let work_mem : &str = row.try_get("work_mem")?;
+        assert_eq!(work_mem, "4MB");
+
+        let res : Result<Vec<Genre>, AppError> = db.get_genre_list(3);
+        assert!(matches!(res, Err(AppError::Unsupported(name)) if name == "Metal"));
+
+        let genres : Vec<Genre> = db.get_genre_list::<Genre, AppError>(2).unwrap();
+        assert_eq!(genres.iter().map(|genre| genre.name.as_str()).collect::<Vec<_>>(), ["Rock", "Jazz"]);
+
+        let res = db.get_genres(i32::MAX, |_| Ok::<_, AppError>(()));
+        assert!(res.is_ok());
+
+        let mut tx = db.transaction()?;
+        tx.batch_execute("SELECT 1/0").unwrap_err();
+        let res = tx.get_genres(1, |_| Ok::<_, AppError>(()));
+        assert!(matches!(res, Err(AppError::Db(err)) if err.code() == Some(&SqlState::IN_FAILED_SQL_TRANSACTION)));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+#[cfg(not(feature = "tokio"))]
+mod test {
+    use include_postgres_sql::{include_sql, impl_sql};
+    use postgres::{Config, NoTls, Error, error::SqlState};
+    use std::{thread, time::{Duration, Instant}};
+
+    include_sql!("tests/sql/query_cancel.sql");
+
+    #[test]
+    fn cancel_impl_method() -> Result<(), Error> {
+        let mut db = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls)?
+        ;
+
+        let token = db.cancel_token();
+        let canceller = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(200));
+            token.cancel_query(NoTls)
+        });
+
+        let start = Instant::now();
+        let res = db.sleep(10.0, |_| Ok(()));
+        assert!(start.elapsed() < Duration::from_secs(5));
+        let err = res.expect_err("canceled query");
+        assert_eq!(err.code(), Some(&SqlState::QUERY_CANCELED));
+        canceller.join().unwrap()?;
+
+        Ok(())
+    }
+}
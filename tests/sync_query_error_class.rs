@@ -0,0 +1,61 @@
+#[cfg(not(feature = "tokio"))]
+mod test {
+    use include_postgres_sql::{include_sql, impl_sql, ErrorClass};
+    use postgres::{Config, NoTls, Error, error::SqlState};
+
+    include_sql!("tests/sql/query_error_class.sql");
+
+    #[test]
+    fn classify_errors() -> Result<(), Error> {
+        let mut db = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls)?
+        ;
+
+        let err = db.copy_album(1).unwrap_err();
+        assert_eq!(ErrorClass::of(&err), ErrorClass::UniqueViolation {
+            constraint: Some("pk_album".to_string()),
+            table: Some("album".to_string()),
+        });
+
+        let err = db.move_album(1, -1).unwrap_err();
+        assert_eq!(ErrorClass::of(&err), ErrorClass::ForeignKeyViolation {
+            constraint: Some("fk_album_artist_id".to_string()),
+            table: Some("album".to_string()),
+        });
+
+        let err = db.rename_album(1, None).unwrap_err();
+        assert_eq!(ErrorClass::of(&err), ErrorClass::NotNullViolation {
+            table: Some("album".to_string()),
+            column: Some("title".to_string()),
+        });
+
+        let err = db.fail_check().unwrap_err();
+        assert_eq!(ErrorClass::of(&err), ErrorClass::CheckViolation {
+            constraint: Some("album_title_check".to_string()),
+            table: Some("album".to_string()),
+        });
+
+        let err = db.fail_serialization().unwrap_err();
+        assert_eq!(ErrorClass::of(&err), ErrorClass::SerializationFailure);
+
+        let err = db.fail_deadlock().unwrap_err();
+        assert_eq!(ErrorClass::of(&err), ErrorClass::Deadlock);
+
+        let err = db.sleep(|_| Ok(())).unwrap_err();
+        assert_eq!(ErrorClass::of(&err), ErrorClass::QueryCanceled);
+
+        let err = db.divide_by_zero().unwrap_err();
+        assert_eq!(ErrorClass::of(&err), ErrorClass::Other(Some(SqlState::DIVISION_BY_ZERO)));
+
+        let err = db.terminate_session().unwrap_err();
+        assert_eq!(ErrorClass::of(&err), ErrorClass::ConnectionClosed);
+
+        let err = db.divide_by_zero().unwrap_err();
+        assert_eq!(ErrorClass::of(&err), ErrorClass::ConnectionClosed);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,25 @@
+#[cfg(not(feature = "tokio"))]
+mod test {
+    use include_postgres_sql::{impl_sql, include_sql};
+    use postgres::{Config, NoTls};
+
+    include_sql!("tests/sql/query_for_update.sql");
+
+    #[test]
+    fn impl_method_requiring_transaction() -> Result<(), postgres::Error> {
+        let mut db = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls)?;
+
+        // `db.lock_genre(1)` does not compile as `lock_genre` requires a transaction
+        let mut tr = db.transaction()?;
+        let row = tr.lock_genre(1)?;
+        let name : &str = row.try_get("name")?;
+        assert_eq!(name, "Rock");
+        tr.rollback()?;
+
+        Ok(())
+    }
+}
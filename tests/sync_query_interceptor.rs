@@ -0,0 +1,116 @@
+#[cfg(not(any(feature = "tokio", feature = "sqlcommenter")))]
+mod test {
+    use include_postgres_sql::{include_sql, set_interceptor, clear_interceptor, CheckFailure, Call, Interceptor, Outcome, Rejection};
+    use postgres::{Config, NoTls, error::SqlState, fallible_iterator::FallibleIterator};
+    use std::{sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}}, time::Duration};
+
+    macro_rules! impl_sql {
+        ( $($tt:tt)+ ) => {
+            include_postgres_sql::impl_sql!{ #[options(statement_error, interceptor)] $($tt)+ }
+        };
+    }
+
+    include_sql!("tests/sql/query_interceptor.sql");
+
+    /// Records hook calls and rejects all calls when told to.
+    #[derive(Clone, Default)]
+    struct Recorder {
+        calls: Arc<Mutex<Vec<String>>>,
+        reject: Arc<AtomicBool>,
+    }
+
+    impl Recorder {
+        fn take(&self) -> Vec<String> {
+            std::mem::take(&mut *self.calls.lock().unwrap())
+        }
+    }
+
+    impl Interceptor for Recorder {
+        fn before(&self, call: &Call) -> Result<(), Rejection> {
+            self.calls.lock().unwrap().push(format!("before {}::{} {:?} {:?}", call.sql_trait, call.name, call.statement, call.parameters));
+            if self.reject.load(Ordering::Relaxed) {
+                Err(Rejection::new(SqlState::FEATURE_NOT_SUPPORTED, format!("{} isn't available", call.name)))
+            } else {
+                Ok(())
+            }
+        }
+
+        fn after(&self, call: &Call, outcome: Outcome, _elapsed: Duration) {
+            let outcome = match outcome {
+                Outcome::Succeeded(num_rows) => format!("{:?}", num_rows),
+                Outcome::Failed(err) => err.code().map_or("unknown", |code| code.code()).to_string(),
+                Outcome::CheckFailed(failure) => failure.to_string(),
+                Outcome::Aborted => "aborted".to_string(),
+            };
+            self.calls.lock().unwrap().push(format!("after {} {}", call.name, outcome));
+        }
+    }
+
+    #[test]
+    fn intercept_calls() -> Result<(), Box<dyn std::error::Error>> {
+        let mut db = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls)?
+        ;
+
+        let recorder = Recorder::default();
+        set_interceptor(recorder.clone());
+
+        let row = db.get_genre_name(1)?;
+        let name : &str = row.try_get("name")?;
+        assert_eq!(name, "Rock");
+        assert_eq!(recorder.take(), [
+            "before QueryInterceptorSql::get_genre_name \"SELECT name FROM genre WHERE genre_id = $1\" [(\"genre_id\", 1)]",
+            "after get_genre_name Some(1)",
+        ]);
+
+        let mut genres = Vec::new();
+        db.get_genres(&[1, 3, 5], |row| {
+            let genre_id : i32 = row.try_get("genre_id")?;
+            genres.push(genre_id);
+            Ok(())
+        })?;
+        assert_eq!(genres, [1, 3, 5]);
+        assert_eq!(recorder.take(), [
+            "before QueryInterceptorSql::get_genres \"SELECT genre_id, name\\n  FROM genre\\n WHERE genre_id IN ($1, $2, $3)\\n ORDER BY genre_id\" [(\"ids\", [1, 3, 5])]",
+            "after get_genres Some(3)",
+        ]);
+
+        let names : Vec<String> = db.get_genre_names()?.map(|row| row.try_get("name")).collect()?;
+        assert_eq!(names.len(), 25);
+        assert_eq!(recorder.take(), [
+            "before QueryInterceptorSql::get_genre_names \"SELECT name FROM genre ORDER BY genre_id\" []",
+            "after get_genre_names Some(25)",
+        ]);
+
+        let mut rows = db.get_genre_names()?;
+        assert!(rows.next()?.is_some());
+        assert_eq!(recorder.take().len(), 1);
+        drop(rows);
+        assert_eq!(recorder.take(), ["after get_genre_names Some(1)"]);
+
+        recorder.reject.store(true, Ordering::Relaxed);
+
+        let res = db.get_genre_name(1);
+        let err = res.unwrap_err();
+        let rejection = Rejection::new(SqlState::FEATURE_NOT_SUPPORTED, "get_genre_name isn't available");
+        assert_eq!(err.check_failure(), Some(&CheckFailure::Rejected(rejection)));
+        assert_eq!(err.code(), None);
+        assert_eq!(err.to_string(), "QueryInterceptorSql::get_genre_name failed: rejected by the interceptor: get_genre_name isn't available (0A000)");
+        assert_eq!(recorder.take()[1], "after get_genre_name rejected by the interceptor: get_genre_name isn't available (0A000)");
+
+        assert!(db.get_genre_names().is_err());
+        assert_eq!(recorder.take()[1], "after get_genre_names rejected by the interceptor: get_genre_names isn't available (0A000)");
+
+        clear_interceptor();
+
+        let row = db.get_genre_name(2)?;
+        let name : &str = row.try_get("name")?;
+        assert_eq!(name, "Jazz");
+        assert!(recorder.take().is_empty());
+
+        Ok(())
+    }
+}
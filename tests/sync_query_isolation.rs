@@ -0,0 +1,40 @@
+#[cfg(not(feature = "tokio"))]
+mod test {
+    use include_postgres_sql::{impl_sql, include_sql};
+    use postgres::{Config, IsolationLevel, NoTls, error::SqlState};
+
+    include_sql!("tests/sql/query_isolation.sql");
+
+    #[test]
+    fn impl_method_with_isolation() -> Result<(), postgres::Error> {
+        let mut db = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls)?;
+
+        let row = db.get_snapshot_mode()?;
+        let isolation : &str = row.try_get("isolation")?;
+        let read_only : &str = row.try_get("read_only")?;
+        assert_eq!(isolation, "repeatable read");
+        assert_eq!(read_only, "on");
+
+        // the transaction started by the method has ended
+        let row = db.query_one("SELECT current_setting('transaction_isolation')", &[])?;
+        let isolation : &str = row.try_get(0)?;
+        assert_eq!(isolation, "read committed");
+
+        let mut tr = db.transaction()?;
+        let err = tr.get_snapshot_mode().expect_err("transaction mode is checked");
+        assert_eq!(err.code(), Some(&SqlState::INVALID_TRANSACTION_STATE));
+        tr.rollback()?;
+
+        let mut tr = db.build_transaction().isolation_level(IsolationLevel::Serializable).read_only(true).start()?;
+        let row = tr.get_snapshot_mode()?;
+        let isolation : &str = row.try_get("isolation")?;
+        assert_eq!(isolation, "serializable");
+        tr.rollback()?;
+
+        Ok(())
+    }
+}
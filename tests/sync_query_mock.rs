@@ -0,0 +1,173 @@
+#[cfg(all(feature = "mock", not(feature = "tokio")))]
+mod test {
+    use std::convert::TryFrom;
+
+    use include_postgres_sql::{include_sql, MockRows};
+    use postgres::Row;
+    use postgres::{error::SqlState, types::Type};
+    use postgres::fallible_iterator::FallibleIterator;
+
+    macro_rules! impl_sql {
+        ( $($tt:tt)+ ) => {
+            include_postgres_sql::impl_sql!{ #[options(app_errors)] $($tt)+ }
+        };
+    }
+
+    include_sql!("tests/sql/query_mock.sql");
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum AppError {
+        Db(String),
+        NotFound(i32),
+    }
+
+    impl From<postgres::Error> for AppError {
+        fn from(err: postgres::Error) -> Self {
+            Self::Db(err.as_db_error().map_or_else(|| err.to_string(), |err| err.message().to_string()))
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Genre {
+        id: i32,
+        name: String,
+    }
+
+    impl TryFrom<Row> for Genre {
+        type Error = postgres::Error;
+
+        fn try_from(row: Row) -> Result<Self, Self::Error> {
+            Ok(Self { id: row.try_get("genre_id")?, name: row.try_get("name")? })
+        }
+    }
+
+    fn genres() -> MockRows {
+        MockRows::new(&[("genre_id", Type::INT4), ("name", Type::TEXT)])
+    }
+
+    /// Application code under test that accepts any implementation of the generated trait.
+    fn add_genre(db: &mut impl QueryMockSql, id: i32, name: &str) -> Result<Vec<Genre>, AppError> {
+        if db.new_genre(id, name)? == 0 {
+            return Err(AppError::NotFound(id));
+        }
+        db.get_genre_list(&[id])
+    }
+
+    #[test]
+    fn return_canned_results() {
+        let mut db = MockQueryMockSql::new();
+        db.expect_new_genre()
+            .with_values(&["42", "\"Polka\""])
+            .times(1)
+            .affects(1);
+        db.expect_get_genre_list()
+            .with(|params| params[0] == ("genre_ids", "[42]".to_string()))
+            .returns(genres().row(&[&42, &"Polka"]));
+        db.expect_new_genre()
+            .affects(0);
+
+        let genres = add_genre(&mut db, 42, "Polka").unwrap();
+        assert_eq!(genres, [Genre { id: 42, name: "Polka".into() }]);
+
+        let res = add_genre(&mut db, 43, "Ska");
+        assert_eq!(res, Err(AppError::NotFound(43)));
+
+        let calls = db.calls();
+        assert_eq!(calls.len(), 3);
+        assert_eq!(calls[0].name, "new_genre");
+        assert_eq!(calls[0].parameters, [("id", "42".to_string()), ("name", "\"Polka\"".to_string())]);
+        assert_eq!(calls[1].to_string(), "get_genre_list(genre_ids = [42])");
+        assert_eq!(calls[2].to_string(), "new_genre(id = 43, name = \"Ska\")");
+
+        db.checkpoint();
+    }
+
+    #[test]
+    fn fail_with_canned_errors() {
+        let mut db = MockQueryMockSql::new();
+        db.expect_new_genre().fails(SqlState::UNIQUE_VIOLATION, "duplicate key value violates unique constraint \"genre_pkey\"");
+        db.expect_get_genres().fails(SqlState::QUERY_CANCELED, "canceling statement due to statement timeout");
+        db.expect_get_genre_names().fails(SqlState::QUERY_CANCELED, "canceling statement due to statement timeout");
+        db.expect_get_work_mem().fails(SqlState::ADMIN_SHUTDOWN, "terminating connection due to administrator command");
+
+        let err = db.new_genre(1, "Rock").unwrap_err();
+        assert_eq!(err.code(), Some(&SqlState::UNIQUE_VIOLATION));
+        assert_eq!(err.as_db_error().unwrap().message(), "duplicate key value violates unique constraint \"genre_pkey\"");
+
+        let res = db.get_genres(1, |_| Ok::<_, AppError>(()));
+        assert_eq!(res, Err(AppError::Db("canceling statement due to statement timeout".into())));
+
+        let err = db.get_genre_names(1).err().unwrap();
+        assert_eq!(err.code(), Some(&SqlState::QUERY_CANCELED));
+
+        let err = db.get_work_mem().unwrap_err();
+        assert_eq!(err.code(), Some(&SqlState::ADMIN_SHUTDOWN));
+    }
+
+    #[test]
+    fn fail_with_application_errors() {
+        let mut db = MockQueryMockSql::new();
+        db.expect_get_genres().returns(genres().row(&[&1, &"Rock"]));
+        db.expect_get_genre_list().returns(MockRows::new(&[("genre_id", Type::INT4)]).row(&[&1]));
+
+        let res = db.get_genres(1, |row| Err(AppError::NotFound(row.get("genre_id"))));
+        assert_eq!(res, Err(AppError::NotFound(1)));
+
+        let res : Result<Vec<Genre>, AppError> = db.get_genre_list(&[1]);
+        assert!(matches!(res, Err(AppError::Db(msg)) if msg.contains("column") && msg.contains("name")), "unexpected result");
+    }
+
+    #[test]
+    fn return_canned_rows() {
+        let mut db = MockQueryMockSql::new();
+        db.expect_get_genres().returns(genres().row(&[&1, &"Rock"]).row(&[&2, &"Jazz"]));
+        db.expect_get_genre_names().returns(MockRows::new(&[("name", Type::TEXT)]).row(&[&"Rock"]).row(&[&"Jazz"]));
+        db.expect_get_work_mem().returns(MockRows::new(&[("work_mem", Type::TEXT)]).row(&[&"4MB"]));
+
+        let mut names = Vec::new();
+        db.get_genres(2, |row| { names.push(format!("{} {}", row.get::<_, i32>("genre_id"), row.get::<_, &str>("name"))); Ok::<_, AppError>(()) }).unwrap();
+        assert_eq!(names, ["1 Rock", "2 Jazz"]);
+
+        let rows = db.get_genre_names(2).unwrap();
+        let names : Vec<String> = rows.map(|row| Ok(row.get("name"))).collect().unwrap();
+        assert_eq!(names, ["Rock", "Jazz"]);
+
+        let row = db.get_work_mem().unwrap();
+        assert_eq!(row.get::<_, &str>("work_mem"), "4MB");
+    }
+
+    #[test]
+    fn process_no_rows() {
+        let mut db = MockQueryMockSql::new();
+        db.expect_get_genres();
+
+        let mut num_rows = 0;
+        db.get_genres(1, |_| { num_rows += 1; Ok::<_, AppError>(()) }).unwrap();
+        assert_eq!(num_rows, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected call QueryMockSql::new_genre(id = 1, name = \"Rock\")")]
+    fn panic_on_unexpected_call() {
+        let mut db = MockQueryMockSql::new();
+        db.expect_new_genre().with_values(&["2", "\"Rock\""]);
+        let _ = db.new_genre(1, "Rock");
+    }
+
+    #[test]
+    #[should_panic(expected = "QueryMockSql::new_genre was expected to be called 2 times, but was called 1 times")]
+    fn panic_on_unsatisfied_expectation() {
+        let mut db = MockQueryMockSql::new();
+        db.expect_new_genre().times(2);
+        let _ = db.new_genre(1, "Rock");
+        db.checkpoint();
+    }
+
+    #[test]
+    fn fail_one_row_calls_without_rows() {
+        let mut db = MockQueryMockSql::new();
+        db.expect_get_work_mem();
+        let err = db.get_work_mem().unwrap_err();
+        assert_eq!(err.to_string(), "query returned an unexpected number of rows");
+    }
+}
@@ -0,0 +1,49 @@
+#[cfg(not(feature = "tokio"))]
+mod test {
+    use include_postgres_sql::{include_sql, impl_sql};
+    use postgres::{Config, NoTls, Error};
+
+    include_sql!("tests/sql/query_opt_args.sql");
+
+    #[test]
+    fn impl_method_with_default_params() -> Result<(), Error> {
+        let mut db = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls)?
+        ;
+
+        let mut genres = Vec::new();
+        db.get_genres(None, |row| {
+            let name : String = row.try_get("name")?;
+            genres.push(name);
+            Ok(())
+        })?;
+        assert_eq!(genres, ["Rock", "Jazz", "Metal"]);
+
+        genres.clear();
+        db.get_genres(Some(1), |row| {
+            let name : String = row.try_get("name")?;
+            genres.push(name);
+            Ok(())
+        })?;
+        assert_eq!(genres, ["Rock"]);
+
+        let row = db.count_tracks(&[2, 3], None)?;
+        let num_tracks : i64 = row.try_get("num_tracks")?;
+        assert_eq!(num_tracks, 501);
+
+        let row = db.count_tracks(&[2, 3], Some("AAC audio file".to_string()))?;
+        let num_tracks : i64 = row.try_get("num_tracks")?;
+        assert_eq!(num_tracks, 3);
+
+        // The default is parsed when the argument is bound
+        let err = db.get_genre_names(None, |_| Ok(())).expect_err("unparsable default");
+        assert!(err.to_string().starts_with("error serializing parameter 0"), "{}", err);
+
+        db.get_genre_names(Some(1), |_| Ok(()))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+#[cfg(not(feature = "tokio"))]
+mod test {
+    use include_postgres_sql::{include_sql, ReconnectingClient};
+    use postgres::{Config, NoTls};
+
+    macro_rules! impl_sql {
+        ( $($tt:tt)+ ) => {
+            include_postgres_sql::impl_sql!{ #[options(reconnect)] $($tt)+ }
+        };
+    }
+
+    include_sql!("tests/sql/query_reconnect.sql");
+
+    fn config() -> Config {
+        let mut config = Config::new();
+        config
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook");
+        config
+    }
+
+    fn terminate(pid: i32) -> Result<(), postgres::Error> {
+        let mut admin = config().connect(NoTls)?;
+        admin.execute("SELECT pg_terminate_backend($1, 5000)", &[&pid])?;
+        Ok(())
+    }
+
+    fn backend_pid(db: &mut ReconnectingClient) -> Result<i32, postgres::Error> {
+        db.get_backend_pid()?.try_get("pid")
+    }
+
+    #[test]
+    fn impl_reconnecting_client() -> Result<(), postgres::Error> {
+        let mut db = ReconnectingClient::connect(config(), NoTls)?;
+        let pid = backend_pid(&mut db)?;
+
+        // idempotent statement is retried on a new connection
+        terminate(pid)?;
+        let new_pid = backend_pid(&mut db)?;
+        assert_ne!(new_pid, pid);
+
+        // non-idempotent statement is not retried...
+        let pid = backend_pid(&mut db)?;
+        db.terminate_session(pid).expect_err("statement is not retried");
+        // ...but the client reconnects before the next call
+        let num_rows = db.terminate_session(pid)?;
+        assert_eq!(num_rows, 0);
+        let new_pid = backend_pid(&mut db)?;
+        assert_ne!(new_pid, pid);
+
+        // idempotent statement is retried
+        let num_rows = db.terminate_session_idempotent(new_pid)?;
+        assert_eq!(num_rows, 0);
+        assert_ne!(backend_pid(&mut db)?, new_pid);
+
+        Ok(())
+    }
+}
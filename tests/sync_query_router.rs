@@ -0,0 +1,70 @@
+#[cfg(not(feature = "tokio"))]
+mod test {
+    use include_postgres_sql::{include_sql, Router};
+    use postgres::{Client, Config, NoTls, Row};
+    use std::convert::TryFrom;
+
+    macro_rules! impl_sql {
+        ( $($tt:tt)+ ) => {
+            include_postgres_sql::impl_sql!{ #[options(router)] $($tt)+ }
+        };
+    }
+
+    include_sql!("tests/sql/query_router.sql");
+
+    #[derive(Debug, PartialEq)]
+    struct ConnectionName(String);
+
+    impl TryFrom<Row> for ConnectionName {
+        type Error = postgres::Error;
+
+        fn try_from(row: Row) -> Result<Self, Self::Error> {
+            Ok(Self(row.try_get("name")?))
+        }
+    }
+
+    fn names(rows: &[ConnectionName]) -> Vec<&str> {
+        rows.iter().map(|ConnectionName(name)| name.as_str()).collect()
+    }
+
+    fn connect(name: &str) -> Result<Client, postgres::Error> {
+        Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .application_name(name)
+            .connect(NoTls)
+    }
+
+    #[test]
+    fn impl_router() -> Result<(), postgres::Error> {
+        let mut db = Router::new(connect("primary")?, vec![connect("replica1")?, connect("replica2")?]);
+
+        let row = db.get_connection_name()?;
+        let name : &str = row.try_get("name")?;
+        assert_eq!(name, "primary");
+
+        let row = db.get_replica_connection_name()?;
+        let name : &str = row.try_get("name")?;
+        assert_eq!(name, "replica1");
+
+        let rows : Vec<ConnectionName> = db.get_connection_names(&[1, 2])?;
+        assert_eq!(names(&rows), ["replica2", "replica2"]);
+
+        let rows : Vec<ConnectionName> = db.get_connection_names(&[3])?;
+        assert_eq!(names(&rows), ["replica1"]);
+
+        db.get_primary_connection_names(|row| {
+            let name : &str = row.try_get("name")?;
+            assert_eq!(name, "primary");
+            Ok(())
+        })?;
+
+        let mut tr = db.transaction()?;
+        let rows : Vec<ConnectionName> = tr.get_connection_names(&[1])?;
+        assert_eq!(names(&rows), ["primary"]);
+        tr.rollback()?;
+
+        Ok(())
+    }
+}
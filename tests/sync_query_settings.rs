@@ -0,0 +1,49 @@
+#[cfg(not(feature = "tokio"))]
+mod test {
+    use include_postgres_sql::{include_sql, impl_sql};
+    use postgres::{fallible_iterator::FallibleIterator, Config, NoTls, Error};
+
+    include_sql!("tests/sql/query_settings.sql");
+
+    #[test]
+    fn impl_method_with_settings() -> Result<(), Error> {
+        let mut db = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls)?
+        ;
+        // The value that the application set for the session outlives the calls
+        db.batch_execute("SET work_mem = '7MB'")?;
+
+        db.get_work_mem(|row| {
+            let work_mem : &str = row.try_get("work_mem")?;
+            assert_eq!(work_mem, "12MB");
+            Ok(())
+        })?;
+        let work_mem : String = db.query_one("SHOW work_mem", &[])?.try_get(0)?;
+        assert_eq!(work_mem, "7MB");
+
+        let mut tr = db.transaction()?;
+        tr.get_work_mem(|row| {
+            let work_mem : &str = row.try_get("work_mem")?;
+            assert_eq!(work_mem, "12MB");
+            Ok(())
+        })?;
+        let work_mem : String = tr.query_one("SHOW work_mem", &[])?.try_get(0)?;
+        assert_eq!(work_mem, "12MB");
+        tr.rollback()?;
+
+        // `db.stream_work_mem()` does not compile as it cannot end its own transaction after the rows are read
+        let mut tr = db.transaction()?;
+        let row = tr.stream_work_mem()?.next()?.expect("one row");
+        let work_mem : &str = row.try_get("work_mem")?;
+        assert_eq!(work_mem, "12MB");
+        tr.rollback()?;
+
+        let work_mem : String = db.query_one("SHOW work_mem", &[])?.try_get(0)?;
+        assert_eq!(work_mem, "7MB");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+#[cfg(not(feature = "tokio"))]
+mod test {
+    use include_postgres_sql::{impl_sql, include_sql, SlowQuery, SlowQueryLog};
+    use postgres::{Config, NoTls, Error};
+    use std::{sync::{Arc, Mutex}, time::Duration};
+
+    include_sql!("tests/sql/query_slow.sql");
+
+    /// Strips the sqlcommenter comment that the statement might be tagged with.
+    fn untagged(sql: &str) -> &str {
+        sql.split(" /* ").next().unwrap_or(sql)
+    }
+
+    #[test]
+    fn log_slow_queries() -> Result<(), Error> {
+        let mut db = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls)?
+        ;
+
+        let slow_queries : Arc<Mutex<Vec<SlowQuery>>> = Arc::default();
+        let log = {
+            let slow_queries = slow_queries.clone();
+            move |query: &SlowQuery| slow_queries.lock().unwrap().push(query.clone())
+        };
+        SlowQueryLog::new(Duration::from_millis(100), log.clone()).explain(true).install();
+
+        let row = db.count_genres(0.0, &[1, 2, 3])?;
+        let num_genres : i64 = row.try_get("num_genres")?;
+        assert_eq!(num_genres, 3);
+        assert!(slow_queries.lock().unwrap().is_empty());
+
+        let row = db.count_genres(0.2, &[1, 2, 3])?;
+        let num_genres : i64 = row.try_get("num_genres")?;
+        assert_eq!(num_genres, 3);
+
+        let query = slow_queries.lock().unwrap().pop().unwrap();
+        assert_eq!(query.sql_trait, "QuerySlowSql");
+        assert_eq!(query.name, "count_genres");
+        assert_eq!(untagged(&query.statement), "SELECT Count(*) AS num_genres\n  FROM genre, pg_sleep($1)\n WHERE genre_id IN ($2, $3, $4)");
+        assert_eq!(query.parameters, [("delay", "<redacted>".to_string()), ("ids", "<redacted>".to_string())]);
+        assert!(query.duration >= Duration::from_millis(200));
+        assert!(query.plan.unwrap().contains("\"Node Type\""));
+
+        // statements that EXPLAIN does not accept are not explained and do not abort the transaction
+        let mut tx = db.transaction()?;
+        let num_rows = tx.sleep_in_block()?;
+        assert_eq!(num_rows, 0);
+        let query = slow_queries.lock().unwrap().pop().unwrap();
+        assert_eq!(query.name, "sleep_in_block");
+        assert_eq!(query.plan, None);
+        let row = tx.count_genres(0.2, &[1, 2])?;
+        let num_genres : i64 = row.try_get("num_genres")?;
+        assert_eq!(num_genres, 2);
+        let query = slow_queries.lock().unwrap().pop().unwrap();
+        assert!(query.plan.unwrap().contains("\"Node Type\""));
+        tx.rollback()?;
+
+        SlowQueryLog::new(Duration::from_millis(100), log).show_parameter_values(true).install();
+
+        db.count_genres(0.2, &[4, 5])?;
+
+        let query = slow_queries.lock().unwrap().pop().unwrap();
+        assert_eq!(query.parameters, [("delay", "0.2".to_string()), ("ids", "[4, 5]".to_string())]);
+        assert_eq!(query.plan, None);
+
+        SlowQueryLog::uninstall();
+
+        db.count_genres(0.2, &[4, 5])?;
+        assert!(slow_queries.lock().unwrap().is_empty());
+
+        Ok(())
+    }
+}
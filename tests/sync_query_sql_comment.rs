@@ -0,0 +1,51 @@
+#[cfg(all(feature = "sqlcommenter", not(feature = "tokio")))]
+mod test {
+    use include_postgres_sql::{include_sql, impl_sql, set_trace_context, clear_trace_context};
+    use postgres::{Config, NoTls, Error};
+
+    include_sql!("tests/sql/query_sql_comment.sql");
+
+    #[test]
+    fn tag_statements() -> Result<(), Error> {
+        let mut db = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls)?
+        ;
+
+        let row = db.get_own_query()?;
+        let query : String = row.try_get("query")?;
+        assert_eq!(query, "SELECT query FROM pg_stat_activity WHERE pid = pg_backend_pid() /* name='get_own_query',trait='QuerySqlCommentSql',app='include-postgres-sql' */");
+
+        let row = db.get_own_query_with_genres(&[1, 2, 3])?;
+        let query : String = row.try_get("query")?;
+        let num_genres : i64 = row.try_get("num_genres")?;
+        assert_eq!(num_genres, 3);
+        assert!(query.ends_with("AND genre_id IN ($1, $2, $3)\n GROUP BY query /* name='get_own_query_with_genres',trait='QuerySqlCommentSql',app='include-postgres-sql' */"));
+
+        set_trace_context(|| Some("00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01".to_string()));
+
+        let row = db.get_own_query()?;
+        let query : String = row.try_get("query")?;
+        assert!(query.ends_with(" /* name='get_own_query',trait='QuerySqlCommentSql',app='include-postgres-sql',traceparent='00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01' */"));
+
+        let row = db.get_own_query_with_genres(&[4, 5])?;
+        let query : String = row.try_get("query")?;
+        assert!(query.ends_with(",app='include-postgres-sql',traceparent='00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01' */"));
+
+        set_trace_context(|| Some("*/ DROP TABLE genre; /*".to_string()));
+
+        let row = db.get_own_query()?;
+        let query : String = row.try_get("query")?;
+        assert!(query.ends_with(",traceparent='%2A%2F%20DROP%20TABLE%20genre%3B%20%2F%2A' */"));
+
+        clear_trace_context();
+
+        let row = db.get_own_query()?;
+        let query : String = row.try_get("query")?;
+        assert!(query.ends_with(",app='include-postgres-sql' */"));
+
+        Ok(())
+    }
+}
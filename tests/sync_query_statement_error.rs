@@ -0,0 +1,50 @@
+#[cfg(all(not(feature = "tokio"), not(feature = "sqlcommenter")))]
+mod test {
+    use include_postgres_sql::{include_sql, StatementError};
+    use postgres::{Config, NoTls, Error, error::SqlState};
+
+    macro_rules! impl_sql {
+        ( $($tt:tt)+ ) => {
+            include_postgres_sql::impl_sql!{ #[options(statement_error)] $($tt)+ }
+        };
+    }
+
+    include_sql!("tests/sql/query_statement_error.sql");
+
+    #[test]
+    fn describe_failed_statements() -> Result<(), Box<dyn std::error::Error>> {
+        let mut db = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls)?
+        ;
+
+        let err = db.get_missing_column(1).unwrap_err();
+        assert_eq!(err.sql_trait, "QueryStatementErrorSql");
+        assert_eq!(err.name, "get_missing_column");
+        assert_eq!(err.statement, "SELECT missing_column FROM genre WHERE genre_id = $1");
+        assert_eq!(err.parameters, [("genre_id", "<redacted>".to_string())]);
+        assert_eq!(err.code(), Some(&SqlState::UNDEFINED_COLUMN));
+        assert!(err.to_string().starts_with("QueryStatementErrorSql::get_missing_column failed: ERROR: column \"missing_column\" does not exist"));
+        let source = std::error::Error::source(&err).unwrap();
+        assert!(source.downcast_ref::<Error>().is_some());
+
+        StatementError::show_parameter_values(true);
+
+        let err = db.count_genres(0, &[1, 2, 3]).unwrap_err();
+        assert_eq!(err.name, "count_genres");
+        assert_eq!(err.statement, "SELECT Count(*) / $1 AS num_genres\n  FROM genre\n WHERE genre_id IN ($2, $3, $4)");
+        assert_eq!(err.parameters, [("divisor", "0".to_string()), ("ids", "[1, 2, 3]".to_string())]);
+        let err = err.into_driver_error().expect("driver error");
+        assert_eq!(err.code(), Some(&SqlState::DIVISION_BY_ZERO));
+
+        StatementError::show_parameter_values(false);
+
+        let row = db.count_genres(1, &[1, 2, 3])?;
+        let num_genres : i64 = row.try_get("num_genres")?;
+        assert_eq!(num_genres, 3);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+#[cfg(all(feature = "tracing", not(feature = "tokio")))]
+mod test {
+    use include_postgres_sql::{include_sql, impl_sql};
+    use postgres::{Config, NoTls, Error, fallible_iterator::FallibleIterator};
+    use std::{collections::HashMap, sync::{Arc, Mutex}};
+    use tracing::{field::{Field, Visit}, span, Event, Metadata, Subscriber};
+
+    include_sql!("tests/sql/query_tracing.sql");
+
+    /// Strips the sqlcommenter comment that the statement might be tagged with.
+    fn untagged(sql: &str) -> &str {
+        sql.split(" /* ").next().unwrap_or(sql)
+    }
+
+    type Fields = HashMap<&'static str, String>;
+
+    /// Collects names and recorded fields of all spans.
+    #[derive(Clone, Default)]
+    struct Spans(Arc<Mutex<Vec<(&'static str, Fields)>>>);
+
+    impl Spans {
+        fn take(&self) -> Vec<(&'static str, Fields)> {
+            std::mem::take(&mut *self.0.lock().unwrap())
+        }
+    }
+
+    struct Recorder<'a>(&'a mut Fields);
+
+    impl Visit for Recorder<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0.insert(field.name(), format!("{:?}", value));
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.insert(field.name(), value.to_string());
+        }
+    }
+
+    impl Subscriber for Spans {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, attrs: &span::Attributes<'_>) -> span::Id {
+            let mut fields = Fields::new();
+            attrs.record(&mut Recorder(&mut fields));
+            let mut spans = self.0.lock().unwrap();
+            spans.push((attrs.metadata().name(), fields));
+            span::Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, id: &span::Id, values: &span::Record<'_>) {
+            let mut spans = self.0.lock().unwrap();
+            let (_, fields) = &mut spans[id.into_u64() as usize - 1];
+            values.record(&mut Recorder(fields));
+        }
+
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+        fn event(&self, _: &Event<'_>) {}
+        fn enter(&self, _: &span::Id) {}
+        fn exit(&self, _: &span::Id) {}
+    }
+
+    #[test]
+    fn trace_calls() -> Result<(), Error> {
+        let mut db = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .dbname("chinook")
+            .connect(NoTls)?
+        ;
+
+        let spans = Spans::default();
+        tracing::subscriber::with_default(spans.clone(), || {
+            let mut genres = Vec::new();
+            db.get_genres(&[1, 3, 5], |row| {
+                let genre_id : i32 = row.try_get("genre_id")?;
+                genres.push(genre_id);
+                Ok(())
+            })?;
+            assert_eq!(genres, [1, 3, 5]);
+
+            let traced = spans.take();
+            assert_eq!(traced.len(), 1);
+            let (name, fields) = &traced[0];
+            assert_eq!(*name, "get_genres");
+            assert_eq!(fields["db.system"], "postgresql");
+            assert_eq!(untagged(&fields["db.statement"]), "SELECT genre_id, name\n  FROM genre\n WHERE genre_id IN ($1, $2, $3)\n ORDER BY genre_id");
+            assert_eq!(fields["db.operation"], "SELECT");
+            assert_eq!(fields["db.in_list_size"], "3");
+            assert_eq!(fields["db.rows"], "3");
+            assert!(!fields.contains_key("error"));
+
+            let row = db.count_genre_tracks(1)?;
+            let num_tracks : i64 = row.try_get("num_tracks")?;
+            assert_eq!(num_tracks, 1297);
+
+            let traced = spans.take();
+            let (name, fields) = &traced[0];
+            assert_eq!(*name, "count_genre_tracks");
+            assert_eq!(untagged(&fields["db.statement"]), "SELECT Count(*) AS num_tracks FROM track WHERE genre_id = $1");
+            assert_eq!(fields["db.rows"], "1");
+            assert!(!fields.contains_key("db.in_list_size"));
+
+            let res = db.divide_by_zero();
+            assert!(res.is_err());
+
+            let traced = spans.take();
+            let (name, fields) = &traced[0];
+            assert_eq!(*name, "divide_by_zero");
+            assert_eq!(fields["otel.status_code"], "ERROR");
+            assert!(fields["error"].contains("division by zero"));
+            assert!(!fields.contains_key("db.rows"));
+
+            let names : Vec<String> = db.get_genre_names()?.map(|row| row.try_get("name")).collect()?;
+            assert_eq!(names.len(), 25);
+
+            let traced = spans.take();
+            let (name, fields) = &traced[0];
+            assert_eq!(*name, "get_genre_names");
+            assert_eq!(untagged(&fields["db.statement"]), "SELECT name FROM genre ORDER BY genre_id");
+            assert_eq!(fields["db.rows"], "25");
+            assert!(!fields.contains_key("error"));
+
+            Ok(())
+        })
+    }
+}